@@ -0,0 +1,110 @@
+//! Benchmarks of the hot paths of the middleware, run with `cargo bench`
+//!
+//! They only depend on the standard library: each case is run for a fixed duration
+//! and reports the mean time per iteration. The `call` cases build a new request on every iteration,
+//! whose cost is reported by `request/build`.
+
+use std::{
+    convert::Infallible,
+    future::{self, Future},
+    hint::black_box,
+    pin::pin,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use http::{Method, Request, Response};
+use tower::{Layer, Service, service_fn};
+use tower_sec_fetch::{PathAuthorizer, Policy, SecFetchAuthorizer, SecFetchLayer, header};
+
+const DURATION: Duration = Duration::from_secs(1);
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    // warm up
+    for _ in 0..1_000 {
+        f();
+    }
+
+    let start = Instant::now();
+    let mut iterations = 0u64;
+    while start.elapsed() < DURATION {
+        for _ in 0..1_000 {
+            f();
+        }
+        iterations += 1_000;
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<40} {:>10.1} ns/iter",
+        elapsed.as_nanos() as f64 / iterations as f64
+    );
+}
+
+fn request(method: Method, path: &str, site: &'static str) -> Request<()> {
+    Request::builder()
+        .method(method)
+        .uri(path)
+        .header(header::SEC_FETCH_SITE, site)
+        .header(header::SEC_FETCH_MODE, "cors")
+        .header(header::SEC_FETCH_DEST, "empty")
+        .body(())
+        .unwrap()
+}
+
+/// Polls a future that is expected to be ready immediately
+fn now<F: Future>(future: F) -> F::Output {
+    let mut cx = Context::from_waker(Waker::noop());
+    match pin!(future).poll(&mut cx) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future is not ready"),
+    }
+}
+
+fn main() {
+    let same_origin = request(Method::POST, "/submit", "same-origin");
+    let cross_site = request(Method::POST, "/submit", "cross-site");
+
+    let policy = Policy::default();
+    bench("policy/evaluate/allow", || {
+        black_box(policy.evaluate(black_box(&same_origin)));
+    });
+    bench("policy/evaluate/deny", || {
+        black_box(policy.evaluate(black_box(&cross_site)));
+    });
+
+    let authorizer = PathAuthorizer::new(["/webhooks/*", "/api/{v}/callbacks", "/public/**"]);
+    let webhook = request(Method::POST, "/webhooks/github", "cross-site");
+    bench("authorizer/path/match", || {
+        black_box(authorizer.authorize(black_box(&webhook)));
+    });
+    bench("authorizer/path/miss", || {
+        black_box(authorizer.authorize(black_box(&cross_site)));
+    });
+
+    let mut service = SecFetchLayer::default().layer(service_fn(|_: Request<()>| {
+        future::ready(Ok::<_, Infallible>(Response::new(())))
+    }));
+    bench("call/allow", || {
+        let request = request(Method::POST, "/submit", "same-origin");
+        black_box(now(service.call(black_box(request)))).ok();
+    });
+    bench("call/deny", || {
+        let request = request(Method::POST, "/submit", "cross-site");
+        black_box(now(service.call(black_box(request)))).ok();
+    });
+
+    let mut service = SecFetchLayer::default()
+        .problem_json()
+        .layer(service_fn(|_: Request<()>| {
+            future::ready(Ok::<_, Infallible>(Response::new(String::new())))
+        }));
+    bench("call/deny/problem-json", || {
+        let request = request(Method::POST, "/submit", "cross-site");
+        black_box(now(service.call(black_box(request)))).ok();
+    });
+
+    bench("request/build", || {
+        black_box(request(Method::POST, "/submit", "same-origin"));
+    });
+}
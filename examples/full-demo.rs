@@ -0,0 +1,283 @@
+//! A complete setup wiring every integration point together
+//!
+//! - the strict evaluation policy, extended with rules loaded from the `SEC_FETCH_RULES` JSON file,
+//!   and reloaded whenever the file changes
+//! - `application/problem+json` bodies for denied requests
+//! - denied requests counted by reason and severity, exposed in the Prometheus text format on `/metrics`
+//! - the denial counters of the [StatsCollector] exposed on `/admin/stats`
+//!
+//! `/metrics` and `/admin/stats` are not authenticated, so that the demo stays short:
+//! in production, serve them on an internal listener or behind an authentication layer.
+//!
+//! Run it with `cargo run --example full-demo --features full-demo`
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use axum::{Router, routing::get};
+use tokio::net::TcpListener;
+use tower_sec_fetch::{
+    DenialReason, Policy, RuleSet, SecFetchLayer, SecFetchReporter, Severities, Severity,
+    SeverityRule, StatsCollector,
+};
+
+const DEFAULT_RULES: &str = r#"[
+    { "path": "/webhooks/**", "methods": ["POST"], "action": "allow" }
+]"#;
+
+/// The rules extending the policy, replaced while the server is running
+#[derive(Clone)]
+struct Rules(Arc<RwLock<RuleSet>>);
+
+impl Rules {
+    fn new(rules: RuleSet) -> Self {
+        Self(Arc::new(RwLock::new(rules)))
+    }
+
+    fn replace(&self, rules: RuleSet) {
+        *self.0.write().expect("rules lock poisoned") = rules;
+    }
+
+    /// Reloads the rules when the file is modified, keeping the current ones if it cannot be parsed
+    async fn watch(self, path: PathBuf) {
+        let mut modified = SystemTime::UNIX_EPOCH;
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+
+        loop {
+            interval.tick().await;
+
+            let Ok(mtime) = tokio::fs::metadata(&path)
+                .await
+                .and_then(|metadata| metadata.modified())
+            else {
+                continue;
+            };
+            if mtime == modified {
+                continue;
+            }
+            modified = mtime;
+
+            match tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|json| RuleSet::from_json(&json).map_err(|err| err.to_string()))
+            {
+                Ok(rules) => {
+                    self.replace(rules);
+                    eprintln!("reloaded rules from {}", path.display());
+                }
+                Err(err) => eprintln!("keeping the current rules: {err}"),
+            }
+        }
+    }
+}
+
+/// Counts denied requests by reason and severity
+#[derive(Clone, Default)]
+struct Metrics(Arc<Mutex<BTreeMap<(&'static str, &'static str), u64>>>);
+
+impl Metrics {
+    fn render(&self) -> String {
+        let mut body = String::from(
+            "# HELP sec_fetch_denied_total Requests denied by the Fetch Metadata policy\n\
+             # TYPE sec_fetch_denied_total counter\n",
+        );
+
+        for ((reason, severity), count) in self.0.lock().expect("metrics lock poisoned").iter() {
+            writeln!(
+                body,
+                "sec_fetch_denied_total{{reason=\"{reason}\",severity=\"{severity}\"}} {count}"
+            )
+            .expect("writing to a string");
+        }
+
+        body
+    }
+}
+
+impl SecFetchReporter for Metrics {
+    fn on_request_denied<B>(&self, request: &http::Request<B>) {
+        let reason = request
+            .extensions()
+            .get::<DenialReason>()
+            .map_or("unknown", DenialReason::as_str);
+        let severity = request
+            .extensions()
+            .get::<Severity>()
+            .map_or("unknown", Severity::as_str);
+
+        *self
+            .0
+            .lock()
+            .expect("metrics lock poisoned")
+            .entry((reason, severity))
+            .or_default() += 1;
+    }
+}
+
+/// Forwards denied requests to both the metrics and the stats
+struct DemoReporter {
+    metrics: Metrics,
+    stats: StatsCollector,
+}
+
+impl SecFetchReporter for DemoReporter {
+    fn on_request_denied<B>(&self, request: &http::Request<B>) {
+        self.metrics.on_request_denied(request);
+        self.stats.on_request_denied(request);
+    }
+
+    fn on_request_evaluated<B>(&self, request: &http::Request<B>) {
+        self.stats.on_request_evaluated(request);
+    }
+}
+
+fn app(rules: Rules) -> Router {
+    let metrics = Metrics::default();
+    let stats = StatsCollector::default();
+
+    let policy = Policy::strict().extend(|policy| {
+        policy.custom(move |metadata, request| {
+            rules
+                .0
+                .read()
+                .expect("rules lock poisoned")
+                .decide(metadata, request)
+        });
+    });
+
+    let severities = Severities::default()
+        .rule(SeverityRule::new(Severity::Info).reason(DenialReason::MissingMetadata))
+        .rule(SeverityRule::new(Severity::Critical).path("/admin/**"));
+
+    let layer = SecFetchLayer::default()
+        .with_policy(policy)
+        .problem_json()
+        .severities(severities)
+        .with_reporter(DemoReporter {
+            metrics: metrics.clone(),
+            stats: stats.clone(),
+        });
+
+    Router::new()
+        .route("/hello", get(async || "hello"))
+        .route("/webhooks/github", axum::routing::post(async || "received"))
+        .route("/admin/users", axum::routing::delete(async || "deleted"))
+        .layer(layer)
+        // unauthenticated, see the module documentation
+        .route("/metrics", get(async move || metrics.render()))
+        .route_service("/admin/stats", stats.service())
+}
+
+#[tokio::main]
+async fn main() {
+    let path = std::env::var_os("SEC_FETCH_RULES").map(PathBuf::from);
+    let json = match &path {
+        Some(path) => std::fs::read_to_string(path).expect("readable rules file"),
+        None => DEFAULT_RULES.to_string(),
+    };
+    let rules = Rules::new(RuleSet::from_json(&json).expect("valid rules"));
+    if let Some(path) = path {
+        tokio::spawn(rules.clone().watch(path));
+    }
+
+    let listener = TcpListener::bind("[::1]:3000").await.unwrap();
+
+    eprintln!("listening on http://localhost:3000");
+    axum::serve(listener, app(rules)).await.unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use axum::body::{Body, to_bytes};
+    use http::{Method, StatusCode};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(
+        app: &Router,
+        method: Method,
+        path: &str,
+        site: Option<&str>,
+    ) -> (StatusCode, String) {
+        let mut request = http::Request::builder().method(method).uri(path);
+        if let Some(site) = site {
+            request = request
+                .header("sec-fetch-site", site)
+                .header("sec-fetch-mode", "cors")
+                .header("sec-fetch-dest", "empty");
+        }
+
+        let response = app
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn it_composes_every_integration_point() {
+        let app = app(Rules::new(RuleSet::from_json(DEFAULT_RULES).unwrap()));
+
+        let (status, _) = send(&app, Method::GET, "/hello", Some("same-origin")).await;
+        check!(status == StatusCode::OK);
+
+        let (status, body) = send(&app, Method::GET, "/hello", Some("same-site")).await;
+        check!(status == StatusCode::FORBIDDEN);
+        check!(body.contains("cross-site"));
+
+        let (status, _) = send(&app, Method::GET, "/hello", None).await;
+        check!(status == StatusCode::FORBIDDEN);
+
+        let (status, _) = send(&app, Method::DELETE, "/admin/users", Some("cross-site")).await;
+        check!(status == StatusCode::FORBIDDEN);
+
+        let (status, _) = send(&app, Method::POST, "/webhooks/github", Some("cross-site")).await;
+        check!(status == StatusCode::OK);
+
+        let (status, metrics) = send(&app, Method::GET, "/metrics", None).await;
+        check!(status == StatusCode::OK);
+        check!(
+            metrics.contains(r#"sec_fetch_denied_total{reason="cross-site",severity="warn"} 1"#)
+        );
+        check!(
+            metrics
+                .contains(r#"sec_fetch_denied_total{reason="cross-site",severity="critical"} 1"#)
+        );
+        check!(
+            metrics
+                .contains(r#"sec_fetch_denied_total{reason="missing-metadata",severity="info"} 1"#)
+        );
+
+        let (status, stats) = send(&app, Method::GET, "/admin/stats", None).await;
+        check!(status == StatusCode::OK);
+        check!(stats.contains(r#""denied":3"#));
+        check!(stats.contains(r#""evaluated":5"#));
+        check!(stats.contains(r#""missing_metadata":1"#));
+    }
+
+    #[tokio::test]
+    async fn it_reloads_the_rules() {
+        let rules = Rules::new(RuleSet::from_json(DEFAULT_RULES).unwrap());
+        let app = app(rules.clone());
+
+        let (status, _) = send(&app, Method::POST, "/webhooks/github", Some("cross-site")).await;
+        check!(status == StatusCode::OK);
+
+        rules.replace(RuleSet::default());
+
+        let (status, _) = send(&app, Method::POST, "/webhooks/github", Some("cross-site")).await;
+        check!(status == StatusCode::FORBIDDEN);
+    }
+}
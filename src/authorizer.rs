@@ -1,9 +1,30 @@
-use std::{ops::Deref, sync::Arc};
+use std::{borrow::Cow, marker::PhantomData, ops::Deref, sync::Arc};
+
+use http::{HeaderName, HeaderValue, Method, request::Parts};
+
+use crate::{
+    header::{NAVIGATE, SEC_FETCH_MODE},
+    policy::{path_matches, request_host},
+    rule::{self, Sealed},
+};
 
 /// Custom request authorization logic
 pub trait SecFetchAuthorizer {
     /// Authorizes the current request
     fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision;
+
+    /// Authorizes a request owned by the middleware, so that [BoxAuthorizer] can erase its body type without copying its head
+    ///
+    /// Sealed, so that only [BoxAuthorizer] can borrow the request mutably.
+    #[doc(hidden)]
+    fn authorize_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) -> AuthorizationDecision {
+        self.authorize(request)
+    }
+
+    /// Number of exemptions configured on this authorizer, if known
+    fn exemptions(&self) -> Option<usize> {
+        None
+    }
 }
 
 #[doc(hidden)]
@@ -13,6 +34,10 @@ impl SecFetchAuthorizer for NoopAuthorizer {
     fn authorize<B>(&self, _: &http::Request<B>) -> AuthorizationDecision {
         AuthorizationDecision::Continue
     }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(0)
+    }
 }
 
 /// The decision made by a [SecFetchAuthorizer]
@@ -36,23 +61,683 @@ where
     fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
         self.deref().authorize(request)
     }
+
+    fn authorize_mut<B>(
+        &self,
+        request: &mut http::Request<B>,
+        sealed: Sealed,
+    ) -> AuthorizationDecision {
+        self.deref().authorize_mut(request, sealed)
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        self.deref().exemptions()
+    }
 }
 
 /// A [SecFetchAuthorizer] that allows requests based on their path
-pub struct PathAuthorizer(Arc<[&'static str]>);
+///
+/// Paths can be patterns, where `*` matches a single path segment, `{v}` matches an API version segment
+/// such as `v1` or `v12`, and a trailing `**` matches any number of segments.
+/// For example, `/api/{v}/webhooks` matches `/api/v1/webhooks` and `/api/v2/webhooks`.
+///
+/// Paths with dynamic suffixes that are not whole segments, such as `/webhooks/github-1234`,
+/// can be allowed by prefix with [with_prefixes](PathAuthorizer::with_prefixes).
+///
+/// Both static and owned strings are accepted, so paths can be loaded from runtime configuration.
+///
+/// ```
+/// use tower_sec_fetch::PathAuthorizer;
+///
+/// let configured = vec![String::from("/webhooks/**")];
+///
+/// PathAuthorizer::new(configured).with_prefixes(["/callbacks/"]);
+/// ```
+///
+/// Paths are matched as they are received by default. With [normalize_paths](PathAuthorizer::normalize_paths),
+/// they are normalized before matching, so that requests such as `/static/%2e%2e/admin` or `//admin`
+/// cannot be matched differently than the router sees them.
+/// With [ignore_trailing_slash](PathAuthorizer::ignore_trailing_slash), `/unprotected` and `/unprotected/` are the same path.
+#[derive(Clone, Default)]
+pub struct PathAuthorizer {
+    paths: Arc<[Cow<'static, str>]>,
+    prefixes: Arc<[Cow<'static, str>]>,
+    normalize: bool,
+    ignore_trailing_slash: bool,
+}
 
 impl PathAuthorizer {
-    pub fn new(allowed_paths: impl Into<Arc<[&'static str]>>) -> Self {
-        Self(allowed_paths.into())
+    pub fn new<P>(allowed_paths: impl IntoIterator<Item = P>) -> Self
+    where
+        P: Into<Cow<'static, str>>,
+    {
+        Self {
+            paths: allowed_paths.into_iter().map(Into::into).collect(),
+            prefixes: Arc::new([]),
+            normalize: false,
+            ignore_trailing_slash: false,
+        }
+    }
+
+    /// Percent-decodes the path, collapses repeated slashes, and resolves `.` and `..` segments before matching
+    ///
+    /// Encoded slashes (`%2F`) are not decoded, as they do not separate path segments.
+    pub fn normalize_paths(mut self) -> Self {
+        self.normalize = true;
+        self
+    }
+
+    /// Matches paths regardless of a trailing slash, both in the request and in the allowed paths
+    pub fn ignore_trailing_slash(mut self) -> Self {
+        self.ignore_trailing_slash = true;
+        self
+    }
+
+    fn trim<'a>(&self, path: &'a str) -> &'a str {
+        match path.strip_suffix('/') {
+            Some(trimmed) if self.ignore_trailing_slash && !trimmed.is_empty() => trimmed,
+            _ => path,
+        }
+    }
+
+    /// Allows the `/.well-known/**` paths, such as ACME `http-01` challenges, `security.txt`, or OpenID Connect discovery,
+    /// which are fetched by clients that do not send Fetch Metadata headers
+    pub fn well_known() -> Self {
+        Self::new(["/.well-known/**"])
+    }
+
+    /// Also allows requests whose path starts with any of `prefixes`
+    pub fn with_prefixes<P>(mut self, prefixes: impl IntoIterator<Item = P>) -> Self
+    where
+        P: Into<Cow<'static, str>>,
+    {
+        self.prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
     }
 }
 
 impl SecFetchAuthorizer for PathAuthorizer {
     fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
-        if self.0.contains(&request.uri().path()) {
+        let path = request.uri().path();
+        let path = if self.normalize {
+            normalize_path(path)
+        } else {
+            Cow::Borrowed(path)
+        };
+
+        if self
+            .paths
+            .iter()
+            .any(|pattern| path_matches(self.trim(pattern), self.trim(&path)))
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_ref()))
+        {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(self.paths.len() + self.prefixes.len())
+    }
+}
+
+/// A [SecFetchAuthorizer] that allows requests based on both their host and their path
+///
+/// Hosts are matched case-insensitively and regardless of the port, and a leading `*.` matches any subdomain.
+/// Paths are patterns with the same syntax as [PathAuthorizer].
+///
+/// ```
+/// use tower_sec_fetch::{HostPathAuthorizer, SecFetchLayer};
+///
+/// // webhooks are exempted on the API host only, the app host stays protected
+/// let authorizer = HostPathAuthorizer::new([
+///     ("api.example.com", "/webhooks/*"),
+///     ("*.tenants.example.com", "/webhooks/**"),
+/// ]);
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+#[derive(Clone, Default)]
+pub struct HostPathAuthorizer(Arc<[(Cow<'static, str>, Cow<'static, str>)]>);
+
+impl HostPathAuthorizer {
+    pub fn new<H, P>(allowed: impl IntoIterator<Item = (H, P)>) -> Self
+    where
+        H: Into<Cow<'static, str>>,
+        P: Into<Cow<'static, str>>,
+    {
+        Self(
+            allowed
+                .into_iter()
+                .map(|(host, path)| (host.into(), path.into()))
+                .collect(),
+        )
+    }
+}
+
+impl SecFetchAuthorizer for HostPathAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let Some(host) = request_hostname(request) else {
+            return AuthorizationDecision::Continue;
+        };
+        let path = request.uri().path();
+
+        if self.0.iter().any(|(pattern, path_pattern)| {
+            host_matches(pattern, host) && path_matches(path_pattern, path)
+        }) {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// The host of the request without its port
+pub(crate) fn request_hostname<B>(request: &http::Request<B>) -> Option<&str> {
+    let host = request_host(request)?;
+
+    Some(
+        host.rsplit_once(':')
+            .filter(|(_, port)| port.bytes().all(|digit| digit.is_ascii_digit()))
+            .map_or(host, |(host, _)| host),
+    )
+}
+
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.len().checked_sub(domain.len() + 1).is_some_and(|dot| {
+            host.as_bytes()[dot] == b'.' && host[dot + 1..].eq_ignore_ascii_case(domain)
+        }),
+        None => pattern.eq_ignore_ascii_case(host),
+    }
+}
+
+/// A [SecFetchAuthorizer] that allows requests carrying a header, optionally with an expected value
+///
+/// This is meant for traffic forwarded by a trusted edge proxy, which must strip the header from client requests.
+///
+/// ```
+/// use tower_sec_fetch::{HeaderAuthorizer, SecFetchLayer};
+///
+/// let authorizer = HeaderAuthorizer::new("x-internal-request").value("1");
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+#[derive(Clone)]
+pub struct HeaderAuthorizer {
+    name: HeaderName,
+    value: Option<HeaderValue>,
+}
+
+impl HeaderAuthorizer {
+    /// Allows requests carrying the `name` header with any value
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: HeaderName::from_static(name),
+            value: None,
+        }
+    }
+
+    /// Only allows requests where the header has the `value` value
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    pub fn value(mut self, value: &'static str) -> Self {
+        self.value = Some(HeaderValue::from_static(value));
+        self
+    }
+}
+
+impl SecFetchAuthorizer for HeaderAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let allowed = request
+            .headers()
+            .get_all(&self.name)
+            .iter()
+            .any(|value| self.value.as_ref().is_none_or(|expected| value == expected));
+
+        if allowed {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+/// A [SecFetchAuthorizer] that allows requests authenticated with an `Authorization: Bearer` token
+///
+/// Browsers never attach bearer tokens automatically, so these requests cannot be forged cross-site.
+/// The server must authenticate them with the token only, and never fall back to cookies.
+///
+/// ```
+/// use tower_sec_fetch::{BearerAuthorizer, SecFetchLayer};
+///
+/// SecFetchLayer::default().with_authorizer(BearerAuthorizer);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BearerAuthorizer;
+
+impl SecFetchAuthorizer for BearerAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let bearer = request
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split_once(' '))
+            .is_some_and(|(scheme, token)| {
+                scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty()
+            });
+
+        if bearer {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+}
+
+/// A verified TLS client certificate, in DER format
+///
+/// Insert it in the request extensions from the TLS accept hook of the server, after verifying the certificate,
+/// to exempt the request with a [ClientCertAuthorizer].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedClientCertificate(pub Vec<u8>);
+
+/// A [SecFetchAuthorizer] that allows requests authenticated with a TLS client certificate
+///
+/// Machine-to-machine callers using mutual TLS never send Fetch Metadata headers.
+/// Requests are allowed when their extensions contain a `T`, which defaults to [VerifiedClientCertificate],
+/// so that servers already storing the certificate in their own type can use it directly.
+///
+/// ```
+/// use tower_sec_fetch::{ClientCertAuthorizer, SecFetchLayer};
+///
+/// #[derive(Clone)]
+/// struct PeerCertificates(Vec<Vec<u8>>);
+///
+/// SecFetchLayer::default().with_authorizer(ClientCertAuthorizer::<PeerCertificates>::new());
+/// ```
+pub struct ClientCertAuthorizer<T = VerifiedClientCertificate>(PhantomData<fn() -> T>);
+
+impl<T> ClientCertAuthorizer<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for ClientCertAuthorizer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ClientCertAuthorizer<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Send + Sync + 'static> SecFetchAuthorizer for ClientCertAuthorizer<T> {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        if request.extensions().get::<T>().is_some() {
             return AuthorizationDecision::Allowed;
         }
 
         AuthorizationDecision::Continue
     }
 }
+
+/// A [SecFetchAuthorizer] that allows gRPC and gRPC-Web requests, recognized by their `application/grpc*` content type
+///
+/// gRPC clients never send Fetch Metadata headers, and browsers cannot send these content types
+/// cross-site without a CORS preflight, so these requests cannot be forged by another site.
+///
+/// ```
+/// use tower_sec_fetch::{GrpcAuthorizer, SecFetchLayer};
+///
+/// SecFetchLayer::new(|policy| {
+///     policy.reject_missing_metadata();
+/// })
+/// .with_authorizer(GrpcAuthorizer);
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GrpcAuthorizer;
+
+impl SecFetchAuthorizer for GrpcAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let grpc = request
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.get(.."application/grpc".len()))
+            .is_some_and(|value| value.eq_ignore_ascii_case("application/grpc"));
+
+        if grpc {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+}
+
+/// A [SecFetchAuthorizer] that allows identity-provider redirects to OAuth 2.0 and OpenID Connect callback paths
+///
+/// Identity providers return to the application with a cross-site navigation, which is a `GET` request
+/// or, with the `form_post` response mode, a `POST` form submission. Both are allowed on the callback paths,
+/// while other requests to them are deferred to the evaluation policy. Paths are patterns as in [PathAuthorizer].
+///
+/// With [require_state](OAuthCallbackAuthorizer::require_state), `GET` callbacks missing the `state` query
+/// parameter are denied. The parameters of `form_post` callbacks are in the body, which is not available
+/// to authorizers, so the application must still validate `state` itself.
+///
+/// ```
+/// use tower_sec_fetch::{OAuthCallbackAuthorizer, SecFetchLayer};
+///
+/// let authorizer = OAuthCallbackAuthorizer::new(["/auth/callback", "/auth/*/callback"]).require_state();
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+#[derive(Clone)]
+pub struct OAuthCallbackAuthorizer {
+    paths: Arc<[Cow<'static, str>]>,
+    require_state: bool,
+}
+
+impl OAuthCallbackAuthorizer {
+    /// Allows callbacks to any of `paths`
+    pub fn new<P>(paths: impl IntoIterator<Item = P>) -> Self
+    where
+        P: Into<Cow<'static, str>>,
+    {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            require_state: false,
+        }
+    }
+
+    /// Denies `GET` callbacks without a `state` query parameter
+    pub fn require_state(mut self) -> Self {
+        self.require_state = true;
+        self
+    }
+}
+
+impl SecFetchAuthorizer for OAuthCallbackAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let callback = self
+            .paths
+            .iter()
+            .any(|pattern| path_matches(pattern, request.uri().path()));
+        let navigation = request
+            .headers()
+            .get(&SEC_FETCH_MODE)
+            .is_none_or(|mode| mode == NAVIGATE);
+
+        if !callback || !navigation {
+            return AuthorizationDecision::Continue;
+        }
+
+        match *request.method() {
+            Method::GET if self.require_state && !has_state(request) => {
+                AuthorizationDecision::Denied
+            }
+            Method::GET | Method::POST => AuthorizationDecision::Allowed,
+            _ => AuthorizationDecision::Continue,
+        }
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(self.paths.len())
+    }
+}
+
+/// A [SecFetchAuthorizer] that exempts webhook endpoints, optionally verifying their signature first
+///
+/// Webhook deliveries are server-to-server requests without Fetch Metadata headers. Exempting their paths
+/// should not make them unauthenticated, so a [verify](WebhookAuthorizer::verify) callback can check the
+/// signature headers of the provider, denying the requests it rejects. Paths are patterns as in [PathAuthorizer].
+///
+/// The callback receives the head of the request, as authorizers run before the body is read. Signatures computed
+/// over the body, such as GitHub's `X-Hub-Signature-256`, can be checked by a previous layer buffering the body
+/// into a request extension, or must be checked again by the handler.
+///
+/// ```
+/// use tower_sec_fetch::{SecFetchLayer, WebhookAuthorizer};
+///
+/// let authorizer = WebhookAuthorizer::new(["/webhooks/github"])
+///     .verify(|request| request.headers.contains_key("x-hub-signature-256"));
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+#[derive(Clone)]
+pub struct WebhookAuthorizer {
+    paths: Arc<[Cow<'static, str>]>,
+    verifier: Option<Verifier>,
+}
+
+type Verifier = Arc<dyn Fn(&Parts) -> bool + Send + Sync>;
+
+impl WebhookAuthorizer {
+    /// Exempts requests to any of `paths` without verification
+    pub fn new<P>(paths: impl IntoIterator<Item = P>) -> Self
+    where
+        P: Into<Cow<'static, str>>,
+    {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            verifier: None,
+        }
+    }
+
+    /// Only exempts requests for which `verifier` returns `true`, denying the others
+    pub fn verify<F>(mut self, verifier: F) -> Self
+    where
+        F: Fn(&Parts) -> bool + Send + Sync + 'static,
+    {
+        self.verifier = Some(Arc::new(verifier));
+        self
+    }
+}
+
+impl SecFetchAuthorizer for WebhookAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let webhook = self
+            .paths
+            .iter()
+            .any(|pattern| path_matches(pattern, request.uri().path()));
+
+        if !webhook {
+            return AuthorizationDecision::Continue;
+        }
+
+        match &self.verifier {
+            Some(verifier) if !verifier(&rule::parts(request)) => AuthorizationDecision::Denied,
+            _ => AuthorizationDecision::Allowed,
+        }
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(self.paths.len())
+    }
+}
+
+fn has_state<B>(request: &http::Request<B>) -> bool {
+    request.uri().query().is_some_and(|query| {
+        query.split('&').any(|pair| {
+            pair.split_once('=')
+                .is_some_and(|(name, value)| name == "state" && !value.is_empty())
+        })
+    })
+}
+
+/// Percent-decodes `path` except for encoded slashes, collapses repeated slashes, and resolves dot segments
+pub(crate) fn normalize_path(path: &str) -> Cow<'_, str> {
+    let decoded = percent_decode(path);
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in decoded.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut normalized = String::with_capacity(decoded.len());
+    for segment in &segments {
+        normalized.push('/');
+        normalized.push_str(segment);
+    }
+    if normalized.is_empty() || (decoded.ends_with('/') && !segments.is_empty()) {
+        normalized.push('/');
+    }
+
+    if normalized == path {
+        Cow::Borrowed(path)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+fn percent_decode(path: &str) -> Cow<'_, str> {
+    if !path.contains('%') {
+        return Cow::Borrowed(path);
+    }
+
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                let byte = (high << 4) | low;
+                if byte != b'/' {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    match String::from_utf8(decoded) {
+        Ok(decoded) => Cow::Owned(decoded),
+        Err(_) => Cow::Borrowed(path),
+    }
+}
+
+/// A type-erased [SecFetchAuthorizer], so that authorizers can be chosen at runtime
+///
+/// [SecFetchAuthorizer::authorize] is generic over the request body, so the trait cannot be used as `dyn SecFetchAuthorizer`.
+/// The middleware lends the head of the request to the inner authorizer without copying it,
+/// while calling [authorize](SecFetchAuthorizer::authorize) directly copies the head to erase its body type.
+///
+/// ```
+/// use tower_sec_fetch::{BoxAuthorizer, NoopAuthorizer, PathAuthorizer, SecFetchLayer};
+///
+/// let webhooks_enabled = true;
+/// let authorizer = if webhooks_enabled {
+///     BoxAuthorizer::new(PathAuthorizer::new(["/webhooks"]))
+/// } else {
+///     BoxAuthorizer::new(NoopAuthorizer)
+/// };
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+pub struct BoxAuthorizer(Box<dyn ErasedAuthorizer + Send + Sync>);
+
+impl BoxAuthorizer {
+    pub fn new<A>(authorizer: A) -> Self
+    where
+        A: SecFetchAuthorizer + Send + Sync + 'static,
+    {
+        Self(Box::new(authorizer))
+    }
+}
+
+impl SecFetchAuthorizer for BoxAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        self.0
+            .authorize_erased(&mut http::Request::from_parts(rule::parts(request), ()))
+    }
+
+    fn authorize_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) -> AuthorizationDecision {
+        rule::with_head(request, |head| self.0.authorize_erased(head))
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        self.0.exemptions_erased()
+    }
+}
+
+trait ErasedAuthorizer {
+    fn authorize_erased(&self, request: &mut http::Request<()>) -> AuthorizationDecision;
+
+    fn exemptions_erased(&self) -> Option<usize>;
+}
+
+impl<A: SecFetchAuthorizer> ErasedAuthorizer for A {
+    fn authorize_erased(&self, request: &mut http::Request<()>) -> AuthorizationDecision {
+        self.authorize_mut(request, Sealed)
+    }
+
+    fn exemptions_erased(&self) -> Option<usize> {
+        self.exemptions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_normalizes_paths() {
+        check!(normalize_path("/unprotected") == "/unprotected");
+        check!(normalize_path("/unprotected/") == "/unprotected/");
+        check!(normalize_path("//unprotected") == "/unprotected");
+        check!(normalize_path("/static/./../unprotected") == "/unprotected");
+        check!(normalize_path("/static/%2e%2e/unprotected") == "/unprotected");
+        check!(normalize_path("/%2E%2E/%2e%2e/unprotected") == "/unprotected");
+        check!(normalize_path("/files/a%2Fb") == "/files/a%2Fb");
+        check!(normalize_path("/files/%ff") == "/files/%ff");
+        check!(normalize_path("/..") == "/");
+        check!(normalize_path("") == "/");
+    }
+
+    #[test]
+    fn it_matches_hosts() {
+        check!(host_matches("api.example.com", "api.example.com"));
+        check!(host_matches("api.example.com", "API.example.com"));
+        check!(!host_matches("api.example.com", "app.example.com"));
+        check!(host_matches("*.example.com", "acme.example.com"));
+        check!(host_matches("*.example.com", "a.b.example.com"));
+        check!(!host_matches("*.example.com", "example.com"));
+        check!(!host_matches("*.example.com", "badexample.com"));
+    }
+}
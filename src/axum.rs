@@ -0,0 +1,137 @@
+use axum_core::{
+    body::Body,
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, HeaderValue, StatusCode, header::CONTENT_TYPE, request::Parts};
+
+use crate::{
+    DenialContext, DenialId, DenialReason, DeniedRequest, SecFetchResponder, SecFetchVerified,
+    responder::problem_json,
+};
+
+impl<S: Send + Sync> FromRequestParts<S> for SecFetchVerified {
+    type Rejection = NotVerified;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<SecFetchVerified>()
+            .copied()
+            .ok_or(NotVerified)
+    }
+}
+
+/// Rejection of the [SecFetchVerified] extractor, responding with `403 Forbidden`
+#[derive(Clone, Copy, Debug)]
+pub struct NotVerified;
+
+impl IntoResponse for NotVerified {
+    fn into_response(self) -> Response {
+        StatusCode::FORBIDDEN.into_response()
+    }
+}
+
+/// Rejection of a request denied by the [SecFetch](crate::SecFetch) middleware, see [RejectionResponder]
+///
+/// Like axum's extractor rejections, it responds with a `text/plain` body describing the denial, or with an
+/// `application/problem+json` body when the request accepts JSON. It can be customized the same way, e.g. by
+/// converting it into an application error in a custom responder:
+///
+/// ```
+/// use axum::response::{IntoResponse, Response};
+/// use tower_sec_fetch::{DenialContext, SecFetchLayer, SecFetchRejection};
+///
+/// SecFetchLayer::default().on_denied(|denial: &DenialContext<'_>| -> Response {
+///     let rejection = SecFetchRejection::from(denial);
+///     (rejection.status(), format!("nope: {}", rejection.body_text())).into_response()
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct SecFetchRejection {
+    reason: DenialReason,
+    status: StatusCode,
+    id: Option<DenialId>,
+    json: bool,
+}
+
+impl SecFetchRejection {
+    /// Why the request was denied
+    pub fn reason(&self) -> DenialReason {
+        self.reason
+    }
+
+    /// The status code of the rejection
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The unique id of the denial, see [denial_ids](crate::SecFetchLayer::denial_ids)
+    pub fn id(&self) -> Option<DenialId> {
+        self.id
+    }
+
+    /// The plain text description of the rejection
+    pub fn body_text(&self) -> String {
+        format!("Request denied: {}", self.reason)
+    }
+}
+
+impl From<&DenialContext<'_>> for SecFetchRejection {
+    fn from(denial: &DenialContext<'_>) -> Self {
+        Self {
+            reason: denial.reason(),
+            status: denial.status(),
+            id: denial.id(),
+            json: accepts_json(denial.headers()),
+        }
+    }
+}
+
+impl From<DeniedRequest> for SecFetchRejection {
+    fn from(denied: DeniedRequest) -> Self {
+        Self {
+            reason: denied.reason(),
+            status: denied.status(),
+            id: denied.id(),
+            json: false,
+        }
+    }
+}
+
+impl IntoResponse for SecFetchRejection {
+    fn into_response(self) -> Response {
+        if self.json {
+            return (
+                self.status,
+                [(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/problem+json"),
+                )],
+                problem_json(self.status, self.reason),
+            )
+                .into_response();
+        }
+
+        (self.status, self.body_text()).into_response()
+    }
+}
+
+/// Responds to denied requests with a [SecFetchRejection], see [axum_rejection](crate::SecFetchLayer::axum_rejection)
+pub struct RejectionResponder;
+
+impl SecFetchResponder<Body> for RejectionResponder {
+    fn respond(&self, denial: &DenialContext<'_>) -> Response {
+        SecFetchRejection::from(denial).into_response()
+    }
+}
+
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(http::header::ACCEPT)
+        .iter()
+        .filter_map(|accept| accept.to_str().ok())
+        .flat_map(|accept| accept.split(','))
+        .map(|media_type| media_type.split(';').next().unwrap_or_default().trim())
+        .any(|media_type| media_type == "application/json" || media_type.ends_with("+json"))
+}
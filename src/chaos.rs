@@ -0,0 +1,106 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::DenialReason;
+
+/// Failure injection for resilience testing
+///
+/// When attached to a [SecFetchLayer](crate::SecFetchLayer) with [with_chaos](crate::SecFetchLayer::with_chaos),
+/// the evaluation policy verdict is randomly flipped and/or the response delayed, so that fallbacks, alerting,
+/// and user-facing error handling can be verified against a misbehaving CSRF layer.
+///
+/// The randomness is driven by a seeded pseudo-random generator, so runs are reproducible.
+///
+/// <div class="warning">
+///
+/// This is meant for testing environments only. Never enable it in production.
+///
+/// </div>
+pub struct Chaos {
+    state: AtomicU64,
+    flip_rate: f64,
+    delay_rate: f64,
+    delay: Duration,
+}
+
+impl Chaos {
+    /// Creates a new chaos configuration that does nothing until rates are set
+    pub fn new(seed: u64) -> Self {
+        Self {
+            // xorshift gets stuck on a zero state
+            state: AtomicU64::new(seed.max(1)),
+            flip_rate: 0.0,
+            delay_rate: 0.0,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Probability (between `0.0` and `1.0`) of a verdict being flipped,
+    /// meaning an allowed request gets denied and vice versa
+    pub fn flip_rate(mut self, rate: f64) -> Self {
+        self.flip_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Probability (between `0.0` and `1.0`) of the response being delayed by `delay`
+    ///
+    /// The delay is an asynchronous Tokio timer, so the service must run within a Tokio runtime with the time driver enabled.
+    pub fn delay_rate(mut self, rate: f64, delay: Duration) -> Self {
+        self.delay_rate = rate.clamp(0.0, 1.0);
+        self.delay = delay;
+        self
+    }
+
+    /// Flips the verdict and rolls the delay of the response
+    pub(crate) fn apply(
+        &self,
+        verdict: Result<(), DenialReason>,
+    ) -> (Result<(), DenialReason>, Option<Duration>) {
+        let delay = self.roll(self.delay_rate).then_some(self.delay);
+        if let Some(_delay) = delay {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(delay = ?_delay, "chaos: delaying response");
+        }
+
+        if !self.roll(self.flip_rate) {
+            return (verdict, delay);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(allowed = verdict.is_ok(), "chaos: flipping verdict");
+
+        let flipped = match verdict {
+            Ok(()) => Err(DenialReason::Chaos),
+            Err(_) => Ok(()),
+        };
+        (flipped, delay)
+    }
+
+    fn roll(&self, rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+
+        let value = self.next();
+        (value >> 11) as f64 / (1u64 << 53) as f64 <= rate
+    }
+
+    // xorshift64*
+    fn next(&self) -> u64 {
+        let step = |mut x: u64| {
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            x
+        };
+
+        let previous = self
+            .state
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+            .expect("update always succeeds");
+
+        step(previous).wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    error::Error,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{AuthorizationDecision, SecFetchAuthorizer, header};
+
+/// A [SecFetchAuthorizer] that allows requests coming from a set of IP ranges,
+/// such as internal tooling that does not send Fetch Metadata headers
+///
+/// The peer address is read from a [SocketAddr] or [IpAddr] in the request extensions by default.
+/// Frameworks that store it in their own type, such as axum's `ConnectInfo`, can provide it with
+/// [peer_addr](CidrAuthorizer::peer_addr).
+///
+/// ```
+/// use std::net::SocketAddr;
+///
+/// use axum::extract::ConnectInfo;
+/// use tower_sec_fetch::{CidrAuthorizer, SecFetchLayer};
+///
+/// let authorizer = CidrAuthorizer::new(["10.0.0.0/8", "fd00::/8"])
+///     .unwrap()
+///     .peer_addr(|extensions| {
+///         extensions
+///             .get::<ConnectInfo<SocketAddr>>()
+///             .map(|info| info.0.ip())
+///     });
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+#[derive(Clone)]
+pub struct CidrAuthorizer {
+    ranges: Arc<[Cidr]>,
+    peer_addr: fn(&http::Extensions) -> Option<IpAddr>,
+    trust_forwarded_for: bool,
+}
+
+impl CidrAuthorizer {
+    pub fn new<C>(ranges: impl IntoIterator<Item = C>) -> Result<Self, InvalidCidr>
+    where
+        C: AsRef<str>,
+    {
+        Ok(Self {
+            ranges: ranges
+                .into_iter()
+                .map(|range| range.as_ref().parse())
+                .collect::<Result<_, _>>()?,
+            peer_addr: |extensions| {
+                extensions
+                    .get::<SocketAddr>()
+                    .map(SocketAddr::ip)
+                    .or_else(|| extensions.get::<IpAddr>().copied())
+            },
+            trust_forwarded_for: false,
+        })
+    }
+
+    /// Reads the peer address from the request extensions
+    pub fn peer_addr(mut self, peer_addr: fn(&http::Extensions) -> Option<IpAddr>) -> Self {
+        self.peer_addr = peer_addr;
+        self
+    }
+
+    /// Uses the last address of the `x-forwarded-for` header instead of the peer address, when present
+    ///
+    /// Only enable this behind a proxy that appends the client address to the header,
+    /// as clients can send arbitrary values otherwise.
+    pub fn trust_forwarded_for(mut self) -> Self {
+        self.trust_forwarded_for = true;
+        self
+    }
+
+    fn client_addr<B>(&self, request: &http::Request<B>) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded = request
+                .headers()
+                .get_all(header::X_FORWARDED_FOR)
+                .iter()
+                .next_back()
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|addr| addr.trim().parse().ok());
+
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+
+        (self.peer_addr)(request.extensions())
+    }
+}
+
+impl SecFetchAuthorizer for CidrAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        let Some(addr) = self.client_addr(request) else {
+            return AuthorizationDecision::Continue;
+        };
+
+        if self.ranges.iter().any(|range| range.contains(addr)) {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(self.ranges.len())
+    }
+}
+
+/// A range of IP addresses in CIDR notation, such as `10.0.0.0/8` or `fd00::/8`
+///
+/// A single address without prefix length is a range containing only that address.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let addr = match addr {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+            addr => addr,
+        };
+
+        match (self.addr, addr) {
+            (IpAddr::V4(range), IpAddr::V4(addr)) => {
+                mask(u32::from(range).into(), self.prefix, 32)
+                    == mask(u32::from(addr).into(), self.prefix, 32)
+            }
+            (IpAddr::V6(range), IpAddr::V6(addr)) => {
+                mask(u128::from(range), self.prefix, 128)
+                    == mask(u128::from(addr), self.prefix, 128)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn mask(addr: u128, prefix: u8, bits: u8) -> u128 {
+    match bits - prefix {
+        0 => addr,
+        host if host >= 128 => 0,
+        host => addr >> host,
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = InvalidCidr;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidCidr(range.to_string());
+        let (addr, prefix) = match range.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (range, None),
+        };
+
+        let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let bits = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| invalid())?,
+            None => bits,
+        };
+
+        if prefix > bits {
+            return Err(invalid());
+        }
+
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// The error returned when parsing a malformed [Cidr]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidCidr(String);
+
+impl fmt::Display for InvalidCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid CIDR range: {:?}", self.0)
+    }
+}
+
+impl Error for InvalidCidr {}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_matches_ranges() {
+        let range: Cidr = "10.0.0.0/8".parse().unwrap();
+        check!(range.contains("10.1.2.3".parse().unwrap()));
+        check!(range.contains("::ffff:10.1.2.3".parse().unwrap()));
+        check!(!range.contains("11.0.0.1".parse().unwrap()));
+        check!(!range.contains("fd00::1".parse().unwrap()));
+
+        let range: Cidr = "fd00::/8".parse().unwrap();
+        check!(range.contains("fd12::1".parse().unwrap()));
+        check!(!range.contains("fe80::1".parse().unwrap()));
+
+        let range: Cidr = "0.0.0.0/0".parse().unwrap();
+        check!(range.contains("192.168.1.1".parse().unwrap()));
+
+        let range: Cidr = "192.168.1.1".parse().unwrap();
+        check!(range.contains("192.168.1.1".parse().unwrap()));
+        check!(!range.contains("192.168.1.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn it_rejects_invalid_ranges() {
+        check!("10.0.0.0/33".parse::<Cidr>().is_err());
+        check!("10.0.0/8".parse::<Cidr>().is_err());
+        check!("fd00::/129".parse::<Cidr>().is_err());
+        check!("10.0.0.0/x".parse::<Cidr>().is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Deserialize;
+
+use crate::{FetchMetadata, RequestHead, RuleDecision, policy::path_matches};
+
+/// Rules loaded from a configuration file, added to the evaluation policy with [rules](crate::PolicyBuilder::rules)
+///
+/// Rules are evaluated in order, and the first one matching the request decides the verdict.
+/// Requests matching no rule continue to the rest of the evaluation policy.
+///
+/// A rule matches a request when all of its matchers match:
+///
+/// - `path`: a pattern where `*` matches a single path segment, `{v}` matches an API version segment such as `v1`,
+///   and a trailing `**` matches any number of segments
+/// - `methods`, `sites`, `modes`, `dests`: lists of allowed values of the method and the `sec-fetch-*` headers
+///
+/// The `sec-fetch-*` matchers see the [FetchMetadata] resolved by the policy, like [custom](crate::PolicyBuilder::custom) rules:
+/// the most restrictive site wins when the header is sent multiple times, values are lowercased with
+/// [case_insensitive_metadata](crate::PolicyBuilder::case_insensitive_metadata), and unknown sites are treated as `cross-site`.
+/// Their values are compared case-insensitively.
+///
+/// Omitted matchers match every request. The rule set can be deserialized from any format supported by [serde],
+/// such as YAML, or from JSON with [from_json](RuleSet::from_json).
+///
+/// ```
+/// use tower_sec_fetch::{RuleSet, SecFetchLayer};
+///
+/// let rules = RuleSet::from_json(
+///     r#"[
+///         { "path": "/webhooks/**", "methods": ["POST"], "action": "allow" },
+///         { "path": "/admin/**", "sites": ["same-site"], "action": "deny" }
+///     ]"#,
+/// )
+/// .unwrap();
+///
+/// SecFetchLayer::new(|policy| {
+///     policy.rules(rules);
+/// });
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(transparent)]
+pub struct RuleSet(Vec<ConfigRule>);
+
+/// A rule of a [RuleSet]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigRule {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    methods: Option<Vec<String>>,
+    #[serde(default)]
+    sites: Option<Vec<String>>,
+    #[serde(default)]
+    modes: Option<Vec<String>>,
+    #[serde(default)]
+    dests: Option<Vec<String>>,
+    action: RuleDecision,
+}
+
+impl RuleSet {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// The action of the first rule matching the request, or [Continue](RuleDecision::Continue) if none match
+    ///
+    /// It allows a [custom](crate::PolicyBuilder::custom) rule to evaluate rules that are reloaded at runtime.
+    pub fn decide(&self, metadata: &FetchMetadata, request: &RequestHead<'_>) -> RuleDecision {
+        self.0
+            .iter()
+            .find(|rule| rule.matches(metadata, request))
+            .map_or(RuleDecision::Continue, |rule| rule.action)
+    }
+}
+
+impl ConfigRule {
+    fn matches(&self, metadata: &FetchMetadata, request: &RequestHead<'_>) -> bool {
+        let contains = |values: &Option<Vec<String>>, value: Option<&str>| {
+            values.as_ref().is_none_or(|values| {
+                value.is_some_and(|value| {
+                    values
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(value))
+                })
+            })
+        };
+
+        self.path
+            .as_ref()
+            .is_none_or(|pattern| path_matches(pattern, request.uri().path()))
+            && self.methods.as_ref().is_none_or(|methods| {
+                methods
+                    .iter()
+                    .any(|method| method == request.method().as_str())
+            })
+            && contains(&self.sites, Some(metadata.site.as_str()))
+            && contains(
+                &self.modes,
+                metadata.mode.as_ref().map(|mode| mode.as_str()),
+            )
+            && contains(
+                &self.dests,
+                metadata.dest.as_ref().map(|dest| dest.as_str()),
+            )
+    }
+}
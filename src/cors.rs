@@ -0,0 +1,94 @@
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{Policy, SecFetchLayer};
+
+impl Policy {
+    /// The [trusted_origins](crate::PolicyBuilder::trusted_origins) of the policy, as the origins allowed by a [CorsLayer]
+    ///
+    /// ```
+    /// use tower_http::cors::CorsLayer;
+    /// use tower_sec_fetch::Policy;
+    ///
+    /// let policy = Policy::new(|policy| {
+    ///     policy.trusted_origins(["https://partner.example"]);
+    /// });
+    ///
+    /// CorsLayer::new().allow_origin(policy.allow_origin());
+    /// ```
+    pub fn allow_origin(&self) -> AllowOrigin {
+        AllowOrigin::list(self.trusted_origins())
+    }
+}
+
+impl<A, R, D, F> SecFetchLayer<A, R, D, F> {
+    /// A [CorsLayer] allowing the [trusted_origins](crate::PolicyBuilder::trusted_origins) of the policy,
+    /// so that the CORS and CSRF policies cannot drift apart
+    ///
+    /// Only the origins are configured, so allowed methods, headers, and credentials can be added to the returned layer.
+    /// The policies of [hosts](SecFetchLayer::with_host_policies) and [schedules](SecFetchLayer::with_schedule) are not considered.
+    ///
+    /// ```
+    /// use tower::ServiceBuilder;
+    /// use tower_sec_fetch::SecFetchLayer;
+    ///
+    /// let sec_fetch = SecFetchLayer::new(|policy| {
+    ///     policy.trusted_origins(["https://partner.example"]);
+    /// });
+    ///
+    /// let cors = sec_fetch.cors_layer().allow_methods([http::Method::POST]);
+    /// ServiceBuilder::new().layer(cors).layer(sec_fetch);
+    /// ```
+    pub fn cors_layer(&self) -> CorsLayer {
+        CorsLayer::new().allow_origin(self.shared.policy.allow_origin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use http::{HeaderValue, StatusCode, header};
+    use tower::{Layer, ServiceExt, service_fn};
+
+    use crate::SecFetchLayer;
+
+    #[tokio::test]
+    async fn it_shares_trusted_origins_with_the_cors_layer() {
+        let sec_fetch = SecFetchLayer::new(|policy| {
+            policy.trusted_origins(["https://partner.example"]);
+        });
+        let service = sec_fetch.cors_layer().layer(sec_fetch.layer(service_fn(
+            async |_: http::Request<()>| Ok::<_, std::convert::Infallible>(http::Response::new(())),
+        )));
+        let request = |origin: &'static str| {
+            http::Request::post("/")
+                .header(header::ORIGIN, origin)
+                .header("sec-fetch-site", "cross-site")
+                .header("sec-fetch-mode", "cors")
+                .header("sec-fetch-dest", "empty")
+                .body(())
+                .unwrap()
+        };
+
+        let response = service
+            .clone()
+            .oneshot(request("https://partner.example"))
+            .await
+            .unwrap();
+        check!(response.status() == StatusCode::OK);
+        check!(
+            response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                == Some(&HeaderValue::from_static("https://partner.example"))
+        );
+
+        let response = service
+            .oneshot(request("https://evil.example"))
+            .await
+            .unwrap();
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(
+            !response
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use http::Method;
+
+use crate::{Dest, Mode, Site, Verdict, header, policy::Policy};
+
+/// The verdicts of an evaluation policy for every combination of Fetch Metadata headers and method
+///
+/// It is returned by [SecFetchLayer::decision_table](crate::SecFetchLayer::decision_table),
+/// so that security reviews can diff the actual behaviour of two configurations.
+///
+/// Each of the `sec-fetch-site`, `sec-fetch-mode`, and `sec-fetch-dest` headers is also evaluated when missing,
+/// and navigations are evaluated both with and without `sec-fetch-user: ?1`.
+///
+/// Every request is evaluated against the `/` path, so rules that only apply to other paths,
+/// such as [untrusted_paths](crate::PolicyBuilder::untrusted_paths), are not represented.
+/// Verdicts are the ones applied by the middleware, see [Policy::verdict].
+/// The authorizer, exemptions, and schedule of the layer are not evaluated.
+///
+/// ```
+/// use tower_sec_fetch::SecFetchLayer;
+///
+/// let default = SecFetchLayer::default().decision_table();
+/// let relaxed = SecFetchLayer::new(|policy| {
+///     policy.allow_safe_methods();
+/// })
+/// .decision_table();
+///
+/// for (before, after) in default.decisions().iter().zip(relaxed.decisions()) {
+///     if before.verdict != after.verdict {
+///         println!("{before} -> {after}");
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct DecisionTable {
+    decisions: Vec<Decision>,
+}
+
+impl DecisionTable {
+    /// The methods evaluated for every combination of Fetch Metadata headers
+    pub const METHODS: [Method; 7] = [
+        Method::GET,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::POST,
+        Method::PUT,
+        Method::PATCH,
+        Method::DELETE,
+    ];
+
+    pub(crate) fn new(policy: &Policy) -> Self {
+        let sites = Site::ALL.into_iter().map(Some).chain([None]);
+        let mut decisions = Vec::new();
+
+        for site in sites {
+            for mode in Mode::ALL.iter().copied().map(Some).chain([None]) {
+                // only navigations carry a user activation
+                let users: &[bool] = if mode == Some(Mode::Navigate) {
+                    &[false, true]
+                } else {
+                    &[false]
+                };
+
+                for dest in Dest::ALL.iter().copied().map(Some).chain([None]) {
+                    for &user in users {
+                        for method in Self::METHODS {
+                            let mut request =
+                                http::Request::builder().method(method.clone()).uri("/");
+                            for (name, value) in [
+                                (header::SEC_FETCH_SITE, site.map(|site| site.as_str())),
+                                (header::SEC_FETCH_MODE, mode.map(|mode| mode.as_str())),
+                                (header::SEC_FETCH_DEST, dest.map(|dest| dest.as_str())),
+                                (header::SEC_FETCH_USER, user.then_some("?1")),
+                            ] {
+                                if let Some(value) = value {
+                                    request = request.header(name, value);
+                                }
+                            }
+                            let request = request.body(()).expect("valid request");
+
+                            decisions.push(Decision {
+                                site,
+                                mode,
+                                dest,
+                                user,
+                                verdict: policy.verdict(&request),
+                                method,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Self { decisions }
+    }
+
+    /// The verdicts of the policy, ordered by site, mode, destination, user activation, and method
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+}
+
+impl fmt::Display for DecisionTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for decision in &self.decisions {
+            writeln!(f, "{decision}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The verdict of an evaluation policy for a combination of Fetch Metadata headers and method
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decision {
+    /// The `sec-fetch-site` header, or `None` if missing
+    pub site: Option<Site>,
+    /// The `sec-fetch-mode` header, or `None` if missing
+    pub mode: Option<Mode>,
+    /// The `sec-fetch-dest` header, or `None` if missing
+    pub dest: Option<Dest>,
+    /// Whether the `sec-fetch-user` header is `?1`
+    pub user: bool,
+    pub method: Method,
+    pub verdict: Verdict,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = |value: Option<&'static str>| value.unwrap_or("-");
+
+        write!(
+            f,
+            "{} {} {} ",
+            header(self.site.map(|site| site.as_str())),
+            header(self.mode.map(|mode| mode.as_str())),
+            header(self.dest.map(|dest| dest.as_str())),
+        )?;
+        if self.user {
+            f.write_str("?1 ")?;
+        }
+
+        write!(f, "{}: {}", self.method, self.verdict)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Decision {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut decision = serializer.serialize_struct("Decision", 7)?;
+        decision.serialize_field("site", &self.site.map(|site| site.as_str()))?;
+        decision.serialize_field("mode", &self.mode.map(|mode| mode.as_str()))?;
+        decision.serialize_field("dest", &self.dest.map(|dest| dest.as_str()))?;
+        decision.serialize_field("user", &self.user)?;
+        decision.serialize_field("method", self.method.as_str())?;
+        decision.serialize_field("verdict", self.verdict.as_str())?;
+        decision.serialize_field(
+            "denial",
+            &self.verdict.reason().map(|reason| reason.as_str()),
+        )?;
+        decision.end()
+    }
+}
@@ -0,0 +1,43 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::BuildHasher,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use http::HeaderValue;
+
+/// Unique identifier of a denied request
+///
+/// When enabled with [denial_ids](crate::SecFetchLayer::denial_ids), it is inserted in the request extensions
+/// before calling the [SecFetchReporter](crate::SecFetchReporter), and returned to the client
+/// in the [X_DENIAL_ID](crate::header::X_DENIAL_ID) response header, so that client-side reports can be correlated
+/// with server-side ones.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DenialId(u128);
+
+impl DenialId {
+    pub(crate) fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        // RandomState is seeded randomly per process, making ids unpredictable across restarts
+        let random = RandomState::new().hash_one((nanos, count));
+
+        Self(((nanos as u128) << 64) | (random ^ count) as u128)
+    }
+
+    pub(crate) fn to_header_value(self) -> HeaderValue {
+        HeaderValue::from_str(&self.to_string()).expect("hex is a valid header value")
+    }
+}
+
+impl fmt::Display for DenialId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use http::{Method, StatusCode, Uri};
+use pin_project_lite::pin_project;
+use tower::{Layer, Service};
+
+use crate::{DenialId, DenialReason};
+
+/// A request denied by the [SecFetch](crate::SecFetch) middleware
+///
+/// With [deny_with_error](crate::SecFetchLayer::deny_with_error), it is inserted in the extensions of the denial responses
+/// built by the responder, and returned as [SecFetchError::Denied].
+#[derive(Clone, Debug)]
+pub struct DeniedRequest {
+    method: Method,
+    uri: Uri,
+    reason: DenialReason,
+    status: StatusCode,
+    id: Option<DenialId>,
+}
+
+impl DeniedRequest {
+    pub(crate) fn new<B>(
+        request: &http::Request<B>,
+        reason: DenialReason,
+        status: StatusCode,
+    ) -> Self {
+        Self {
+            method: request.method().clone(),
+            uri: request.uri().clone(),
+            reason,
+            status,
+            id: request.extensions().get().copied(),
+        }
+    }
+
+    /// The method of the denied request
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// The URI of the denied request
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Why the request was denied
+    pub fn reason(&self) -> DenialReason {
+        self.reason
+    }
+
+    /// The status code configured for the denial reason, see [status_for](crate::SecFetchLayer::status_for)
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The unique id of the denial, see [denial_ids](crate::SecFetchLayer::denial_ids)
+    pub fn id(&self) -> Option<DenialId> {
+        self.id
+    }
+}
+
+/// Error of services wrapped with [deny_with_error](crate::SecFetchLayer::deny_with_error)
+#[derive(Debug)]
+pub enum SecFetchError<E> {
+    /// The request was denied by the middleware
+    Denied(DeniedRequest),
+    /// The inner service failed
+    Inner(E),
+}
+
+impl<E: fmt::Display> fmt::Display for SecFetchError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Denied(denied) => write!(f, "request denied: {}", denied.reason()),
+            Self::Inner(error) => error.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for SecFetchError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Denied(_) => None,
+            Self::Inner(error) => Some(error),
+        }
+    }
+}
+
+/// Layer returning the denials of the wrapped [SecFetchLayer](crate::SecFetchLayer) as errors,
+/// see [deny_with_error](crate::SecFetchLayer::deny_with_error)
+#[derive(Clone)]
+pub struct DenialErrorLayer<L>(L);
+
+impl<L> DenialErrorLayer<L> {
+    pub(crate) fn new(layer: L) -> Self {
+        Self(layer)
+    }
+}
+
+impl<L, S> Layer<S> for DenialErrorLayer<L>
+where
+    L: Layer<S>,
+{
+    type Service = DenialErrors<L::Service>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DenialErrors(self.0.layer(inner))
+    }
+}
+
+/// Middleware turning denial responses into [SecFetchError::Denied], see [DenialErrorLayer]
+#[derive(Clone)]
+pub struct DenialErrors<S>(S);
+
+impl<S, ReqB, ResB> Service<http::Request<ReqB>> for DenialErrors<S>
+where
+    S: Service<http::Request<ReqB>, Response = http::Response<ResB>>,
+{
+    type Response = S::Response;
+
+    type Error = SecFetchError<S::Error>;
+
+    type Future = DenialErrorsFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx).map_err(SecFetchError::Inner)
+    }
+
+    fn call(&mut self, request: http::Request<ReqB>) -> Self::Future {
+        DenialErrorsFuture {
+            inner: self.0.call(request),
+        }
+    }
+}
+
+pin_project! {
+    /// Response future of [DenialErrors]
+    pub struct DenialErrorsFuture<F> {
+        #[pin]
+        inner: F,
+    }
+}
+
+impl<F, B, E> Future for DenialErrorsFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = Result<http::Response<B>, SecFetchError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut response = ready!(self.project().inner.poll(cx)).map_err(SecFetchError::Inner)?;
+
+        match response.extensions_mut().remove::<DeniedRequest>() {
+            Some(denied) => Poll::Ready(Err(SecFetchError::Denied(denied))),
+            None => Poll::Ready(Ok(response)),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::HeaderName;
+
+use crate::hmac::{self, HmacAlgorithm, Keyring};
+
+/// Default header carrying the exemption token
+pub const DEFAULT_EXEMPTION_HEADER: HeaderName = HeaderName::from_static("x-sec-fetch-exemption");
+
+/// Short-lived signed tokens that let requests bypass the evaluation policy
+///
+/// Operators can [mint](ExemptionTokens::mint) tokens for partners or during incident mitigation.
+/// When attached to a [SecFetchLayer](crate::SecFetchLayer) with [with_exemptions](crate::SecFetchLayer::with_exemptions),
+/// requests carrying a valid, unexpired token in the exemption header are allowed, and reported to
+/// [on_exemption_used](crate::SecFetchReporter::on_exemption_used).
+/// Invalid or expired tokens are ignored, and the request is evaluated as usual.
+///
+/// Tokens have the form `{expiry}.{subject}.{key_id}.{signature}`, where `expiry` is a Unix timestamp in seconds,
+/// `subject` identifies who the token was minted for, `key_id` is the id of the [Keyring] key that signed it,
+/// and `signature` is the hex-encoded HMAC of `{expiry}.{subject}.{key_id}`.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tower_sec_fetch::{ExemptionTokens, SecFetchLayer};
+///
+/// let exemptions = ExemptionTokens::new(b"exemption-secret".to_vec());
+/// let token = exemptions.mint("partner-integration", Duration::from_secs(60 * 60));
+///
+/// SecFetchLayer::default().with_exemptions(exemptions);
+/// ```
+pub struct ExemptionTokens {
+    keyring: Keyring,
+    algorithm: HmacAlgorithm,
+    header: HeaderName,
+}
+
+/// A valid exemption token
+#[derive(Clone, Debug)]
+pub struct Exemption {
+    /// Who the token was minted for
+    pub subject: String,
+    /// When the token expires
+    pub expires_at: SystemTime,
+}
+
+impl ExemptionTokens {
+    pub fn new(keyring: impl Into<Keyring>) -> Self {
+        Self {
+            keyring: keyring.into(),
+            algorithm: HmacAlgorithm::default(),
+            header: DEFAULT_EXEMPTION_HEADER,
+        }
+    }
+
+    /// The HMAC algorithm used to sign tokens, defaults to [HmacAlgorithm::Sha256]
+    pub fn algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// The header carrying the token, defaults to [DEFAULT_EXEMPTION_HEADER]
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Mints a token for `subject`, valid for `ttl`
+    ///
+    /// The subject must only contain characters allowed in header values.
+    pub fn mint(&self, subject: &str, ttl: Duration) -> String {
+        let expiry = (SystemTime::now() + ttl)
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |expiry| expiry.as_secs());
+        let (key_id, key) = self.keyring.signing_key();
+        let payload = format!("{expiry}.{subject}.{key_id}");
+        let signature = hmac::hex_encode(&self.algorithm.sign(&key, payload.as_bytes()));
+
+        format!("{payload}.{signature}")
+    }
+
+    /// Validates the signature and expiry of `token`
+    pub fn verify(&self, token: &str, now: SystemTime) -> Option<Exemption> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let signature = hmac::hex_decode(signature.as_bytes())?;
+        let (claims, key_id) = payload.rsplit_once('.')?;
+        let key = self.keyring.key(key_id)?;
+        if !self.algorithm.verify(&key, payload.as_bytes(), &signature) {
+            return None;
+        }
+
+        let (expiry, subject) = claims.split_once('.')?;
+        let expires_at = UNIX_EPOCH + Duration::from_secs(expiry.parse().ok()?);
+        if expires_at <= now {
+            return None;
+        }
+
+        Some(Exemption {
+            subject: subject.to_string(),
+            expires_at,
+        })
+    }
+
+    pub(crate) fn exemption<B>(&self, request: &http::Request<B>) -> Option<Exemption> {
+        let token = request.headers().get(&self.header)?.to_str().ok()?;
+
+        self.verify(token, SystemTime::now())
+    }
+}
@@ -0,0 +1,61 @@
+use std::future::{Future, Pending};
+
+use tower::{Service, ServiceExt, util::Oneshot};
+
+use crate::DenialReason;
+
+/// Handles denied requests in place of the [SecFetchResponder](crate::SecFetchResponder)
+pub trait DenialFallback<ReqB, ResB, E> {
+    type Future: Future<Output = Result<http::Response<ResB>, E>>;
+
+    /// Handles the denied request, or hands it back to be answered by the responder
+    fn call(
+        &self,
+        request: http::Request<ReqB>,
+        reason: DenialReason,
+    ) -> Result<Self::Future, http::Request<ReqB>>;
+}
+
+#[doc(hidden)]
+pub struct NoopFallback;
+
+impl<ReqB, ResB, E> DenialFallback<ReqB, ResB, E> for NoopFallback {
+    type Future = Pending<Result<http::Response<ResB>, E>>;
+
+    fn call(
+        &self,
+        request: http::Request<ReqB>,
+        _: DenialReason,
+    ) -> Result<Self::Future, http::Request<ReqB>> {
+        Err(request)
+    }
+}
+
+/// A [DenialFallback] that forwards denied requests to another [Service]
+///
+/// The [DenialReason] is inserted in the request extensions, and the service is cloned for every denied request.
+#[derive(Clone)]
+pub struct DeniedService<S>(S);
+
+impl<S> DeniedService<S> {
+    pub fn new(service: S) -> Self {
+        Self(service)
+    }
+}
+
+impl<S, ReqB, ResB> DenialFallback<ReqB, ResB, S::Error> for DeniedService<S>
+where
+    S: Service<http::Request<ReqB>, Response = http::Response<ResB>> + Clone,
+{
+    type Future = Oneshot<S, http::Request<ReqB>>;
+
+    fn call(
+        &self,
+        mut request: http::Request<ReqB>,
+        reason: DenialReason,
+    ) -> Result<Self::Future, http::Request<ReqB>> {
+        request.extensions_mut().insert(reason);
+
+        Ok(self.0.clone().oneshot(request))
+    }
+}
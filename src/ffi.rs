@@ -0,0 +1,195 @@
+//! C interface to the evaluation policy, for sidecar proxies written in C or C++
+//!
+//! Build a static library with `cargo rustc --release --features ffi --crate-type staticlib`, and declare:
+//!
+//! ```c
+//! int32_t tower_sec_fetch_evaluate(const char *method, const char *site, const char *mode,
+//!                                  const char *dest, uint32_t flags);
+//! ```
+
+use std::ffi::{CStr, c_char};
+
+use crate::{DenialReason, SafeMethodsOrder, SecFetchLayer, Verdict};
+
+/// Rejects requests without Fetch Metadata headers, see [reject_missing_metadata](crate::PolicyBuilder::reject_missing_metadata)
+pub const TSF_REJECT_MISSING_METADATA: u32 = 1;
+/// Allows safe methods regardless of their origin, see [allow_safe_methods](crate::PolicyBuilder::allow_safe_methods)
+pub const TSF_ALLOW_SAFE_METHODS: u32 = 1 << 1;
+/// Evaluates requests like Go's `http.CrossOriginProtection`, see [go_compat](crate::PolicyBuilder::go_compat)
+pub const TSF_GO_COMPAT: u32 = 1 << 2;
+/// Allows safe methods only after reporting them, see [SafeMethodsOrder::AfterReport]
+pub const TSF_SAFE_METHODS_AFTER_REPORT: u32 = 1 << 3;
+
+/// The request is allowed
+pub const TSF_ALLOWED: i32 = 0;
+/// The request is denied with [DenialReason::MissingMetadata]
+pub const TSF_DENIED_MISSING_METADATA: i32 = 1;
+/// The request is denied with [DenialReason::CrossSite]
+pub const TSF_DENIED_CROSS_SITE: i32 = 2;
+/// The request is denied with [DenialReason::UntrustedPath]
+pub const TSF_DENIED_UNTRUSTED_PATH: i32 = 3;
+/// The request is denied with [DenialReason::OriginMismatch]
+pub const TSF_DENIED_ORIGIN_MISMATCH: i32 = 4;
+/// The request is denied with [DenialReason::MissingUserActivation]
+pub const TSF_DENIED_MISSING_USER_ACTIVATION: i32 = 5;
+/// The request is denied with [DenialReason::Custom]
+pub const TSF_DENIED_CUSTOM: i32 = 6;
+/// The request is denied with [DenialReason::Authorizer]
+pub const TSF_DENIED_AUTHORIZER: i32 = 7;
+/// The request is denied by chaos testing
+pub const TSF_DENIED_CHAOS: i32 = 8;
+/// The request is denied with [DenialReason::WebsocketOrigin]
+pub const TSF_DENIED_WEBSOCKET_ORIGIN: i32 = 9;
+/// The request is denied with [DenialReason::AmbiguousMetadata]
+pub const TSF_DENIED_AMBIGUOUS_METADATA: i32 = 10;
+/// The request is denied with [DenialReason::UnknownMetadata]
+pub const TSF_DENIED_UNKNOWN_METADATA: i32 = 11;
+/// The request is denied with [DenialReason::ConflictingMetadata]
+pub const TSF_DENIED_CONFLICTING_METADATA: i32 = 12;
+/// Set on the `TSF_DENIED_*` verdict of requests that are allowed after being reported, see [Verdict::Reported]
+pub const TSF_REPORTED: i32 = 1 << 8;
+/// The arguments are not valid UTF-8, or do not describe a valid request
+pub const TSF_INVALID: i32 = -1;
+
+/// Evaluates a request described by its method and Fetch Metadata headers
+///
+/// `site`, `mode`, and `dest` are the values of the `sec-fetch-*` headers, or null when missing.
+/// `flags` is a combination of the `TSF_*` policy flags.
+///
+/// Returns [TSF_ALLOWED], one of the `TSF_DENIED_*` verdicts, or [TSF_INVALID].
+/// Requests that the middleware would allow after reporting them return their `TSF_DENIED_*` verdict
+/// combined with [TSF_REPORTED], so that `verdict & TSF_REPORTED` tells them apart from denied requests.
+///
+/// # Safety
+///
+/// `method` must be a valid pointer to a nul-terminated string.
+/// `site`, `mode`, and `dest` must be either null or valid pointers to nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tower_sec_fetch_evaluate(
+    method: *const c_char,
+    site: *const c_char,
+    mode: *const c_char,
+    dest: *const c_char,
+    flags: u32,
+) -> i32 {
+    let string = |value: *const c_char| {
+        if value.is_null() {
+            return Ok(None);
+        }
+
+        // SAFETY: the caller guarantees non-null pointers are valid nul-terminated strings
+        unsafe { CStr::from_ptr(value) }.to_str().map(Some)
+    };
+
+    let (Ok(Some(method)), Ok(site), Ok(mode), Ok(dest)) =
+        (string(method), string(site), string(mode), string(dest))
+    else {
+        return TSF_INVALID;
+    };
+
+    let layer = SecFetchLayer::new(|policy| {
+        if flags & TSF_REJECT_MISSING_METADATA != 0 {
+            policy.reject_missing_metadata();
+        }
+        if flags & TSF_ALLOW_SAFE_METHODS != 0 {
+            policy.allow_safe_methods();
+        }
+        if flags & TSF_GO_COMPAT != 0 {
+            policy.go_compat();
+        }
+        if flags & TSF_SAFE_METHODS_AFTER_REPORT != 0 {
+            policy.safe_methods_order(SafeMethodsOrder::AfterReport);
+        }
+    });
+
+    let headers: Vec<_> = [
+        ("sec-fetch-site", site),
+        ("sec-fetch-mode", mode),
+        ("sec-fetch-dest", dest),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((name, value?)))
+    .collect();
+
+    match layer.simulate(method, "/", &headers) {
+        Ok(Verdict::Allowed) => TSF_ALLOWED,
+        Ok(Verdict::Reported(reason)) => verdict(reason) | TSF_REPORTED,
+        Ok(Verdict::Denied(reason)) => verdict(reason),
+        Err(_) => TSF_INVALID,
+    }
+}
+
+fn verdict(reason: DenialReason) -> i32 {
+    match reason {
+        DenialReason::MissingMetadata => TSF_DENIED_MISSING_METADATA,
+        DenialReason::CrossSite => TSF_DENIED_CROSS_SITE,
+        DenialReason::UntrustedPath => TSF_DENIED_UNTRUSTED_PATH,
+        DenialReason::OriginMismatch => TSF_DENIED_ORIGIN_MISMATCH,
+        DenialReason::MissingUserActivation => TSF_DENIED_MISSING_USER_ACTIVATION,
+        DenialReason::AmbiguousMetadata => TSF_DENIED_AMBIGUOUS_METADATA,
+        DenialReason::ConflictingMetadata => TSF_DENIED_CONFLICTING_METADATA,
+        DenialReason::UnknownMetadata => TSF_DENIED_UNKNOWN_METADATA,
+        DenialReason::Custom => TSF_DENIED_CUSTOM,
+        DenialReason::Authorizer => TSF_DENIED_AUTHORIZER,
+        DenialReason::WebsocketOrigin => TSF_DENIED_WEBSOCKET_ORIGIN,
+        #[cfg(feature = "chaos")]
+        DenialReason::Chaos => TSF_DENIED_CHAOS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_evaluates_requests() {
+        let evaluate = |method: &CStr, site: &CStr, flags| unsafe {
+            tower_sec_fetch_evaluate(
+                method.as_ptr(),
+                site.as_ptr(),
+                c"cors".as_ptr(),
+                c"empty".as_ptr(),
+                flags,
+            )
+        };
+
+        check!(evaluate(c"POST", c"same-origin", 0) == TSF_ALLOWED);
+        check!(evaluate(c"POST", c"cross-site", 0) == TSF_DENIED_CROSS_SITE);
+        check!(evaluate(c"GET", c"cross-site", 0) == TSF_DENIED_CROSS_SITE);
+        check!(evaluate(c"GET", c"cross-site", TSF_ALLOW_SAFE_METHODS) == TSF_ALLOWED);
+        check!(evaluate(c"GET", c"cross-site", TSF_GO_COMPAT) == TSF_ALLOWED);
+        check!(
+            evaluate(
+                c"GET",
+                c"cross-site",
+                TSF_ALLOW_SAFE_METHODS | TSF_SAFE_METHODS_AFTER_REPORT
+            ) == TSF_DENIED_CROSS_SITE | TSF_REPORTED
+        );
+        check!(evaluate(c"NOT A METHOD", c"cross-site", 0) == TSF_INVALID);
+    }
+
+    #[test]
+    fn it_handles_missing_headers() {
+        let evaluate = |flags| unsafe {
+            tower_sec_fetch_evaluate(
+                c"POST".as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                flags,
+            )
+        };
+
+        check!(evaluate(0) == TSF_ALLOWED);
+        check!(evaluate(TSF_REJECT_MISSING_METADATA) == TSF_DENIED_MISSING_METADATA);
+        check!(
+            unsafe {
+                tower_sec_fetch_evaluate(ptr::null(), ptr::null(), ptr::null(), ptr::null(), 0)
+            } == TSF_INVALID
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use pin_project_lite::pin_project;
+
+use crate::response_headers::ResponseHeaders;
+
+pin_project! {
+    /// Response future of the [SecFetch](crate::SecFetch) middleware
+    ///
+    /// It resolves to the response of the inner service for allowed requests, to the denial response built by
+    /// the [responder](crate::SecFetchResponder), or to the response of the [fallback](crate::DenialFallback) service.
+    /// Denied requests to [non-canonical origins](crate::PolicyBuilder::canonical_origin) resolve to the redirect
+    /// of the inner service, or to the denial response.
+    pub struct SecFetchFuture<F, Fb, B> {
+        #[pin]
+        kind: Kind<F, Fb, B>,
+        #[pin]
+        delay: Delay,
+    }
+}
+
+#[cfg(feature = "chaos")]
+type Delay = Option<tokio::time::Sleep>;
+
+#[cfg(not(feature = "chaos"))]
+type Delay = ();
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F, Fb, B> {
+        Allowed {
+            #[pin]
+            future: F,
+            headers: Option<ResponseHeaders>,
+        },
+        Denied {
+            response: Option<http::Response<B>>,
+        },
+        Fallback {
+            #[pin]
+            future: Fb,
+        },
+        Redirect {
+            #[pin]
+            future: F,
+            denial: Option<http::Response<B>>,
+        },
+    }
+}
+
+impl<F, Fb, B> SecFetchFuture<F, Fb, B> {
+    pub(crate) fn allowed(future: F, headers: ResponseHeaders) -> Self {
+        Self {
+            kind: Kind::Allowed {
+                future,
+                headers: Some(headers).filter(|headers| !headers.is_empty()),
+            },
+            delay: Delay::default(),
+        }
+    }
+
+    pub(crate) fn denied(response: http::Response<B>) -> Self {
+        Self {
+            kind: Kind::Denied {
+                response: Some(response),
+            },
+            delay: Delay::default(),
+        }
+    }
+
+    pub(crate) fn fallback(future: Fb) -> Self {
+        Self {
+            kind: Kind::Fallback { future },
+            delay: Delay::default(),
+        }
+    }
+
+    pub(crate) fn redirect(future: F, denial: http::Response<B>) -> Self {
+        Self {
+            kind: Kind::Redirect {
+                future,
+                denial: Some(denial),
+            },
+            delay: Delay::default(),
+        }
+    }
+
+    /// Delays the response, see [Chaos](crate::Chaos)
+    #[cfg(feature = "chaos")]
+    pub(crate) fn delayed(mut self, delay: Option<std::time::Duration>) -> Self {
+        self.delay = delay.map(tokio::time::sleep);
+        self
+    }
+}
+
+impl<F, Fb, B, E> Future for SecFetchFuture<F, Fb, B>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+    Fb: Future<Output = F::Output>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        #[cfg(feature = "chaos")]
+        if let Some(delay) = this.delay.as_pin_mut() {
+            ready!(delay.poll(cx));
+        }
+
+        match this.kind.project() {
+            KindProj::Allowed { future, headers } => {
+                let mut response = ready!(future.poll(cx))?;
+
+                if let Some(headers) = headers.take() {
+                    headers.apply(response.headers_mut());
+                }
+
+                Poll::Ready(Ok(response))
+            }
+            KindProj::Denied { response } => Poll::Ready(Ok(response
+                .take()
+                .expect("SecFetchFuture polled after completion"))),
+            KindProj::Fallback { future } => future.poll(cx),
+            KindProj::Redirect { future, denial } => {
+                let response = ready!(future.poll(cx))?;
+                if response.status().is_redirection() {
+                    return Poll::Ready(Ok(response));
+                }
+
+                Poll::Ready(Ok(denial
+                    .take()
+                    .expect("SecFetchFuture polled after completion")))
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use http::{HeaderName, HeaderValue};
+
+use crate::{
+    AuthorizationDecision, DenialId, SecFetchAuthorizer,
+    hmac::{self, HmacAlgorithm, Keyring},
+};
+
+/// Default header carrying the gateway signature
+pub const DEFAULT_SIGNATURE_HEADER: HeaderName = HeaderName::from_static("x-sec-fetch-signature");
+
+/// Header carrying the Unix timestamp, in seconds, at which the gateway signed the request
+pub const GATEWAY_TIMESTAMP_HEADER: HeaderName = HeaderName::from_static("x-sec-fetch-timestamp");
+
+/// Header carrying the single-use nonce of the gateway signature
+pub const GATEWAY_NONCE_HEADER: HeaderName = HeaderName::from_static("x-sec-fetch-nonce");
+
+/// A [SecFetchAuthorizer] that trusts requests signed by an edge gateway
+///
+/// The gateway performs the CSRF check, and then signs the request by setting the [GATEWAY_TIMESTAMP_HEADER]
+/// and [GATEWAY_NONCE_HEADER] headers, and the signature header to the hex-encoded HMAC of
+/// `{timestamp} {nonce} {method} {path_and_query}` (e.g. `1735689600 4f2a POST /submit?draft=true`).
+///
+/// Requests carrying a valid signature are allowed without re-evaluating them.
+/// Requests with a missing or invalid signature, a timestamp outside the [max_skew](GatewayAuthorizer::max_skew) window
+/// or an already used nonce are denied, so that traffic bypassing the gateway fails closed and captured signatures
+/// cannot be replayed.
+///
+/// Signatures are accepted from any key in the [Keyring], so the gateway can be rotated to a new key independently.
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use tower_sec_fetch::{GatewayAuthorizer, HmacAlgorithm, SecFetchLayer};
+///
+/// let authorizer = GatewayAuthorizer::new(b"shared-secret".to_vec())
+///     .algorithm(HmacAlgorithm::Sha256)
+///     .header(http::HeaderName::from_static("x-edge-signature"))
+///     .max_skew(Duration::from_secs(30));
+///
+/// let mut request = http::Request::post("/submit").body(()).unwrap();
+/// authorizer.sign(&mut request, SystemTime::now());
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+pub struct GatewayAuthorizer {
+    keyring: Keyring,
+    algorithm: HmacAlgorithm,
+    header: HeaderName,
+    max_skew: Duration,
+    nonces: Mutex<Nonces>,
+}
+
+impl GatewayAuthorizer {
+    pub fn new(keyring: impl Into<Keyring>) -> Self {
+        Self {
+            keyring: keyring.into(),
+            algorithm: HmacAlgorithm::default(),
+            header: DEFAULT_SIGNATURE_HEADER,
+            max_skew: Duration::from_secs(60),
+            nonces: Mutex::default(),
+        }
+    }
+
+    /// The HMAC algorithm used by the gateway, defaults to [HmacAlgorithm::Sha256]
+    pub fn algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// The header carrying the signature, defaults to [DEFAULT_SIGNATURE_HEADER]
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// How far the signature timestamp may be from the current time, in either direction, defaults to 60 seconds
+    pub fn max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Signs the request as the gateway would, using the newest key
+    ///
+    /// Sets the timestamp, a fresh nonce and the signature headers.
+    pub fn sign<B>(&self, request: &mut http::Request<B>, timestamp: SystemTime) {
+        let timestamp = unix_seconds(timestamp).to_string();
+        let nonce = DenialId::generate().to_string();
+        let (_, key) = self.keyring.signing_key();
+        let signature = hmac::hex_encode(&self.algorithm.sign(
+            &key,
+            &message(request, timestamp.as_bytes(), nonce.as_bytes()),
+        ));
+
+        let headers = request.headers_mut();
+        for (name, value) in [
+            (GATEWAY_TIMESTAMP_HEADER, timestamp),
+            (GATEWAY_NONCE_HEADER, nonce),
+            (self.header.clone(), signature),
+        ] {
+            headers.insert(
+                name,
+                HeaderValue::try_from(value).expect("digits and hex are valid header values"),
+            );
+        }
+    }
+
+    fn verify<B>(&self, request: &http::Request<B>, now: SystemTime) -> Result<(), &'static str> {
+        let headers = request.headers();
+        let (Some(signature), Some(timestamp), Some(nonce)) = (
+            headers.get(&self.header),
+            headers.get(GATEWAY_TIMESTAMP_HEADER),
+            headers.get(GATEWAY_NONCE_HEADER),
+        ) else {
+            return Err("request is missing the gateway signature");
+        };
+
+        let signature = hmac::hex_decode(signature.as_bytes())
+            .ok_or("request has an invalid gateway signature")?;
+        if nonce.is_empty() || nonce.as_bytes().contains(&b' ') {
+            return Err("request has an invalid gateway nonce");
+        }
+        let message = message(request, timestamp.as_bytes(), nonce.as_bytes());
+        if !self
+            .keyring
+            .keys()
+            .iter()
+            .any(|key| self.algorithm.verify(key, &message, &signature))
+        {
+            return Err("request has an invalid gateway signature");
+        }
+
+        let signed_at = timestamp
+            .to_str()
+            .ok()
+            .and_then(|timestamp| timestamp.parse::<u64>().ok())
+            .ok_or("request has an invalid gateway timestamp")?;
+        let now = unix_seconds(now);
+        if now.abs_diff(signed_at) > self.max_skew.as_secs() {
+            return Err("request has an expired gateway signature");
+        }
+
+        let mut nonces = self.nonces.lock().expect("gateway nonces lock poisoned");
+        // a nonce must be remembered for as long as its timestamp can be accepted
+        if !nonces.insert(nonce.as_bytes(), now, now + 2 * self.max_skew.as_secs()) {
+            return Err("request reuses a gateway nonce");
+        }
+
+        Ok(())
+    }
+}
+
+impl SecFetchAuthorizer for GatewayAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        if let Err(_reason) = self.verify(request, SystemTime::now()) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "{_reason}: denied",
+            );
+
+            return AuthorizationDecision::Denied;
+        }
+
+        AuthorizationDecision::Allowed
+    }
+}
+
+/// Nonces seen within the skew window, in insertion order
+#[derive(Default)]
+struct Nonces {
+    seen: HashSet<Box<[u8]>>,
+    expiries: VecDeque<(u64, Box<[u8]>)>,
+}
+
+impl Nonces {
+    /// Records the nonce, returning false if it was already seen
+    fn insert(&mut self, nonce: &[u8], now: u64, expires_at: u64) -> bool {
+        while let Some((expiry, _)) = self.expiries.front() {
+            if *expiry >= now {
+                break;
+            }
+            let (_, expired) = self.expiries.pop_front().expect("front exists");
+            self.seen.remove(&expired);
+        }
+
+        if !self.seen.insert(nonce.into()) {
+            return false;
+        }
+        self.expiries.push_back((expires_at, nonce.into()));
+        true
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn message<B>(request: &http::Request<B>, timestamp: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let path = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path| path.as_str());
+
+    let mut message = Vec::with_capacity(timestamp.len() + nonce.len() + path.len() + 16);
+    message.extend_from_slice(timestamp);
+    message.push(b' ');
+    message.extend_from_slice(nonce);
+    message.push(b' ');
+    message.extend_from_slice(request.method().as_str().as_bytes());
+    message.push(b' ');
+    message.extend_from_slice(path.as_bytes());
+    message
+}
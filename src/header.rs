@@ -1,5 +1,90 @@
-use http::HeaderName;
+use http::{HeaderName, HeaderValue};
 
 pub const SEC_FETCH_SITE: HeaderName = HeaderName::from_static("sec-fetch-site");
 pub const SEC_FETCH_MODE: HeaderName = HeaderName::from_static("sec-fetch-mode");
 pub const SEC_FETCH_DEST: HeaderName = HeaderName::from_static("sec-fetch-dest");
+pub const SEC_FETCH_USER: HeaderName = HeaderName::from_static("sec-fetch-user");
+pub const CROSS_ORIGIN_OPENER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-opener-policy");
+pub const CROSS_ORIGIN_EMBEDDER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-embedder-policy");
+pub const CROSS_ORIGIN_RESOURCE_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-resource-policy");
+pub const UPGRADE_INSECURE_REQUESTS: HeaderName =
+    HeaderName::from_static("upgrade-insecure-requests");
+pub const X_DENIAL_ID: HeaderName = HeaderName::from_static("x-denial-id");
+pub const X_SEC_FETCH_WOULD_BLOCK: HeaderName = HeaderName::from_static("x-sec-fetch-would-block");
+pub const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+pub const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+// Values of the Fetch Metadata headers, compared against request headers without allocating
+
+pub const SAME_ORIGIN: HeaderValue = HeaderValue::from_static("same-origin");
+pub const SAME_SITE: HeaderValue = HeaderValue::from_static("same-site");
+pub const CROSS_SITE: HeaderValue = HeaderValue::from_static("cross-site");
+pub const NONE: HeaderValue = HeaderValue::from_static("none");
+pub const CORS: HeaderValue = HeaderValue::from_static("cors");
+pub const NAVIGATE: HeaderValue = HeaderValue::from_static("navigate");
+pub const NO_CORS: HeaderValue = HeaderValue::from_static("no-cors");
+pub const WEBSOCKET: HeaderValue = HeaderValue::from_static("websocket");
+pub const DOCUMENT: HeaderValue = HeaderValue::from_static("document");
+pub const EMPTY: HeaderValue = HeaderValue::from_static("empty");
+pub const IFRAME: HeaderValue = HeaderValue::from_static("iframe");
+pub const USER_ACTIVATED: HeaderValue = HeaderValue::from_static("?1");
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+    use crate::{Dest, Mode, Site};
+
+    #[test]
+    fn it_defines_the_fetch_metadata_values() {
+        for (constant, value) in [
+            (SAME_ORIGIN, Site::SameOrigin.as_str()),
+            (SAME_SITE, Site::SameSite.as_str()),
+            (CROSS_SITE, Site::CrossSite.as_str()),
+            (NONE, Site::None.as_str()),
+            (CORS, Mode::Cors.as_str()),
+            (NAVIGATE, Mode::Navigate.as_str()),
+            (NO_CORS, Mode::NoCors.as_str()),
+            (WEBSOCKET, Mode::Websocket.as_str()),
+            (DOCUMENT, Dest::Document.as_str()),
+            (EMPTY, Dest::Empty.as_str()),
+            (IFRAME, Dest::Iframe.as_str()),
+            (USER_ACTIVATED, "?1"),
+        ] {
+            check!(constant == value);
+            check!(constant.as_bytes() == value.as_bytes());
+        }
+    }
+
+    #[test]
+    fn it_compares_values_like_strings() {
+        for (header, value) in [
+            ("cors", "cors"),
+            ("CORS", "cors"),
+            ("Navigate", "navigate"),
+            ("navigate, navigate", "navigate"),
+            ("navigate,cors", "navigate"),
+            (" cors", "cors"),
+            ("?1", "?1"),
+            ("?0", "?1"),
+        ] {
+            let header = HeaderValue::from_static(header);
+            let constant = HeaderValue::from_static(value);
+
+            // the comparisons replaced by the constants
+            check!((header == constant) == (header == value), "{header:?}");
+            check!(
+                (header.as_bytes() == constant.as_bytes()) == (header.to_str().unwrap() == value),
+                "{header:?}"
+            );
+        }
+
+        check!(HeaderValue::from_static("CORS") != CORS);
+        check!(HeaderValue::from_static("cors, cors") != CORS);
+        check!(HeaderValue::from_bytes(b"cors\xff").unwrap() != CORS);
+    }
+}
@@ -0,0 +1,171 @@
+//! HMAC signing backing the signed features of this crate
+
+use std::sync::{Arc, RwLock};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Hash function used to compute an HMAC
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HmacAlgorithm {
+    /// HMAC-SHA256, as defined in [RFC 4231](https://www.rfc-editor.org/rfc/rfc4231)
+    #[default]
+    Sha256,
+}
+
+impl HmacAlgorithm {
+    pub(crate) fn sign(&self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => hmac_sha256(key, message).finalize().into_bytes().to_vec(),
+        }
+    }
+
+    /// Verifies `signature` in constant time
+    pub(crate) fn verify(&self, key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            Self::Sha256 => hmac_sha256(key, message).verify_slice(signature).is_ok(),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac
+}
+
+/// Set of HMAC keys identified by id, supporting rotation
+///
+/// The newest key signs new tokens, while every key in the keyring is accepted for verification.
+/// Cloning the keyring shares the underlying keys, so keys can be rotated and retired while the layer is running.
+///
+/// ```
+/// use tower_sec_fetch::Keyring;
+///
+/// let keyring = Keyring::new("2025-01", b"old-secret".to_vec());
+///
+/// // start signing with the new key, while still accepting tokens signed with the old one
+/// keyring.rotate("2025-02", b"new-secret".to_vec());
+///
+/// // once all the old tokens have expired
+/// keyring.retire("2025-01");
+/// ```
+#[derive(Clone)]
+pub struct Keyring(Arc<RwLock<Vec<Key>>>);
+
+type Key = (Arc<str>, Arc<[u8]>);
+
+impl Keyring {
+    pub fn new(id: impl Into<Arc<str>>, key: impl Into<Arc<[u8]>>) -> Self {
+        Self(Arc::new(RwLock::new(vec![(id.into(), key.into())])))
+    }
+
+    /// Adds a new key, which becomes the one used for signing
+    ///
+    /// Ids must not contain `.`, as they are embedded in the signed tokens.
+    pub fn rotate(&self, id: impl Into<Arc<str>>, key: impl Into<Arc<[u8]>>) {
+        let id = id.into();
+        let mut keys = self.0.write().expect("keyring lock poisoned");
+        keys.retain(|(existing, _)| *existing != id);
+        keys.push((id, key.into()));
+    }
+
+    /// Removes a key, so that it's no longer accepted for verification
+    ///
+    /// The last remaining key cannot be retired.
+    pub fn retire(&self, id: &str) {
+        let mut keys = self.0.write().expect("keyring lock poisoned");
+        if keys.len() > 1 {
+            keys.retain(|(existing, _)| &**existing != id);
+        }
+    }
+
+    /// The id and key currently used for signing
+    pub(crate) fn signing_key(&self) -> Key {
+        let keys = self.0.read().expect("keyring lock poisoned");
+        keys.last().cloned().expect("keyring is never empty")
+    }
+
+    #[cfg(any(feature = "exemptions", feature = "reports"))]
+    pub(crate) fn key(&self, id: &str) -> Option<Arc<[u8]>> {
+        let keys = self.0.read().expect("keyring lock poisoned");
+        keys.iter()
+            .find(|(existing, _)| &**existing == id)
+            .map(|(_, key)| key.clone())
+    }
+
+    #[cfg(feature = "gateway")]
+    pub(crate) fn keys(&self) -> Vec<Arc<[u8]>> {
+        let keys = self.0.read().expect("keyring lock poisoned");
+        keys.iter().rev().map(|(_, key)| key.clone()).collect()
+    }
+}
+
+impl From<Vec<u8>> for Keyring {
+    fn from(key: Vec<u8>) -> Self {
+        Self::new("default", key)
+    }
+}
+
+impl From<&[u8]> for Keyring {
+    fn from(key: &[u8]) -> Self {
+        Self::new("default", key)
+    }
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let mut output = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        output.push(DIGITS[(byte >> 4) as usize] as char);
+        output.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+    output
+}
+
+pub(crate) fn hex_decode(input: &[u8]) -> Option<Vec<u8>> {
+    fn nibble(c: u8) -> Option<u8> {
+        match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    if input.len() % 2 != 0 {
+        return None;
+    }
+
+    input
+        .chunks(2)
+        .map(|pair| Some((nibble(pair[0])? << 4) | nibble(pair[1])?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_matches_the_rfc_4231_test_vectors() {
+        let signature = HmacAlgorithm::Sha256.sign(&[0x0b; 20], b"Hi There");
+        check!(
+            hex_encode(&signature)
+                == "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+
+        let signature = HmacAlgorithm::Sha256.sign(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        check!(
+            hex_encode(&signature)
+                == "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
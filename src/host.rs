@@ -0,0 +1,64 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    Policy,
+    authorizer::{host_matches, request_hostname},
+};
+
+/// Selects the evaluation policy by the host of the request, so that a single layer can serve many tenant domains
+///
+/// When attached to a [SecFetchLayer](crate::SecFetchLayer) with [with_host_policies](crate::SecFetchLayer::with_host_policies),
+/// the policy configured for the request host is used instead of the layer's own policy.
+/// Hosts are matched case-insensitively and regardless of the port, and a leading `*.` matches any subdomain.
+/// Exact hosts take precedence over wildcards, which are matched in order. Other hosts use the layer's policy.
+///
+/// Per-tenant exemptions can be configured with a [HostPathAuthorizer](crate::HostPathAuthorizer).
+///
+/// ```
+/// use tower_sec_fetch::{HostPathAuthorizer, HostPolicyMap, Policy, SecFetchLayer};
+///
+/// let policies = HostPolicyMap::new()
+///     .host("admin.example.com", Policy::strict())
+///     .host("*.tenants.example.com", Policy::website());
+///
+/// SecFetchLayer::default()
+///     .with_host_policies(policies)
+///     .with_authorizer(HostPathAuthorizer::new([("acme.tenants.example.com", "/webhooks/*")]));
+/// ```
+#[derive(Default)]
+pub struct HostPolicyMap {
+    hosts: HashMap<String, Policy>,
+    wildcards: Vec<(Cow<'static, str>, Policy)>,
+}
+
+impl HostPolicyMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies `policy` to requests whose host matches `host`
+    pub fn host(mut self, host: impl Into<Cow<'static, str>>, policy: Policy) -> Self {
+        let host = host.into();
+        if host.starts_with("*.") {
+            self.wildcards.push((host, policy));
+        } else {
+            self.hosts.insert(host.to_ascii_lowercase(), policy);
+        }
+        self
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.hosts.len() + self.wildcards.len()
+    }
+
+    pub(crate) fn policy_for<B>(&self, request: &http::Request<B>) -> Option<&Policy> {
+        let host = request_hostname(request)?;
+
+        self.hosts.get(&host.to_ascii_lowercase()).or_else(|| {
+            self.wildcards
+                .iter()
+                .find(|(pattern, _)| host_matches(pattern, host))
+                .map(|(_, policy)| policy)
+        })
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+use pin_project_lite::pin_project;
+use tower::{Layer, Service};
+
+use crate::header;
+
+/// Layer that applies [SecFetchResponse], setting the isolation headers that complement Fetch Metadata validation on responses
+///
+/// By default, it sets `cross-origin-opener-policy: same-origin`, `cross-origin-resource-policy: same-origin`,
+/// and `x-frame-options: DENY`. `cross-origin-embedder-policy` is not set by default, as `require-corp` blocks
+/// cross-origin resources that do not opt in. Headers already set by the inner service are left untouched.
+///
+/// ```
+/// use tower::ServiceBuilder;
+/// use tower_sec_fetch::{SecFetchLayer, SecFetchResponseLayer};
+///
+/// let middleware = ServiceBuilder::new()
+///     .layer(SecFetchResponseLayer::new().cross_origin_embedder_policy(Some("require-corp")))
+///     .layer(SecFetchLayer::default());
+/// ```
+#[derive(Clone)]
+pub struct SecFetchResponseLayer {
+    headers: Arc<HeaderMap>,
+}
+
+impl Default for SecFetchResponseLayer {
+    fn default() -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CROSS_ORIGIN_OPENER_POLICY,
+            HeaderValue::from_static("same-origin"),
+        );
+        headers.insert(
+            header::CROSS_ORIGIN_RESOURCE_POLICY,
+            HeaderValue::from_static("same-origin"),
+        );
+        headers.insert(
+            http::header::X_FRAME_OPTIONS,
+            HeaderValue::from_static("DENY"),
+        );
+
+        Self {
+            headers: Arc::new(headers),
+        }
+    }
+}
+
+impl SecFetchResponseLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `cross-origin-opener-policy` header, or removes it when `None`
+    pub fn cross_origin_opener_policy(self, value: Option<&'static str>) -> Self {
+        self.header(header::CROSS_ORIGIN_OPENER_POLICY, value)
+    }
+
+    /// Sets the `cross-origin-embedder-policy` header, or removes it when `None`
+    pub fn cross_origin_embedder_policy(self, value: Option<&'static str>) -> Self {
+        self.header(header::CROSS_ORIGIN_EMBEDDER_POLICY, value)
+    }
+
+    /// Sets the `cross-origin-resource-policy` header, or removes it when `None`
+    ///
+    /// Use `same-site` or `cross-origin` for resources allowed cross-site by the request policy,
+    /// e.g. with [allow_static_assets](crate::PolicyBuilder::allow_static_assets).
+    pub fn cross_origin_resource_policy(self, value: Option<&'static str>) -> Self {
+        self.header(header::CROSS_ORIGIN_RESOURCE_POLICY, value)
+    }
+
+    /// Sets the `x-frame-options` header, or removes it when `None`
+    ///
+    /// Remove it for pages allowed in cross-site frames by [allow_embedding](crate::PolicyBuilder::allow_embedding).
+    pub fn x_frame_options(self, value: Option<&'static str>) -> Self {
+        self.header(http::header::X_FRAME_OPTIONS, value)
+    }
+
+    fn header(mut self, name: HeaderName, value: Option<&'static str>) -> Self {
+        let headers = Arc::make_mut(&mut self.headers);
+        match value {
+            Some(value) => {
+                headers.insert(name, HeaderValue::from_static(value));
+            }
+            None => {
+                headers.remove(name);
+            }
+        }
+        self
+    }
+}
+
+impl<S> Layer<S> for SecFetchResponseLayer {
+    type Service = SecFetchResponse<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecFetchResponse {
+            headers: self.headers.clone(),
+            inner,
+        }
+    }
+}
+
+/// Middleware setting isolation headers on responses, see [SecFetchResponseLayer]
+#[derive(Clone)]
+pub struct SecFetchResponse<S> {
+    headers: Arc<HeaderMap>,
+    inner: S,
+}
+
+impl<S, ReqB, ResB> Service<http::Request<ReqB>> for SecFetchResponse<S>
+where
+    S: Service<http::Request<ReqB>, Response = http::Response<ResB>>,
+{
+    type Response = S::Response;
+
+    type Error = S::Error;
+
+    type Future = SecFetchResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqB>) -> Self::Future {
+        SecFetchResponseFuture {
+            inner: self.inner.call(request),
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+pin_project! {
+    /// Response future of [SecFetchResponse]
+    pub struct SecFetchResponseFuture<F> {
+        #[pin]
+        inner: F,
+        headers: Arc<HeaderMap>,
+    }
+}
+
+impl<F, B, E> Future for SecFetchResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut response = ready!(this.inner.poll(cx))?;
+
+        for (name, value) in this.headers.iter() {
+            response
+                .headers_mut()
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
+
+        Poll::Ready(Ok(response))
+    }
+}
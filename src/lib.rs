@@ -37,7 +37,7 @@
 //! # use tower_sec_fetch::SecFetchLayer;
 //! #
 //! # fn main() {
-//! let routes = axum::Router::new()
+//! let routes = ::axum::Router::new()
 //!     .route("/hello", get(async || "hello"))
 //!     .layer(SecFetchLayer::default());
 //! #
@@ -52,7 +52,7 @@
 //! # use tower_sec_fetch::SecFetchLayer;
 //! #
 //! # fn main() {
-//! let routes = axum::Router::new()
+//! let routes = ::axum::Router::new()
 //!     .route("/hello", get(async || "hello"))
 //!     .route("/unprotected", get(async || "unprotected"))
 //!     .layer(SecFetchLayer::default().allowing(["/unprotected"]));
@@ -103,6 +103,74 @@
 //! SecFetchLayer::default().no_enforce().with_reporter(LogReporter);
 //! ```
 //!
+//! Simple reporters can also be closures wrapped in a [FnReporter].
+//!
+//! Denied requests receive an empty `403 Forbidden` response by default. You can return a different response with a custom [SecFetchResponder], or a closure.
+//!
+//! ```
+//! use http::StatusCode;
+//! use tower_sec_fetch::{DenialContext, SecFetchLayer};
+//!
+//! SecFetchLayer::default().on_denied(|_: &DenialContext<'_>| {
+//!     http::Response::builder()
+//!         .status(StatusCode::NOT_FOUND)
+//!         .body(String::from("not found"))
+//!         .unwrap()
+//! });
+//! ```
+//!
+//! The status code of the denial can be chosen based on the [DenialReason].
+//!
+//! ```
+//! use http::StatusCode;
+//! use tower_sec_fetch::{DenialReason, SecFetchLayer};
+//!
+//! SecFetchLayer::default().status_for(|reason| match reason {
+//!     DenialReason::MissingMetadata => StatusCode::BAD_REQUEST,
+//!     _ => StatusCode::FORBIDDEN,
+//! });
+//! ```
+//!
+//! Pure JSON APIs can use [api_default](SecFetchLayer::api_default), which rejects requests without Fetch Metadata headers, denies cross-site navigations, and returns `application/problem+json` errors.
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::api_default();
+//! ```
+//!
+//! JSON APIs can opt into an [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457) `application/problem+json` body describing why the request was denied.
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::default().problem_json();
+//! ```
+//!
+//! Denied navigations can also be redirected to a landing page, while other requests still receive a `403 Forbidden`.
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::default().redirect_on_deny("/blocked");
+//! ```
+//!
+//! For full control, denied requests can be forwarded to a fallback [Service] with [with_denied_service](SecFetchLayer::with_denied_service). The [DenialReason] is available in the request extensions.
+//!
+//! ```
+//! # use std::convert::Infallible;
+//! # use tower_sec_fetch::{DenialReason, SecFetchLayer};
+//! #
+//! let fallback = tower::service_fn(async |request: http::Request<String>| {
+//!     let reason = request.extensions().get::<DenialReason>();
+//!     eprintln!("request was denied: {reason:?}");
+//!
+//!     Ok::<_, Infallible>(http::Response::new(String::from("denied")))
+//! });
+//!
+//! SecFetchLayer::default().with_denied_service(fallback);
+//! ```
+//!
 //! [Safe methods](https://developer.mozilla.org/en-US/docs/Glossary/Safe/HTTP) are not allowed for cross-origin requests, but this can optionally be disabled by setting the [allow_safe_methods](PolicyBuilder::allow_safe_methods) flag on the evaluation policy.
 //!
 //! ```
@@ -113,6 +181,29 @@
 //! });
 //! ```
 //!
+//! The set of safe methods can be customized, for example to support WebDAV.
+//!
+//! ```
+//! # use http::Method;
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.safe_methods([
+//!         Method::GET,
+//!         Method::HEAD,
+//!         Method::from_bytes(b"PROPFIND").unwrap(),
+//!     ]);
+//! });
+//! ```
+//!
+//! Instead of toggling individual flags, you can start from one of the [Policy] presets.
+//!
+//! ```
+//! # use tower_sec_fetch::{Policy, SecFetchLayer};
+//! #
+//! SecFetchLayer::default().with_policy(Policy::api());
+//! ```
+//!
 //! If the Fetch Metadata headers are missing, the request is allowed. This can be disabled by setting the [reject_missing_metadata](PolicyBuilder::reject_missing_metadata) flag on the evaluation policy.
 //!
 //! ```
@@ -123,54 +214,412 @@
 //! });
 //! ```
 //!
+//! Subtrees serving uploaded or user-generated content can be hardened further by marking them as untrusted with [untrusted_paths](PolicyBuilder::untrusted_paths). Cross-site requests loading them as a `document` or `iframe` are always denied.
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.untrusted_paths(["/uploads/", "/render/"]);
+//! });
+//! ```
+//!
+//! Cross-site navigations are allowed by default, which is not safe for endpoints that change state on `GET`. They can be denied with [deny_navigation_to](PolicyBuilder::deny_navigation_to).
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.deny_navigation_to(["/logout", "/account/delete-confirm"]);
+//! });
+//! ```
+//!
+//! Navigations to admin surfaces can be required to be triggered by the user, rejecting script-driven ones, with [require_user_activation](PolicyBuilder::require_user_activation).
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.require_user_activation(["/admin/**"]);
+//! });
+//! ```
+//!
+//! Embeddable widgets can be loaded in `<iframe>` elements from an allowlist of partner origins, only under a given path prefix, with [allow_embedding](PolicyBuilder::allow_embedding).
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.allow_embedding("/embed/", ["https://partner.example.com"]);
+//! });
+//! ```
+//!
+//! When an HTTP to HTTPS or host canonicalization redirect middleware is placed below this layer, denied requests to non-canonical origins can be let through with [canonical_origin](PolicyBuilder::canonical_origin), so that they are redirected instead of denied. Any response other than a redirect is replaced with the denial.
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.canonical_origin("https://www.example.com");
+//! });
+//! ```
+//!
+//! Cross-site requests can be allowed or denied based on their `sec-fetch-dest` header, for example to let partner sites load images and fonts while never allowing plugin content.
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy
+//!         .allow_destinations("/assets/", ["image", "font"])
+//!         .deny_destinations(["object", "embed"]);
+//! });
+//! ```
+//!
+//! For full control, the decision table mapping the `sec-fetch-dest`, `sec-fetch-mode`, and `sec-fetch-site` headers to a verdict can be replaced with a [Matrix].
+//!
+//! ```
+//! # use tower_sec_fetch::{Action, Matrix, Mode, SecFetchLayer, Site};
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.matrix(
+//!         Matrix::default()
+//!             .set_mode(Mode::Cors, Site::CrossSite, Action::Allow)
+//!             .set_mode(Mode::Websocket, Site::SameSite, Action::Deny),
+//!     );
+//! });
+//! ```
+//!
+//! Rules based on the Fetch Metadata headers can be added to the evaluation policy with [custom](PolicyBuilder::custom).
+//!
+//! ```
+//! # use tower_sec_fetch::{Dest, FetchMetadata, RequestHead, RuleDecision, SecFetchLayer, Site};
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy.custom(|metadata: &FetchMetadata, request: &RequestHead<'_>| {
+//!         if metadata.site == Site::CrossSite
+//!             && metadata.dest == Some(Dest::Image)
+//!             && request.uri().path().starts_with("/avatars/")
+//!         {
+//!             return RuleDecision::Allow;
+//!         }
+//!
+//!         RuleDecision::Continue
+//!     });
+//! });
+//! ```
+//!
+//! Cross-origin requests from partner sites can be allowed with [trusted_origins](PolicyBuilder::trusted_origins).
+//! With the `tower-http` feature, `SecFetchLayer::cors_layer` builds a `CorsLayer` allowing the same origins,
+//! so that the CORS and CSRF policies cannot drift apart.
+//!
+//! Mixed Go and Rust deployments can opt into the exact semantics of Go's [`http.CrossOriginProtection`](https://pkg.go.dev/net/http#CrossOriginProtection) with [go_compat](PolicyBuilder::go_compat).
+//!
+//! ```
+//! # use tower_sec_fetch::SecFetchLayer;
+//! #
+//! SecFetchLayer::new(|policy| {
+//!     policy
+//!         .go_compat()
+//!         .trusted_origins(["https://partner.example.com"]);
+//! });
+//! ```
+//!
 //! [Tower]: https://docs.rs/tower
 //! [Cross-Site-Request-Forgery]: https://developer.mozilla.org/en-US/docs/Web/Security/Attacks/CSRF
 //! [Fetch Metadata]: https://developer.mozilla.org/en-US/docs/Glossary/Fetch_metadata_request_header
 //! [tower-surf]: https://docs.rs/tower-surf
 //! [Axum]: https://docs.rs/axum
 
-use std::sync::Arc;
+#[cfg(feature = "tracing")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{borrow::Cow, sync::Arc, time::SystemTime};
 
-use futures::future::{self, Either, Ready};
-use http::StatusCode;
-use policy::Policy;
+use http::{HeaderValue, StatusCode};
 use tower::{Layer, Service};
 
+use crate::{policy::path_matches, response_headers::ResponseHeaders};
+
 pub use authorizer::*;
-pub use policy::PolicyBuilder;
+#[cfg(feature = "axum")]
+pub use axum::{NotVerified, RejectionResponder, SecFetchRejection};
+#[cfg(feature = "chaos")]
+pub use chaos::Chaos;
+pub use cidr::{Cidr, CidrAuthorizer, InvalidCidr};
+#[cfg(feature = "config")]
+pub use config::{ConfigRule, RuleSet};
+pub use decision_table::{Decision, DecisionTable};
+pub use denial_id::DenialId;
+pub use error::{DenialErrorLayer, DenialErrors, DenialErrorsFuture, DeniedRequest, SecFetchError};
+#[cfg(feature = "exemptions")]
+pub use exemption::*;
+pub use fallback::*;
+pub use future::SecFetchFuture;
+#[cfg(feature = "gateway")]
+pub use gateway::*;
+#[cfg(any(feature = "gateway", feature = "exemptions", feature = "reports"))]
+pub use hmac::{HmacAlgorithm, Keyring};
+pub use host::HostPolicyMap;
+pub use isolation::{SecFetchResponse, SecFetchResponseFuture, SecFetchResponseLayer};
+pub use matrix::{Action, Dest, Matrix, Mode, Site};
+pub use pipeline::{Pipeline, Step};
+pub use policy::{DenialReason, Policy, PolicyBuilder, SafeMethodsOrder, Verdict};
+#[cfg(feature = "reports")]
+pub use report::*;
 pub use reporter::*;
+pub use responder::*;
+#[cfg(feature = "router")]
+pub use router::{InvalidRoute, RouterAuthorizer};
+pub use rule::{FetchMetadata, RequestHead, RuleDecision};
+#[cfg(feature = "tracing")]
+pub use sampling::SamplingPriority;
+#[cfg(feature = "schedule")]
+pub use schedule::{InvalidCron, Schedule, TimeWindow};
+pub use severity::{Severities, Severity, SeverityRule};
+#[cfg(feature = "stats")]
+pub use stats::*;
+#[cfg(feature = "template")]
+pub use template::HtmlTemplateResponder;
+pub use user_agent::{MissingMetadata, UserAgent, UserAgentRules};
 
 mod authorizer;
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod cidr;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "tower-http")]
+mod cors;
+mod decision_table;
+mod denial_id;
+mod error;
+#[cfg(feature = "exemptions")]
+mod exemption;
+mod fallback;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod future;
+#[cfg(feature = "gateway")]
+mod gateway;
 pub mod header;
+#[cfg(any(feature = "gateway", feature = "exemptions", feature = "reports"))]
+mod hmac;
+mod host;
+mod isolation;
+mod matrix;
+mod pipeline;
 mod policy;
+#[cfg(feature = "pyo3")]
+pub mod python;
+#[cfg(feature = "reports")]
+mod report;
 mod reporter;
+mod responder;
+mod response_headers;
+#[cfg(feature = "router")]
+mod router;
+mod rule;
+#[cfg(feature = "tracing")]
+mod sampling;
+#[cfg(feature = "schedule")]
+mod schedule;
+mod severity;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "template")]
+mod template;
+mod user_agent;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 /// Layer that applies [SecFetch] which validates request against CSRF attacks
-pub struct SecFetchLayer<A = NoopAuthorizer, R = NoopReporter> {
+pub struct SecFetchLayer<
+    A = NoopAuthorizer,
+    R = NoopReporter,
+    D = ForbiddenResponder,
+    F = NoopFallback,
+> {
+    shared: Arc<Shared<A, R, D, F>>,
+}
+
+impl<A, R, D, F> Clone for SecFetchLayer<A, R, D, F> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// Configuration of the layer, shared by all the services it creates so that cloning them is a single refcount bump
+struct Shared<A, R, D, F> {
     enforce: bool,
+    panic_on_duplicate: bool,
+    denial_ids: bool,
+    close_above: Option<u64>,
+    cors_on_deny: Arc<[&'static str]>,
+    denial_errors: bool,
+    vary: bool,
+    host_policies: Option<Arc<HostPolicyMap>>,
+    report_only_paths: Arc<[&'static str]>,
+    would_block_header: bool,
+    enforce_after: Option<SystemTime>,
+    clock: fn() -> SystemTime,
+    severities: Option<Arc<Severities>>,
+    status_for: fn(DenialReason) -> StatusCode,
     policy: Policy,
     authorizer: Arc<A>,
     reporter: Arc<R>,
+    responder: Arc<D>,
+    fallback: Arc<F>,
+    /// Whether [with_denied_service](SecFetchLayer::with_denied_service) replaced the default fallback
+    has_fallback: bool,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<Chaos>>,
+    #[cfg(feature = "schedule")]
+    schedule: Option<Arc<Schedule>>,
+    #[cfg(feature = "exemptions")]
+    exemptions: Option<Arc<ExemptionTokens>>,
+    #[cfg(feature = "tracing")]
+    sampling_priority: Option<SamplingPriority>,
+    /// Whether the configuration was logged, so that it is logged once rather than for every wrapped service
+    #[cfg(feature = "tracing")]
+    announced: AtomicBool,
+}
+
+/// The parts of the [Shared] configuration that change its type
+struct Parts<A, R, D, F> {
+    authorizer: Arc<A>,
+    reporter: Arc<R>,
+    responder: Arc<D>,
+    fallback: Arc<F>,
 }
 
-impl<A, R> Clone for SecFetchLayer<A, R> {
+impl<A, R, D, F> Clone for Shared<A, R, D, F> {
     fn clone(&self) -> Self {
         Self {
             enforce: self.enforce,
-            policy: self.policy,
+            panic_on_duplicate: self.panic_on_duplicate,
+            denial_ids: self.denial_ids,
+            close_above: self.close_above,
+            cors_on_deny: self.cors_on_deny.clone(),
+            denial_errors: self.denial_errors,
+            vary: self.vary,
+            host_policies: self.host_policies.clone(),
+            report_only_paths: self.report_only_paths.clone(),
+            would_block_header: self.would_block_header,
+            enforce_after: self.enforce_after,
+            clock: self.clock,
+            severities: self.severities.clone(),
+            status_for: self.status_for,
+            policy: self.policy.clone(),
             authorizer: self.authorizer.clone(),
             reporter: self.reporter.clone(),
+            responder: self.responder.clone(),
+            fallback: self.fallback.clone(),
+            has_fallback: self.has_fallback,
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos.clone(),
+            #[cfg(feature = "schedule")]
+            schedule: self.schedule.clone(),
+            #[cfg(feature = "exemptions")]
+            exemptions: self.exemptions.clone(),
+            #[cfg(feature = "tracing")]
+            sampling_priority: self.sampling_priority,
+            #[cfg(feature = "tracing")]
+            announced: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<A, R, D, F> Shared<A, R, D, F> {
+    /// Builds the response denying the request with the responder
+    fn denial_response<B, ResB>(
+        &self,
+        request: &http::Request<B>,
+        reason: DenialReason,
+        headers: ResponseHeaders,
+    ) -> http::Response<ResB>
+    where
+        D: SecFetchResponder<ResB>,
+    {
+        let mut response = self.responder.respond(&DenialContext::new(
+            request,
+            reason,
+            (self.status_for)(reason),
+        ));
+
+        if self
+            .close_above
+            .is_some_and(|limit| content_length(request).is_none_or(|length| length > limit))
+        {
+            if request.version() < http::Version::HTTP_2 {
+                response
+                    .headers_mut()
+                    .insert(http::header::CONNECTION, HeaderValue::from_static("close"));
+            } else {
+                response.extensions_mut().insert(AbortStream);
+            }
+        }
+
+        if let Some(id) = request.extensions().get::<DenialId>() {
+            response
+                .headers_mut()
+                .insert(header::X_DENIAL_ID, id.to_header_value());
+        }
+
+        headers.apply(response.headers_mut());
+        if self.denial_errors {
+            let denied = DeniedRequest::new(request, reason, response.status());
+            response.extensions_mut().insert(denied);
+        }
+
+        if !self.cors_on_deny.is_empty() {
+            allow_cors(request, response.headers_mut(), &self.cors_on_deny);
         }
+
+        response
     }
 }
 
 impl Default for SecFetchLayer {
     fn default() -> Self {
-        Self {
+        let shared = Shared {
             enforce: true,
+            panic_on_duplicate: false,
+            denial_ids: false,
+            close_above: None,
+            cors_on_deny: Arc::new([]),
+            denial_errors: false,
+            vary: false,
+            host_policies: None,
+            report_only_paths: Arc::new([]),
+            would_block_header: false,
+            enforce_after: None,
+            clock: SystemTime::now,
+            severities: None,
+            status_for: |_| StatusCode::FORBIDDEN,
             policy: Policy::default(),
             authorizer: Arc::new(NoopAuthorizer),
             reporter: Arc::new(NoopReporter),
+            responder: Arc::new(ForbiddenResponder),
+            fallback: Arc::new(NoopFallback),
+            has_fallback: false,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+            #[cfg(feature = "schedule")]
+            schedule: None,
+            #[cfg(feature = "exemptions")]
+            exemptions: None,
+            #[cfg(feature = "tracing")]
+            sampling_priority: None,
+            #[cfg(feature = "tracing")]
+            announced: AtomicBool::new(false),
+        };
+
+        Self {
+            shared: Arc::new(shared),
         }
     }
 }
@@ -180,310 +629,4534 @@ impl SecFetchLayer {
     where
         F: FnOnce(&mut PolicyBuilder),
     {
-        let mut builder = PolicyBuilder::new();
-        make_policy(&mut builder);
-        let policy = builder.build();
-        Self {
-            policy,
-            ..Default::default()
-        }
+        Self::default().with_policy(Policy::new(make_policy))
+    }
+}
+
+impl SecFetchLayer<NoopAuthorizer, NoopReporter, ProblemJsonResponder> {
+    /// A layer tuned for JSON APIs, using the [api](Policy::api) policy and [problem_json](SecFetchLayer::problem_json) responses
+    pub fn api_default() -> Self {
+        SecFetchLayer::default()
+            .with_policy(Policy::api())
+            .problem_json()
     }
 }
 
-impl<OldA, OldR> SecFetchLayer<OldA, OldR> {
-    pub fn allowing(
+impl<OldA, OldR, OldD, OldF> SecFetchLayer<OldA, OldR, OldD, OldF> {
+    /// The configuration of the layer, cloned if it is shared with services or other layers
+    fn shared_mut(&mut self) -> &mut Shared<OldA, OldR, OldD, OldF> {
+        let shared = Arc::make_mut(&mut self.shared);
+        #[cfg(feature = "tracing")]
+        shared.announced.store(false, Ordering::Relaxed);
+        shared
+    }
+
+    /// Replaces the generic parts of the configuration, keeping everything else
+    fn map_parts<A, R, D, F>(
         self,
-        paths: impl Into<Arc<[&'static str]>>,
-    ) -> SecFetchLayer<PathAuthorizer, OldR> {
+        map: impl FnOnce(Parts<OldA, OldR, OldD, OldF>) -> Parts<A, R, D, F>,
+    ) -> SecFetchLayer<A, R, D, F> {
+        let shared = Arc::unwrap_or_clone(self.shared);
+        let parts = map(Parts {
+            authorizer: shared.authorizer,
+            reporter: shared.reporter,
+            responder: shared.responder,
+            fallback: shared.fallback,
+        });
+
+        SecFetchLayer {
+            shared: Arc::new(Shared {
+                enforce: shared.enforce,
+                panic_on_duplicate: shared.panic_on_duplicate,
+                denial_ids: shared.denial_ids,
+                close_above: shared.close_above,
+                cors_on_deny: shared.cors_on_deny,
+                denial_errors: shared.denial_errors,
+                vary: shared.vary,
+                host_policies: shared.host_policies,
+                report_only_paths: shared.report_only_paths,
+                would_block_header: shared.would_block_header,
+                enforce_after: shared.enforce_after,
+                clock: shared.clock,
+                severities: shared.severities,
+                status_for: shared.status_for,
+                policy: shared.policy,
+                authorizer: parts.authorizer,
+                reporter: parts.reporter,
+                responder: parts.responder,
+                fallback: parts.fallback,
+                has_fallback: shared.has_fallback,
+                #[cfg(feature = "chaos")]
+                chaos: shared.chaos,
+                #[cfg(feature = "schedule")]
+                schedule: shared.schedule,
+                #[cfg(feature = "exemptions")]
+                exemptions: shared.exemptions,
+                #[cfg(feature = "tracing")]
+                sampling_priority: shared.sampling_priority,
+                #[cfg(feature = "tracing")]
+                announced: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn allowing<P>(
+        self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> SecFetchLayer<PathAuthorizer, OldR, OldD, OldF>
+    where
+        P: Into<Cow<'static, str>>,
+    {
         self.with_authorizer(PathAuthorizer::new(paths))
     }
 
+    /// Allows requests whose path starts with any of `prefixes`, see [PathAuthorizer::with_prefixes]
+    pub fn allowing_prefix<P>(
+        self,
+        prefixes: impl IntoIterator<Item = P>,
+    ) -> SecFetchLayer<PathAuthorizer, OldR, OldD, OldF>
+    where
+        P: Into<Cow<'static, str>>,
+    {
+        self.with_authorizer(PathAuthorizer::default().with_prefixes(prefixes))
+    }
+
+    /// Replaces the evaluation policy, e.g. with one of the [Policy] presets,
+    /// or with a policy built once with [Policy::new] and shared by many layers
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.shared_mut().policy = policy;
+        self
+    }
+
     pub fn no_enforce(mut self) -> Self {
-        self.enforce = false;
+        self.shared_mut().enforce = false;
         self
     }
 
-    pub fn with_authorizer<A: SecFetchAuthorizer>(self, authorizer: A) -> SecFetchLayer<A, OldR> {
-        SecFetchLayer {
-            enforce: self.enforce,
-            policy: self.policy,
-            authorizer: Arc::from(authorizer),
-            reporter: self.reporter,
-        }
+    /// Runs in report-only mode for requests whose path matches any of the given patterns,
+    /// while enforcing the policy on all other paths
+    ///
+    /// Patterns are matched like in [PathAuthorizer], where `*` matches a single path segment
+    /// and a trailing `**` matches any number of segments.
+    ///
+    /// ```
+    /// use tower_sec_fetch::SecFetchLayer;
+    ///
+    /// SecFetchLayer::default().report_only_paths(["/legacy/**"]);
+    /// ```
+    pub fn report_only_paths(mut self, paths: impl Into<Arc<[&'static str]>>) -> Self {
+        self.shared_mut().report_only_paths = paths.into();
+        self
     }
 
-    pub fn with_reporter<R: SecFetchReporter>(self, reporter: R) -> SecFetchLayer<OldA, R> {
-        SecFetchLayer {
-            enforce: self.enforce,
-            policy: self.policy,
-            authorizer: self.authorizer,
-            reporter: Arc::from(reporter),
-        }
+    /// Adds the `x-sec-fetch-would-block` header to the responses of requests that would have been denied
+    /// in report-only mode, set to the [DenialReason], so that would-be blocks can be detected by clients
+    /// and synthetic tests without reading the server logs
+    ///
+    /// It only applies with [no_enforce](SecFetchLayer::no_enforce) or before the [enforce_after](SecFetchLayer::enforce_after) cutover.
+    /// Requests allowed by the [AfterReport](SafeMethodsOrder::AfterReport) safe methods do not receive it.
+    pub fn would_block_header(mut self) -> Self {
+        self.shared_mut().would_block_header = true;
+        self
     }
-}
 
-impl<A, R, S> Layer<S> for SecFetchLayer<A, R> {
-    type Service = SecFetch<A, R, S>;
+    /// Appends `vary: sec-fetch-site, sec-fetch-mode, sec-fetch-dest` to the responses of requests evaluated by the policy,
+    /// so that shared caches do not serve a response allowed for some Fetch Metadata to a request with different ones
+    ///
+    /// Requests skipping the policy, such as unprotected requests or requests allowed by the authorizer,
+    /// and responses of the [fallback](SecFetchLayer::with_denied_service) service are left untouched.
+    pub fn vary(mut self) -> Self {
+        self.shared_mut().vary = true;
+        self
+    }
 
-    fn layer(&self, inner: S) -> Self::Service {
-        SecFetch {
-            enforce: self.enforce,
-            policy: self.policy,
-            authorizer: self.authorizer.clone(),
-            reporter: self.reporter.clone(),
-            inner,
-        }
+    /// Runs in report-only mode until `time`, then starts enforcing automatically,
+    /// so that the cutover of many services can be scheduled in a single rollout
+    ///
+    /// It has no effect together with [no_enforce](SecFetchLayer::no_enforce).
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    ///
+    /// use tower_sec_fetch::SecFetchLayer;
+    ///
+    /// // 2030-01-01T00:00:00Z
+    /// let cutover = UNIX_EPOCH + Duration::from_secs(1_893_456_000);
+    ///
+    /// SecFetchLayer::default().enforce_after(cutover);
+    /// ```
+    pub fn enforce_after(mut self, time: SystemTime) -> Self {
+        self.shared_mut().enforce_after = Some(time);
+        self
     }
-}
 
-/// Middleware protecting against CSRF attacks
-pub struct SecFetch<A, R, S> {
-    enforce: bool,
-    policy: Policy,
-    authorizer: Arc<A>,
-    reporter: Arc<R>,
-    inner: S,
-}
+    /// Replaces the clock used by [enforce_after](SecFetchLayer::enforce_after) and schedules,
+    /// [SystemTime::now] by default, e.g. to test a cutover
+    pub fn clock(mut self, clock: fn() -> SystemTime) -> Self {
+        self.shared_mut().clock = clock;
+        self
+    }
 
-impl<A, R, S> Clone for SecFetch<A, R, S>
-where
-    S: Clone,
-{
-    fn clone(&self) -> Self {
-        Self {
-            enforce: self.enforce,
-            policy: self.policy,
-            authorizer: self.authorizer.clone(),
-            reporter: self.reporter.clone(),
-            inner: self.inner.clone(),
-        }
+    /// Panic in debug builds when a request has already been evaluated by another enforcing [SecFetch] middleware.
+    ///
+    /// Applying the middleware twice on the same route causes double reporting and makes the final verdict
+    /// harder to reason about. Duplicates are always logged when the `tracing` feature is enabled.
+    pub fn panic_on_duplicate(mut self) -> Self {
+        self.shared_mut().panic_on_duplicate = true;
+        self
     }
-}
 
-impl<A, R, ReqB, ResB, S> Service<http::Request<ReqB>> for SecFetch<A, R, S>
-where
-    A: SecFetchAuthorizer,
-    R: SecFetchReporter,
-    S: Service<http::Request<ReqB>, Response = http::Response<ResB>>,
-    ResB: Default,
-{
-    type Response = S::Response;
+    /// Assigns a unique [DenialId] to every denied request, returned in the `x-denial-id` response header
+    ///
+    /// The id is available to reporters, responders, and fallback services in the request extensions.
+    pub fn denial_ids(mut self) -> Self {
+        self.shared_mut().denial_ids = true;
+        self
+    }
 
-    type Error = S::Error;
+    /// Closes the connection after denying a request with a `content-length` above `bytes`,
+    /// to avoid receiving a body that will be discarded
+    ///
+    /// Bodies of unknown length are considered above the limit: chunked requests, requests with an invalid
+    /// `content-length`, and HTTP/2 requests without one.
+    /// HTTP/1 responses receive the `connection: close` header. As HTTP/2 does not support it, responses instead
+    /// carry the [AbortStream] extension, which servers can use to reset the stream.
+    /// Responses of the [fallback](SecFetchLayer::with_denied_service) service are left untouched.
+    pub fn close_above(mut self, bytes: u64) -> Self {
+        self.shared_mut().close_above = Some(bytes);
+        self
+    }
+
+    /// Allows the given origins to read the denial response of their `cors` requests, so that the calling script
+    /// sees the denial status instead of an opaque network error
+    ///
+    /// The `origin` header is echoed in `access-control-allow-origin` when it is one of `origins`, and the
+    /// `x-denial-id` header is exposed when [denial_ids](SecFetchLayer::denial_ids) are enabled.
+    /// `vary: origin` is added to the denial responses of all `cors` requests.
+    /// Credentials are never allowed, so the browser still hides the denial of credentialed requests behind a network error.
+    /// Only the denial response is exposed, never the one of the inner service.
+    /// Responses of the [fallback](SecFetchLayer::with_denied_service) service are left untouched.
+    ///
+    /// ```
+    /// use tower_sec_fetch::SecFetchLayer;
+    ///
+    /// SecFetchLayer::default().cors_on_deny(["https://partner.example"]);
+    /// ```
+    pub fn cors_on_deny(mut self, origins: impl Into<Arc<[&'static str]>>) -> Self {
+        self.shared_mut().cors_on_deny = origins.into();
+        self
+    }
 
-    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+    /// Returns denials as [SecFetchError::Denied] instead of a response, for stacks that map errors
+    /// to responses in an outer layer, see [DenialErrorLayer]
+    ///
+    /// The error carries the [DeniedRequest] with the reason and status of the denial.
+    /// Responses of the [fallback](SecFetchLayer::with_denied_service) service are returned as usual.
+    ///
+    /// ```
+    /// use tower_sec_fetch::SecFetchLayer;
+    ///
+    /// let layer = SecFetchLayer::default().deny_with_error();
+    /// ```
+    pub fn deny_with_error(mut self) -> DenialErrorLayer<Self> {
+        self.shared_mut().denial_errors = true;
+        DenialErrorLayer::new(self)
+    }
 
-    #[inline]
-    fn poll_ready(
-        &mut self,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx)
+    /// Assigns a [Severity] to denied requests and exemption hits, see [Severities]
+    pub fn severities(mut self, severities: Severities) -> Self {
+        self.shared_mut().severities = Some(Arc::new(severities));
+        self
+    }
+
+    /// Chooses the status code of the denial response based on the [DenialReason], `403 Forbidden` by default
+    ///
+    /// The status is exposed to custom responders through [DenialContext::status].
+    pub fn status_for(mut self, status_for: fn(DenialReason) -> StatusCode) -> Self {
+        self.shared_mut().status_for = status_for;
+        self
+    }
+
+    pub fn with_authorizer<A: SecFetchAuthorizer>(
+        self,
+        authorizer: A,
+    ) -> SecFetchLayer<A, OldR, OldD, OldF> {
+        self.map_parts(|parts| Parts {
+            authorizer: Arc::from(authorizer),
+            reporter: parts.reporter,
+            responder: parts.responder,
+            fallback: parts.fallback,
+        })
+    }
+
+    /// Erases the types of the authorizer and reporter with [BoxAuthorizer] and [BoxReporter], see [BoxedSecFetch]
+    ///
+    /// Layers with different authorizers and reporters then share a single type, so that applications applying
+    /// many of them are not compiled once per combination. The boxed authorizer and reporter borrow the head
+    /// of the request without copying it, at the cost of a dynamic call.
+    ///
+    /// ```
+    /// use tower_sec_fetch::{BoxAuthorizer, BoxReporter, SecFetchLayer};
+    ///
+    /// let layers: [SecFetchLayer<BoxAuthorizer, BoxReporter>; 2] = [
+    ///     SecFetchLayer::default().allowing(["/webhooks"]).boxed(),
+    ///     SecFetchLayer::default().boxed(),
+    /// ];
+    /// ```
+    pub fn boxed(self) -> SecFetchLayer<BoxAuthorizer, BoxReporter, OldD, OldF>
+    where
+        OldA: SecFetchAuthorizer + Send + Sync + 'static,
+        OldR: SecFetchReporter + Send + Sync + 'static,
+    {
+        let authorizer = BoxAuthorizer::new(self.shared.authorizer.clone());
+        let reporter = BoxReporter::new(self.shared.reporter.clone());
+        self.with_authorizer(authorizer).with_reporter(reporter)
+    }
+
+    pub fn with_reporter<R: SecFetchReporter>(
+        self,
+        reporter: R,
+    ) -> SecFetchLayer<OldA, R, OldD, OldF> {
+        self.map_parts(|parts| Parts {
+            authorizer: parts.authorizer,
+            reporter: Arc::from(reporter),
+            responder: parts.responder,
+            fallback: parts.fallback,
+        })
+    }
+
+    /// Customizes the response returned for denied requests.
+    ///
+    /// It accepts any [SecFetchResponder], including closures taking a [DenialContext].
+    pub fn on_denied<D>(self, responder: D) -> SecFetchLayer<OldA, OldR, D, OldF> {
+        self.map_parts(|parts| Parts {
+            authorizer: parts.authorizer,
+            reporter: parts.reporter,
+            responder: Arc::new(responder),
+            fallback: parts.fallback,
+        })
+    }
+
+    /// Respond to denied requests with an `application/problem+json` body, see [ProblemJsonResponder]
+    pub fn problem_json(self) -> SecFetchLayer<OldA, OldR, ProblemJsonResponder, OldF> {
+        self.on_denied(ProblemJsonResponder)
+    }
+
+    /// Respond to denied requests with a [SecFetchRejection], like axum's extractor rejections
+    #[cfg(feature = "axum")]
+    pub fn axum_rejection(self) -> SecFetchLayer<OldA, OldR, RejectionResponder, OldF> {
+        self.on_denied(RejectionResponder)
+    }
+
+    /// Builds the body of denied responses with `make_body`, keeping the status configured for the denial reason,
+    /// see [MakeDenyBody]
+    ///
+    /// Unlike the default responder, it does not require the body to implement [Default].
+    ///
+    /// ```
+    /// use tower_sec_fetch::{DenialContext, SecFetchLayer};
+    ///
+    /// SecFetchLayer::default().deny_body(|denial: &DenialContext<'_>| {
+    ///     format!("denied: {}", denial.reason())
+    /// });
+    /// ```
+    pub fn deny_body<M>(
+        self,
+        make_body: M,
+    ) -> SecFetchLayer<OldA, OldR, DenyBodyResponder<M>, OldF> {
+        self.on_denied(DenyBodyResponder::new(make_body))
+    }
+
+    /// Converts the body of denied responses with `map`, so that the middleware can wrap services
+    /// whose response body is not supported by the responder, see [MapBodyResponder]
+    ///
+    /// The responder builds a body of type `B`, e.g. a [String] with [problem_json](SecFetchLayer::problem_json),
+    /// or any [Default] body with the default responder.
+    ///
+    /// ```
+    /// use tower_sec_fetch::SecFetchLayer;
+    ///
+    /// /// A streaming body without a `Default` implementation
+    /// struct StreamBody(Vec<u8>);
+    ///
+    /// SecFetchLayer::default()
+    ///     .problem_json()
+    ///     .map_denied_body(|body: String| StreamBody(body.into_bytes()));
+    /// ```
+    pub fn map_denied_body<B, M, MappedB>(
+        self,
+        map: M,
+    ) -> SecFetchLayer<OldA, OldR, MapBodyResponder<OldD, M, B>, OldF>
+    where
+        M: Fn(B) -> MappedB,
+    {
+        let responder = self.shared.responder.clone();
+        self.on_denied(MapBodyResponder::shared(responder, map))
+    }
+
+    /// Redirect denied navigation requests to `location`, see [RedirectResponder]
+    pub fn redirect_on_deny(
+        self,
+        location: &'static str,
+    ) -> SecFetchLayer<OldA, OldR, RedirectResponder, OldF> {
+        self.on_denied(RedirectResponder::new(HeaderValue::from_static(location)))
+    }
+
+    /// Forwards denied requests to a fallback `service` instead of responding directly, see [DeniedService]
+    pub fn with_denied_service<S>(
+        self,
+        service: S,
+    ) -> SecFetchLayer<OldA, OldR, OldD, DeniedService<S>> {
+        let mut layer = self.map_parts(|parts| Parts {
+            authorizer: parts.authorizer,
+            reporter: parts.reporter,
+            responder: parts.responder,
+            fallback: Arc::new(DeniedService::new(service)),
+        });
+        layer.shared_mut().has_fallback = true;
+        layer
+    }
+
+    /// Evaluates the policy for a request described by its method, path, and headers,
+    /// returning its [Verdict](Policy::verdict)
+    ///
+    /// It is a framework-agnostic entry point for simulations and bindings to other languages,
+    /// which cannot build an [http::Request]. The authorizer, exemptions, and schedule of the layer are not evaluated.
+    ///
+    /// ```
+    /// # use tower_sec_fetch::{DenialReason, SecFetchLayer, Verdict};
+    /// #
+    /// let verdict = SecFetchLayer::default().simulate(
+    ///     "POST",
+    ///     "/submit",
+    ///     &[
+    ///         ("sec-fetch-site", "cross-site"),
+    ///         ("sec-fetch-mode", "cors"),
+    ///         ("sec-fetch-dest", "empty"),
+    ///     ],
+    /// );
+    ///
+    /// assert_eq!(verdict.unwrap(), Verdict::Denied(DenialReason::CrossSite));
+    /// ```
+    pub fn simulate(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<Verdict, http::Error> {
+        let request = headers
+            .iter()
+            .fold(
+                http::Request::builder().method(method).uri(path),
+                |request, (name, value)| request.header(*name, *value),
+            )
+            .body(())?;
+
+        Ok(self.shared.policy.verdict(&request))
+    }
+
+    /// Evaluates the policy for every combination of Fetch Metadata headers and method, see [DecisionTable]
+    pub fn decision_table(&self) -> DecisionTable {
+        DecisionTable::new(&self.shared.policy)
+    }
+
+    /// Describes the steps executed for every request with the current configuration
+    pub fn pipeline(&self) -> Pipeline {
+        let name = |name: &str| name.to_string();
+        let methods = |methods: &[http::Method]| methods.iter().map(ToString::to_string).collect();
+        let policy = &self.shared.policy;
+        let mut pipeline = Pipeline::new();
+
+        if let Some(policies) = &self.shared.host_policies {
+            pipeline = pipeline.step(Step::HostPolicies {
+                hosts: policies.len(),
+            });
+        }
+
+        #[cfg(feature = "schedule")]
+        if let Some(schedule) = &self.shared.schedule {
+            pipeline = pipeline.step(Step::Schedule {
+                windows: schedule.len(),
+            });
+        }
+
+        pipeline = pipeline.step(Step::Skip);
+
+        if policy.protected_methods.is_some()
+            || policy.only_with_credentials
+            || policy.session_cookie.is_some()
+        {
+            pipeline = pipeline.step(Step::Protect {
+                methods: policy.protected_methods.as_deref().map(methods),
+                only_with_credentials: policy.only_with_credentials,
+                session_cookie: policy.session_cookie.map(String::from),
+            });
+        }
+
+        #[cfg(feature = "exemptions")]
+        if self.shared.exemptions.is_some() {
+            pipeline = pipeline.step(Step::Exemptions);
+        }
+
+        pipeline = pipeline.step(Step::Authorizer {
+            name: name(std::any::type_name::<OldA>()),
+        });
+
+        pipeline = policy.steps().into_iter().fold(pipeline, Pipeline::step);
+
+        #[cfg(feature = "chaos")]
+        if self.shared.chaos.is_some() {
+            pipeline = pipeline.step(Step::Chaos);
+        }
+
+        pipeline = pipeline.step(Step::Report {
+            name: name(std::any::type_name::<OldR>()),
+        });
+
+        pipeline = policy
+            .denial_steps()
+            .into_iter()
+            .fold(pipeline, Pipeline::step);
+
+        if !self.shared.enforce {
+            return pipeline.step(Step::ReportOnly);
+        }
+
+        if !self.shared.report_only_paths.is_empty() {
+            pipeline = pipeline.step(Step::ReportOnlyPaths {
+                paths: self
+                    .shared
+                    .report_only_paths
+                    .iter()
+                    .map(|path| path.to_string())
+                    .collect(),
+            });
+        }
+
+        if let Some(after) = self.shared.enforce_after {
+            pipeline = pipeline.step(Step::ReportOnlyUntil {
+                unix_seconds: after
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs()),
+            });
+        }
+
+        pipeline = policy
+            .enforced_denial_steps()
+            .into_iter()
+            .fold(pipeline, Pipeline::step);
+
+        if self.shared.has_fallback {
+            pipeline = pipeline.step(Step::Fallback {
+                name: name(std::any::type_name::<OldF>()),
+            });
+        }
+
+        pipeline.step(Step::Respond {
+            name: name(std::any::type_name::<OldD>()),
+        })
+    }
+
+    /// Randomly flips the evaluation policy verdicts or delays the responses, see [Chaos]
+    #[cfg(feature = "chaos")]
+    pub fn with_chaos(mut self, chaos: Chaos) -> Self {
+        self.shared_mut().chaos = Some(Arc::new(chaos));
+        self
+    }
+
+    /// Switches the evaluation policy during recurring time windows, see [Schedule]
+    #[cfg(feature = "schedule")]
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.shared_mut().schedule = Some(Arc::new(schedule));
+        self
+    }
+
+    /// Selects the evaluation policy by the host of the request, see [HostPolicyMap]
+    ///
+    /// The policy of an active [Schedule] window still takes precedence when the `schedule` feature is enabled.
+    pub fn with_host_policies(mut self, policies: HostPolicyMap) -> Self {
+        self.shared_mut().host_policies = Some(Arc::new(policies));
+        self
+    }
+
+    /// Allows requests carrying a valid signed exemption token, see [ExemptionTokens]
+    #[cfg(feature = "exemptions")]
+    pub fn with_exemptions(mut self, exemptions: ExemptionTokens) -> Self {
+        self.shared_mut().exemptions = Some(Arc::new(exemptions));
+        self
+    }
+
+    /// Records a sampling priority on the current span of denied requests, see [SamplingPriority]
+    #[cfg(feature = "tracing")]
+    pub fn sampling_priority(mut self, priority: SamplingPriority) -> Self {
+        self.shared_mut().sampling_priority = Some(priority);
+        self
+    }
+}
+
+impl<A, R, D, F, S> Layer<S> for SecFetchLayer<A, R, D, F>
+where
+    A: SecFetchAuthorizer,
+{
+    type Service = SecFetch<A, R, S, D, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        #[cfg(feature = "tracing")]
+        if !self.shared.announced.swap(true, Ordering::Relaxed) {
+            tracing::info!(
+                enforce = self.shared.enforce,
+                enforce_after = ?self.shared.enforce_after,
+                would_block_header = self.shared.would_block_header,
+                vary = self.shared.vary,
+                cors_on_deny = ?self.shared.cors_on_deny,
+                denial_errors = self.shared.denial_errors,
+                report_only_paths = ?self.shared.report_only_paths,
+                host_policies = self.shared.host_policies.as_ref().map(|policies| policies.len()),
+                reject_missing_metadata = self.shared.policy.reject_missing_metadata,
+                reject_form_posts_without_metadata = self.shared.policy.reject_form_posts_without_metadata,
+                missing_metadata_header = ?self.shared.policy.missing_metadata_header,
+                user_agent_rules = ?self.shared.policy.user_agent_rules,
+                reject_ambiguous_metadata = self.shared.policy.reject_ambiguous_metadata,
+                reject_conflicting_metadata = self.shared.policy.reject_conflicting_metadata,
+                reject_unknown_metadata = self.shared.policy.reject_unknown_metadata,
+                case_insensitive_metadata = self.shared.policy.case_insensitive_metadata,
+                allow_safe_methods = self.shared.policy.allow_safe_methods,
+                allow_preflights = self.shared.policy.allow_preflights,
+                honor_method_override = self.shared.policy.honor_method_override,
+                safe_methods = ?self.shared.policy.safe_methods,
+                safe_methods_order = ?self.shared.policy.safe_methods_order,
+                protected_methods = ?self.shared.policy.protected_methods,
+                only_with_credentials = self.shared.policy.only_with_credentials,
+                session_cookie = ?self.shared.policy.session_cookie,
+                untrusted_paths = ?self.shared.policy.untrusted_paths,
+                sensitive_paths = ?self.shared.policy.sensitive_paths,
+                user_activated_paths = ?self.shared.policy.user_activated_paths,
+                embeddable = ?self.shared.policy.embeddable,
+                matrix = ?self.shared.policy.matrix,
+                custom_rules = self.shared.policy.rules.len(),
+                allowed_destinations = ?self.shared.policy.allowed_destinations,
+                denied_destinations = ?self.shared.policy.denied_destinations,
+                go_compat = self.shared.policy.go_compat,
+                trusted_origins = ?self.shared.policy.trusted_origins,
+                canonical_origin = ?self.shared.policy.canonical_origin,
+                external_origins = ?self.shared.policy.external_origins,
+                websocket_origins = ?self.shared.policy.websocket_origins,
+                event_streams = ?self.shared.policy.event_streams,
+                exemptions = self.shared.authorizer.exemptions(),
+                authorizer = std::any::type_name::<A>(),
+                reporter = std::any::type_name::<R>(),
+                sampling_priority = ?self.shared.sampling_priority,
+                "sec-fetch layer configured in {} mode",
+                if self.shared.enforce {
+                    "enforce"
+                } else {
+                    "report-only"
+                },
+            );
+        }
+
+        SecFetch {
+            shared: self.shared.clone(),
+            inner,
+        }
+    }
+}
+
+/// Marks denial responses whose HTTP/2 stream should be reset without receiving the request body,
+/// see [close_above](SecFetchLayer::close_above)
+#[derive(Clone, Copy, Debug)]
+pub struct AbortStream;
+
+/// The length of the request body, or `None` if it is unknown
+///
+/// HTTP/1 requests without `content-length` or `transfer-encoding` have no body, while later versions
+/// can stream a body of any length without announcing it.
+fn content_length<B>(request: &http::Request<B>) -> Option<u64> {
+    let headers = request.headers();
+    if headers.contains_key(http::header::TRANSFER_ENCODING) {
+        return None;
+    }
+
+    match headers.get(http::header::CONTENT_LENGTH) {
+        Some(length) => length.to_str().ok()?.parse().ok(),
+        None if request.version() < http::Version::HTTP_2 => Some(0),
+        None => None,
+    }
+}
+
+/// Lets the origin of a `cors` request read the denial response, if it is one of `origins`
+fn allow_cors<B>(request: &http::Request<B>, headers: &mut http::HeaderMap, origins: &[&str]) {
+    let cors = request
+        .headers()
+        .get(header::SEC_FETCH_MODE)
+        .is_some_and(|mode| mode == header::CORS);
+    if !cors {
+        return;
+    }
+
+    headers.append(http::header::VARY, HeaderValue::from_static("origin"));
+    let Some(origin) = request
+        .headers()
+        .get(http::header::ORIGIN)
+        .filter(|origin| origins.iter().any(|allowed| origin == allowed))
+    else {
+        return;
+    };
+
+    headers.insert(http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    if headers.contains_key(header::X_DENIAL_ID) {
+        headers.insert(
+            http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            HeaderValue::from_static("x-denial-id"),
+        );
+    }
+}
+
+/// Marks requests that went through an enforcing [SecFetch] middleware
+#[derive(Clone, Copy)]
+struct Enforced;
+
+/// Opts a request out of the evaluation policy when present in its extensions
+///
+/// Route-specific middleware running before [SecFetch] can insert it to exempt their routes
+/// without listing their paths.
+///
+/// ```
+/// use tower_sec_fetch::SkipSecFetch;
+///
+/// let mut request = http::Request::new(());
+/// request.extensions_mut().insert(SkipSecFetch);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SkipSecFetch;
+
+/// Marks requests allowed by the evaluation policy of an enforcing [SecFetch] middleware
+///
+/// It is not inserted in requests that skipped the evaluation policy, such as unprotected requests,
+/// requests allowed by the authorizer, or requests let through by [no_enforce](SecFetchLayer::no_enforce).
+///
+/// With the `axum` feature, it can be used as an extractor rejecting requests without it,
+/// so that handlers of sensitive mutations can require the evaluation policy to have run.
+#[derive(Clone, Copy, Debug)]
+pub struct SecFetchVerified;
+
+/// [SecFetch] middleware with a type-erased authorizer and reporter, see [boxed](SecFetchLayer::boxed)
+pub type BoxedSecFetch<S, D = ForbiddenResponder, F = NoopFallback> =
+    SecFetch<BoxAuthorizer, BoxReporter, S, D, F>;
+
+/// Middleware protecting against CSRF attacks
+pub struct SecFetch<A, R, S, D = ForbiddenResponder, F = NoopFallback> {
+    shared: Arc<Shared<A, R, D, F>>,
+    inner: S,
+}
+
+impl<A, R, S, D, F> Clone for SecFetch<A, R, S, D, F>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A, R, D, F, ReqB, ResB, S> Service<http::Request<ReqB>> for SecFetch<A, R, S, D, F>
+where
+    A: SecFetchAuthorizer,
+    R: SecFetchReporter,
+    D: SecFetchResponder<ResB>,
+    F: DenialFallback<ReqB, ResB, S::Error>,
+    S: Service<http::Request<ReqB>, Response = http::Response<ResB>>,
+{
+    type Response = S::Response;
+
+    type Error = S::Error;
+
+    type Future = SecFetchFuture<S::Future, F::Future, ResB>;
+
+    #[inline]
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    #[cfg_attr(not(feature = "chaos"), allow(clippy::let_and_return))]
+    fn call(&mut self, mut request: http::Request<ReqB>) -> Self::Future {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            method = %request.method(),
+            path = request.uri().path(),
+            "processing request",
+        );
+
+        let enforce = self.shared.enforce
+            && self
+                .shared
+                .enforce_after
+                .is_none_or(|after| (self.shared.clock)() >= after)
+            && !self
+                .shared
+                .report_only_paths
+                .iter()
+                .any(|pattern| path_matches(pattern, request.uri().path()));
+
+        if enforce && request.extensions_mut().insert(Enforced).is_some() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request has already been evaluated by another sec-fetch layer",
+            );
+
+            debug_assert!(
+                !self.shared.panic_on_duplicate,
+                "request has already been evaluated by another sec-fetch layer",
+            );
+        }
+
+        let mut allow = |request: http::Request<ReqB>, headers: ResponseHeaders| {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request allowed",
+            );
+
+            SecFetchFuture::allowed(self.inner.call(request), headers)
+        };
+
+        let deny =
+            |request: http::Request<ReqB>, reason: DenialReason, headers: ResponseHeaders| {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    method = %request.method(),
+                    path = request.uri().path(),
+                    reason = reason.as_str(),
+                    "request denied",
+                );
+
+                match self.shared.fallback.call(request, reason) {
+                    Ok(future) => SecFetchFuture::fallback(future),
+                    Err(request) => SecFetchFuture::denied(
+                        self.shared.denial_response(&request, reason, headers),
+                    ),
+                }
+            };
+
+        // inserts the reason, denial id, and severity of a denied request in its extensions
+        let mark_denied = |request: &mut http::Request<ReqB>, reason: DenialReason| {
+            #[cfg(feature = "tracing")]
+            if let Some(priority) = self.shared.sampling_priority {
+                priority.record();
+            }
+
+            request.extensions_mut().insert(reason);
+
+            if self.shared.denial_ids {
+                request.extensions_mut().insert(DenialId::generate());
+            }
+
+            let Some(severities) = &self.shared.severities else {
+                return;
+            };
+
+            let severity = severities.classify(request, reason);
+            request.extensions_mut().insert(severity);
+
+            #[cfg(feature = "tracing")]
+            {
+                let method = request.method();
+                let path = request.uri().path();
+                let reason = reason.as_str();
+                match severity {
+                    Severity::Info => tracing::info!(%method, path, reason, "request denied"),
+                    Severity::Warn => tracing::warn!(%method, path, reason, "request denied"),
+                    Severity::Critical => tracing::error!(%method, path, reason, "request denied"),
+                }
+            }
+        };
+
+        let policy = self
+            .shared
+            .host_policies
+            .as_ref()
+            .and_then(|policies| policies.policy_for(&request))
+            .unwrap_or(&self.shared.policy);
+
+        #[cfg(feature = "schedule")]
+        let policy = self
+            .shared
+            .schedule
+            .as_ref()
+            .and_then(|schedule| schedule.policy_at((self.shared.clock)()))
+            .unwrap_or(policy);
+
+        if request.extensions().get::<SkipSecFetch>().is_some() {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request opted out of sec-fetch: skipped",
+            );
+
+            return allow(request, ResponseHeaders::default());
+        }
+
+        if !policy.is_protected(&request) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is not protected: skipped",
+            );
+
+            return allow(request, ResponseHeaders::default());
+        }
+
+        #[cfg(feature = "exemptions")]
+        if let Some(exemption) = self
+            .shared
+            .exemptions
+            .as_ref()
+            .and_then(|exemptions| exemptions.exemption(&request))
+        {
+            #[cfg(feature = "tracing")]
+            tracing::info!(
+                method = %request.method(),
+                path = request.uri().path(),
+                subject = exemption.subject,
+                "request carries a valid exemption token",
+            );
+
+            if let Some(severities) = &self.shared.severities {
+                request.extensions_mut().insert(severities.exemption());
+            }
+
+            self.shared
+                .reporter
+                .on_exemption_used_mut(&mut request, &exemption, rule::Sealed);
+
+            return allow(request, ResponseHeaders::default());
+        }
+
+        match self
+            .shared
+            .authorizer
+            .authorize_mut(&mut request, rule::Sealed)
+        {
+            AuthorizationDecision::Allowed => {
+                return allow(request, ResponseHeaders::default());
+            }
+            AuthorizationDecision::Denied => {
+                mark_denied(&mut request, DenialReason::Authorizer);
+
+                return deny(
+                    request,
+                    DenialReason::Authorizer,
+                    ResponseHeaders::default(),
+                );
+            }
+            AuthorizationDecision::Continue => {}
+        }
+
+        self.shared
+            .reporter
+            .on_request_evaluated_mut(&mut request, rule::Sealed);
+
+        let verdict = policy.check(&request);
+        let evaluated = ResponseHeaders {
+            would_block: None,
+            vary: self.shared.vary,
+        };
+
+        #[cfg(feature = "chaos")]
+        let (verdict, delay) = match &self.shared.chaos {
+            Some(chaos) => chaos.apply(verdict),
+            None => (verdict, None),
+        };
+
+        let future = 'verdict: {
+            let Err(reason) = verdict else {
+                if enforce {
+                    request.extensions_mut().insert(SecFetchVerified);
+                }
+
+                break 'verdict allow(request, evaluated);
+            };
+
+            mark_denied(&mut request, reason);
+
+            self.shared
+                .reporter
+                .on_request_denied_mut(&mut request, rule::Sealed);
+
+            // the request was denied, but safe methods are allowed after being reported
+            if policy.is_reported(&request) {
+                break 'verdict allow(request, evaluated);
+            }
+
+            // the request was denied, but we are not enforcing it
+            // we report the failure and let the request continue
+            if !enforce {
+                let headers = ResponseHeaders {
+                    would_block: self.shared.would_block_header.then_some(reason),
+                    ..evaluated
+                };
+
+                break 'verdict allow(request, headers);
+            }
+
+            // requests to non-canonical origins are let through to be redirected,
+            // but any other response is replaced with the denial
+            if policy.is_canonicalization(&request) {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    method = %request.method(),
+                    path = request.uri().path(),
+                    reason = reason.as_str(),
+                    "request to a non-canonical origin: denied unless redirected",
+                );
+
+                let denial = self.shared.denial_response(&request, reason, evaluated);
+                break 'verdict SecFetchFuture::redirect(self.inner.call(request), denial);
+            }
+
+            deny(request, reason, evaluated)
+        };
+
+        #[cfg(feature = "chaos")]
+        let future = future.delayed(delay);
+
+        future
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use assert2::{check, let_assert};
+    use http::{Method, StatusCode};
+    use tower::ServiceExt;
+    use tower_test::mock;
+
+    use super::*;
+
+    macro_rules! request {
+        (site => $site:expr, mode => $mode:expr, dest => $dest:expr) => {
+            request!(::http::Method::GET, "/", site => $site, mode => $mode, dest => $dest)
+        };
+
+        ($path:expr, site => $site:expr, mode => $mode:expr, dest => $dest:expr) => {
+            request!(::http::Method::GET, $path, site => $site, mode => $mode, dest => $dest)
+        };
+
+        ($method:expr, $path:expr, site => $site:expr, mode => $mode:expr, dest => $dest:expr) => {
+            ::http::Request::builder()
+                .method($method)
+                .uri(format!("https://example.com{}", $path))
+                .header(header::SEC_FETCH_SITE, $site)
+                .header(header::SEC_FETCH_MODE, $mode)
+                .header(header::SEC_FETCH_DEST, $dest)
+                .body(())
+                .unwrap()
+        };
+    }
+
+    macro_rules! assert_request {
+        ($req:expr, $assert_resp:expr) => {
+            assert_request!($req, $assert_resp, SecFetchLayer::default())
+        };
+
+        ($req:expr, $assert_resp:expr, $layer:expr) => {
+            let (service, mut handler) =
+                mock::spawn_layer::<http::Request<()>, http::Response<()>, _>($layer);
+
+            tokio::spawn(async move {
+                let_assert!(Some((_, send)) = handler.next_request().await);
+                send.send_response(http::Response::new(()));
+            });
+
+            let response = service.into_inner().oneshot($req).await.unwrap();
+
+            ($assert_resp)(response);
+        };
+    }
+
+    #[tokio::test]
+    async fn it_allows_requests_missing_the_fetch_metadata() {
+        let request = http::Request::new(());
+
+        assert_request!(request, |response: http::Response<()>| {
+            check!(response.status().is_success());
+        });
+    }
+
+    #[tokio::test]
+    async fn it_rejects_requests_missing_the_fetch_metadata_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        });
+        let request = http::Request::new(());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_same_site_requests() {
+        let request = request!(site => "same-site", mode => "navigate", dest => "document");
+
+        assert_request!(request, |response: http::Response<()>| {
+            check!(response.status().is_success());
+        });
+    }
+
+    #[tokio::test]
+    async fn it_rejects_cross_origin_requests() {
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(request, |response: http::Response<()>| {
+            check!(response.status() == StatusCode::FORBIDDEN);
+        });
+    }
+
+    #[tokio::test]
+    async fn it_allows_cross_origin_requests_safe_methods_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.allow_safe_methods();
+        });
+        let request =
+            request!(Method::GET, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_cross_origin_requests_custom_safe_methods_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.safe_methods([Method::from_bytes(b"PROPFIND").unwrap()]);
+        });
+        let request = request!(
+            Method::from_bytes(b"PROPFIND").unwrap(),
+            "/",
+            site => "cross-site",
+            mode => "cors",
+            dest => "empty"
+        );
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_reports_safe_methods_if_configured() {
+        let reporter = Arc::new(TestReporter::default());
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .allow_safe_methods()
+                .safe_methods_order(SafeMethodsOrder::AfterReport);
+        })
+        .with_reporter(reporter.clone());
+        let request =
+            request!(Method::GET, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+
+        check!(reporter.called.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_skips_unprotected_methods_if_configured() {
+        let reporter = Arc::new(TestReporter::default());
+        let layer = SecFetchLayer::new(|policy| {
+            policy.protect_methods([Method::POST, Method::PUT, Method::PATCH, Method::DELETE]);
+        })
+        .with_reporter(reporter.clone());
+        let request =
+            request!(Method::GET, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+
+        check!(!reporter.called.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_only_enforces_requests_with_credentials_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.only_with_credentials();
+        });
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(http::header::COOKIE, "session=1".parse().unwrap());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_only_enforces_requests_with_a_session_cookie_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.only_with_session_cookie("id");
+        });
+
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(http::header::COOKIE, "theme=dark; ids=1".parse().unwrap());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(http::header::COOKIE, "theme=dark; id=abc".parse().unwrap());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_adds_the_would_block_header_in_report_only_mode() {
+        let layer = SecFetchLayer::default().no_enforce().would_block_header();
+
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+                check!(response.headers()[header::X_SEC_FETCH_WOULD_BLOCK] == "cross-site");
+            },
+            layer.clone()
+        );
+
+        let request =
+            request!(Method::POST, "/", site => "same-origin", mode => "cors", dest => "empty");
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+                check!(
+                    !response
+                        .headers()
+                        .contains_key(header::X_SEC_FETCH_WOULD_BLOCK)
+                );
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_sets_isolation_headers_on_responses() {
+        let (service, mut handler) = mock::spawn_layer::<http::Request<()>, http::Response<()>, _>(
+            SecFetchResponseLayer::new()
+                .cross_origin_embedder_policy(Some("require-corp"))
+                .x_frame_options(None),
+        );
+
+        tokio::spawn(async move {
+            let_assert!(Some((_, send)) = handler.next_request().await);
+            let mut response = http::Response::new(());
+            response.headers_mut().insert(
+                header::CROSS_ORIGIN_RESOURCE_POLICY,
+                HeaderValue::from_static("cross-origin"),
+            );
+            send.send_response(response);
+        });
+
+        let response = service
+            .into_inner()
+            .oneshot(http::Request::new(()))
+            .await
+            .unwrap();
+        let headers = response.headers();
+
+        check!(headers[header::CROSS_ORIGIN_OPENER_POLICY] == "same-origin");
+        check!(headers[header::CROSS_ORIGIN_EMBEDDER_POLICY] == "require-corp");
+        check!(headers[header::CROSS_ORIGIN_RESOURCE_POLICY] == "cross-origin");
+        check!(!headers.contains_key(http::header::X_FRAME_OPTIONS));
+    }
+
+    #[tokio::test]
+    async fn it_selects_the_policy_by_host() {
+        let layer = SecFetchLayer::default().with_host_policies(
+            HostPolicyMap::new()
+                .host("Admin.example.com", Policy::strict())
+                .host(
+                    "*.tenants.example.com",
+                    Policy::new(|policy| {
+                        policy.safe_methods([Method::POST]);
+                    }),
+                ),
+        );
+
+        for (host, status) in [
+            ("admin.example.com", StatusCode::FORBIDDEN),
+            ("acme.tenants.example.com:8443", StatusCode::OK),
+            ("tenants.example.com", StatusCode::FORBIDDEN),
+            ("example.com", StatusCode::FORBIDDEN),
+        ] {
+            let mut request =
+                request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+            request
+                .headers_mut()
+                .insert(http::header::HOST, HeaderValue::from_static(host));
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status() == status, "{host}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_only_reports_denials_on_report_only_paths() {
+        let layer = SecFetchLayer::default().report_only_paths(["/legacy/**"]);
+
+        for (path, status) in [
+            ("/api/transfer", StatusCode::FORBIDDEN),
+            ("/legacy/transfer", StatusCode::OK),
+        ] {
+            let request =
+                request!(Method::POST, path, site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status() == status, "{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_builds_the_body_of_denied_responses() {
+        #[derive(Debug)]
+        struct NotDefault(&'static str);
+
+        let layer = SecFetchLayer::default()
+            .status_for(|_| StatusCode::NOT_FOUND)
+            .deny_body(|denial: &DenialContext<'_>| NotDefault(denial.reason().as_str()));
+        let (service, _handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<NotDefault>, _>(layer);
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        let response = service.into_inner().oneshot(request).await.unwrap();
+
+        check!(response.status() == StatusCode::NOT_FOUND);
+        check!(response.body().0 == "cross-site");
+    }
+
+    #[tokio::test]
+    async fn it_maps_the_body_of_denied_responses() {
+        #[derive(Debug)]
+        struct NotDefault(String);
+
+        let layer = SecFetchLayer::default()
+            .problem_json()
+            .map_denied_body(NotDefault);
+        let (service, _handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<NotDefault>, _>(layer);
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        let response = service.into_inner().oneshot(request).await.unwrap();
+
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(response.body().0.contains("cross-site"));
+    }
+
+    #[tokio::test]
+    async fn it_returns_denials_as_errors_if_configured() {
+        let layer = SecFetchLayer::default().denial_ids().deny_with_error();
+        let (service, mut handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<()>, _>(layer);
+
+        tokio::spawn(async move {
+            let (_, send) = handler.next_request().await.unwrap();
+            send.send_response(http::Response::new(()));
+        });
+
+        let request = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+        let Err(SecFetchError::Denied(denied)) =
+            service.clone().into_inner().oneshot(request).await
+        else {
+            panic!("expected the request to be denied");
+        };
+
+        check!(denied.method() == Method::POST);
+        check!(denied.uri().path() == "/submit");
+        check!(denied.reason() == DenialReason::CrossSite);
+        check!(denied.status() == StatusCode::FORBIDDEN);
+        check!(denied.id().is_some());
+
+        let request = request!(site => "same-origin", mode => "cors", dest => "empty");
+        let response = service.into_inner().oneshot(request).await.unwrap();
+        check!(response.status() == StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn it_lets_cors_callers_read_denials() {
+        let layer = SecFetchLayer::default()
+            .cors_on_deny(["https://partner.example"])
+            .denial_ids();
+
+        for (mode, origin, allowed_origin) in [
+            (
+                "cors",
+                Some("https://partner.example"),
+                Some("https://partner.example"),
+            ),
+            ("cors", Some("https://evil.example"), None),
+            ("cors", None, None),
+            ("no-cors", Some("https://partner.example"), None),
+        ] {
+            let mut request =
+                request!(Method::POST, "/", site => "cross-site", mode => mode, dest => "empty");
+            if let Some(origin) = origin {
+                request
+                    .headers_mut()
+                    .insert(http::header::ORIGIN, HeaderValue::from_static(origin));
+            }
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    let headers = response.headers();
+                    check!(response.status() == StatusCode::FORBIDDEN);
+                    check!(
+                        headers
+                            .get(http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                            .map(|origin| origin.to_str().unwrap())
+                            == allowed_origin,
+                        "{mode} {origin:?}"
+                    );
+                    check!(
+                        headers.contains_key(http::header::ACCESS_CONTROL_EXPOSE_HEADERS)
+                            == allowed_origin.is_some()
+                    );
+                    check!(!headers.contains_key(http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+                    check!(
+                        headers
+                            .get_all(http::header::VARY)
+                            .iter()
+                            .any(|vary| vary == "origin")
+                            == (mode == "cors"),
+                        "{mode} {origin:?}"
+                    );
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_adds_the_vary_header_to_evaluated_requests() {
+        let layer = SecFetchLayer::default().vary().allowing(["/webhooks"]);
+
+        for (path, site, status, vary) in [
+            ("/", "same-origin", StatusCode::OK, true),
+            ("/", "cross-site", StatusCode::FORBIDDEN, true),
+            ("/webhooks", "cross-site", StatusCode::OK, false),
+        ] {
+            let request =
+                request!(Method::POST, path, site => site, mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status() == status, "{path} {site}");
+                    check!(
+                        response.headers().get(http::header::VARY).is_some() == vary,
+                        "{path} {site}"
+                    );
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_enforces_after_the_cutover() {
+        use std::{
+            sync::atomic::AtomicU64,
+            time::{Duration, UNIX_EPOCH},
+        };
+
+        static NOW: AtomicU64 = AtomicU64::new(0);
+
+        let cutover = UNIX_EPOCH + Duration::from_secs(1_000);
+        let layer = SecFetchLayer::default()
+            .enforce_after(cutover)
+            .clock(|| UNIX_EPOCH + Duration::from_secs(NOW.load(Ordering::SeqCst)));
+
+        for (now, status) in [
+            (999, StatusCode::OK),
+            (1_000, StatusCode::FORBIDDEN),
+            (2_000, StatusCode::FORBIDDEN),
+        ] {
+            NOW.store(now, Ordering::SeqCst);
+            let request =
+                request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status() == status, "{now}");
+                },
+                layer.clone()
+            );
+        }
+
+        check!(layer.pipeline().steps().contains(&Step::ReportOnlyUntil {
+            unix_seconds: 1_000
+        }));
+    }
+
+    #[tokio::test]
+    async fn it_allows_navigation_requests() {
+        let request = request!(site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(request, |response: http::Response<()>| {
+            check!(response.status().is_success());
+        });
+    }
+
+    #[tokio::test]
+    async fn it_rejects_navigation_requests_resulting_from_embedding() {
+        let request = request!(site => "cross-site", mode => "navigate", dest => "iframe");
+
+        assert_request!(request, |response: http::Response<()>| {
+            check!(response.status() == StatusCode::FORBIDDEN);
+        });
+    }
+
+    #[tokio::test]
+    async fn it_rejects_navigation_requests_to_untrusted_paths() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.untrusted_paths(["/uploads/"]);
+        });
+
+        for path in [
+            "/uploads/avatar.svg",
+            "//uploads/avatar.svg",
+            "/%75ploads/avatar.svg",
+            "/uploads/%61vatar.svg",
+            "/static/../uploads/avatar.svg",
+            "/uploads;x=1/avatar.svg",
+            "/uploads/",
+        ] {
+            let request =
+                request!(path, site => "cross-site", mode => "navigate", dest => "document");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status() == StatusCode::FORBIDDEN, "{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_rejects_navigation_requests_to_sensitive_paths() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.deny_navigation_to(["/logout"]);
+        });
+        let request =
+            request!("/logout", site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer.clone()
+        );
+
+        let request =
+            request!("/logout/help", site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[test]
+    fn it_normalizes_sensitive_paths() {
+        let policy = Policy::new(|policy| {
+            policy.deny_navigation_to(["/logout"]);
+        });
+
+        for path in [
+            "/logout/",
+            "/logout;x",
+            "/logout;jsessionid=1/",
+            "//logout",
+            "/logou%74",
+            "/static/../logout",
+        ] {
+            let request =
+                request!(path, site => "cross-site", mode => "navigate", dest => "document");
+
+            check!(
+                policy.evaluate(&request) == Verdict::Denied(DenialReason::CrossSite),
+                "{path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_rejects_navigations_to_sensitive_paths_with_safe_methods() {
+        for order in [
+            SafeMethodsOrder::BeforePolicy,
+            SafeMethodsOrder::AfterReport,
+        ] {
+            let layer = SecFetchLayer::new(|policy| {
+                policy
+                    .allow_safe_methods()
+                    .safe_methods_order(order)
+                    .deny_navigation_to(["/logout"]);
+            });
+
+            for path in ["/logout", "/logout/", "/logout;x"] {
+                let request =
+                    request!(path, site => "cross-site", mode => "navigate", dest => "document");
+
+                assert_request!(
+                    request,
+                    |response: http::Response<()>| {
+                        check!(response.status() == StatusCode::FORBIDDEN);
+                    },
+                    layer.clone()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn it_rejects_navigations_without_user_activation() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.require_user_activation(["/admin/**"]);
+        });
+        let request =
+            request!("/admin/users", site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_navigations_with_user_activation() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.require_user_activation(["/admin/**"]);
+        });
+        let mut request =
+            request!("/admin", site => "cross-site", mode => "navigate", dest => "document");
+        request
+            .headers_mut()
+            .insert(header::SEC_FETCH_USER, "?1".parse().unwrap());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let request = request!("/administrator", site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_configured_destinations() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.allow_destinations("/assets/", ["image", "font"]);
+        });
+        let request =
+            request!("/assets/logo.png", site => "cross-site", mode => "no-cors", dest => "image");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let request =
+            request!("/avatar.png", site => "cross-site", mode => "no-cors", dest => "image");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rejects_denied_destinations() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.deny_destinations(["document"]);
+        });
+        let request = request!(site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_navigations_to_configured_destinations() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.navigation_destinations(["document", "frame"]);
+        });
+        let request = request!(site => "cross-site", mode => "navigate", dest => "frame");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[test]
+    fn it_evaluates_requests_outside_of_the_middleware() {
+        let policy = Policy::new(|policy| {
+            policy
+                .allow_safe_methods()
+                .safe_methods_order(SafeMethodsOrder::AfterReport)
+                .protect_methods([Method::POST, Method::GET]);
+        });
+
+        let allowed =
+            request!(Method::POST, "/", site => "same-origin", mode => "cors", dest => "empty");
+        check!(policy.evaluate(&allowed) == Verdict::Allowed);
+        check!(policy.evaluate(&allowed).reason() == None);
+
+        let denied =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        let verdict = policy.evaluate(&denied);
+        check!(verdict == Verdict::Denied(DenialReason::CrossSite));
+        check!(verdict.reason() == Some(DenialReason::CrossSite));
+        check!(!verdict.is_allowed());
+
+        // the reporting and protection of the middleware are only applied by the verdict
+        let reported =
+            request!(Method::GET, "/", site => "cross-site", mode => "cors", dest => "empty");
+        check!(policy.evaluate(&reported) == Verdict::Denied(DenialReason::CrossSite));
+        check!(policy.verdict(&reported) == Verdict::Reported(DenialReason::CrossSite));
+
+        let unprotected =
+            request!(Method::DELETE, "/", site => "cross-site", mode => "cors", dest => "empty");
+        check!(policy.evaluate(&unprotected) == Verdict::Denied(DenialReason::CrossSite));
+        check!(policy.verdict(&unprotected) == Verdict::Allowed);
+    }
+
+    #[tokio::test]
+    async fn it_evaluates_the_decision_matrix() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.matrix(
+                Matrix::default()
+                    .set(Dest::Image, Site::CrossSite, Action::Allow)
+                    .set(Dest::Document, Site::SameSite, Action::Deny),
+            );
+        });
+        let request = request!(site => "cross-site", mode => "no-cors", dest => "image");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let request = request!(site => "same-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_evaluates_the_decision_matrix_per_mode() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.matrix(Matrix::default().set_mode(Mode::Cors, Site::CrossSite, Action::Allow));
+        });
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "no-cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_evaluates_custom_rules() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .custom(|metadata, request| {
+                    if metadata.mode == Some(Mode::Websocket) && request.uri().path() == "/live" {
+                        return RuleDecision::Allow;
+                    }
+
+                    RuleDecision::Continue
+                })
+                .custom(|metadata, _| {
+                    if metadata.site == Site::SameSite && !metadata.user {
+                        return RuleDecision::Deny;
+                    }
+
+                    RuleDecision::Continue
+                });
+        });
+        let request =
+            request!("/live", site => "cross-site", mode => "websocket", dest => "websocket");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let request = request!(site => "same-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[cfg(feature = "config")]
+    #[tokio::test]
+    async fn it_evaluates_rules_from_configuration() {
+        let rules = RuleSet::from_json(
+            r#"[
+                { "path": "/webhooks/**", "methods": ["POST"], "action": "allow" },
+                { "sites": ["same-site"], "dests": ["iframe"], "action": "deny" }
+            ]"#,
+        )
+        .unwrap();
+        let layer = SecFetchLayer::new(|policy| {
+            policy.rules(rules);
+        });
+
+        let request = request!(Method::POST, "/webhooks/github", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        let request =
+            request!(Method::POST, "/other", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer.clone()
+        );
+
+        let request = request!(site => "same-site", mode => "navigate", dest => "iframe");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn it_matches_rules_from_configuration_against_the_resolved_metadata() {
+        let rules = RuleSet::from_json(
+            r#"[
+                { "sites": ["Same-Site"], "action": "deny" },
+                { "sites": ["same-origin"], "action": "allow" }
+            ]"#,
+        )
+        .unwrap();
+        let policy = Policy::new(|policy| {
+            policy.case_insensitive_metadata().rules(rules);
+        });
+
+        let request =
+            request!(Method::POST, "/", site => "SAME-SITE", mode => "cors", dest => "empty");
+        check!(policy.evaluate(&request) == Verdict::Denied(DenialReason::Custom));
+
+        // the most restrictive site wins, so the allow rule does not match
+        let mut request =
+            request!(Method::POST, "/", site => "same-origin", mode => "cors", dest => "empty");
+        request.headers_mut().append(
+            header::SEC_FETCH_SITE,
+            HeaderValue::from_static("cross-site"),
+        );
+        check!(policy.evaluate(&request) == Verdict::Denied(DenialReason::CrossSite));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn it_rejects_unknown_rule_fields() {
+        check!(RuleSet::from_json(r#"[{ "paths": "/", "action": "allow" }]"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn it_accepts_a_prebuilt_policy() {
+        let policy = Policy::new(|policy| {
+            policy.allow_safe_methods();
+        });
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            SecFetchLayer::default().with_policy(policy)
+        );
+    }
+
+    #[test]
+    fn it_provides_policy_presets() {
+        let denial = |policy: Policy, site, mode, dest| {
+            let verdict: Verdict = SecFetchLayer::default()
+                .with_policy(policy)
+                .simulate(
+                    "GET",
+                    "/",
+                    &[
+                        ("sec-fetch-site", site),
+                        ("sec-fetch-mode", mode),
+                        ("sec-fetch-dest", dest),
+                    ],
+                )
+                .unwrap();
+            verdict.reason()
+        };
+
+        check!(denial(Policy::website(), "cross-site", "navigate", "document") == None);
+        check!(denial(Policy::api(), "same-site", "cors", "empty") == None);
+        check!(
+            denial(Policy::api(), "cross-site", "navigate", "document")
+                == Some(DenialReason::CrossSite)
+        );
+        check!(denial(Policy::strict(), "same-origin", "cors", "empty") == None);
+        check!(
+            denial(Policy::strict(), "same-site", "cors", "empty") == Some(DenialReason::CrossSite)
+        );
+        check!(
+            denial(Policy::strict(), "cross-site", "navigate", "document")
+                == Some(DenialReason::CrossSite)
+        );
+        check!(
+            SecFetchLayer::default()
+                .with_policy(Policy::api())
+                .simulate("POST", "/", &[])
+                .unwrap()
+                == Verdict::Denied(DenialReason::MissingMetadata)
+        );
+
+        let extended = Policy::strict().extend(|policy| {
+            policy.allow_safe_methods();
+        });
+        check!(denial(extended.clone(), "cross-site", "cors", "empty") == None);
+        check!(
+            SecFetchLayer::default()
+                .with_policy(extended)
+                .simulate("POST", "/", &[])
+                .unwrap()
+                == Verdict::Denied(DenialReason::MissingMetadata)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_lets_requests_to_non_canonical_origins_be_redirected() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.canonical_origin("https://www.example.com");
+        });
+        let request = request!(Method::POST, "/", site => "cross-site", mode => "navigate", dest => "document");
+
+        let (service, mut handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<()>, _>(layer);
+
+        tokio::spawn(async move {
+            let_assert!(Some((_, send)) = handler.next_request().await);
+            let mut response = http::Response::new(());
+            *response.status_mut() = StatusCode::PERMANENT_REDIRECT;
+            send.send_response(response);
+        });
+
+        let response = service.into_inner().oneshot(request).await.unwrap();
+
+        check!(response.status() == StatusCode::PERMANENT_REDIRECT);
+    }
+
+    #[tokio::test]
+    async fn it_denies_requests_to_non_canonical_origins_that_are_not_redirected() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.canonical_origin("https://www.example.com");
+        });
+        let request = request!(Method::POST, "/", site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[test]
+    fn it_compares_the_canonical_origin_case_insensitively() {
+        let policy = Policy::new(|policy| {
+            policy.canonical_origin("https://example.com");
+        });
+        let request = http::Request::post("/")
+            .header(http::header::HOST, "EXAMPLE.com")
+            .header(header::X_FORWARDED_PROTO, "HTTPS")
+            .body(())
+            .unwrap();
+
+        check!(!policy.is_canonicalization(&request));
+    }
+
+    #[tokio::test]
+    async fn it_evaluates_requests_to_the_canonical_origin() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.canonical_origin("https://example.com");
+        });
+        let request = request!(Method::POST, "/", site => "cross-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_embedding_widgets_from_allowed_origins() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.allow_embedding("/embed/", ["https://partner.example.com"]);
+        });
+        let mut request =
+            request!("/embed/widget", site => "cross-site", mode => "navigate", dest => "iframe");
+        request.headers_mut().insert(
+            http::header::REFERER,
+            "https://partner.example.com/page".parse().unwrap(),
+        );
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rejects_embedding_widgets_from_other_origins() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.allow_embedding("/embed/", ["https://partner.example.com"]);
+        });
+        let mut request =
+            request!("/embed/widget", site => "cross-site", mode => "navigate", dest => "iframe");
+        request.headers_mut().insert(
+            http::header::ORIGIN,
+            "https://evil.example.com".parse().unwrap(),
+        );
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_falls_back_to_the_origin_header_in_go_compat_mode() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.go_compat();
+        });
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/")
+            .header(http::header::ORIGIN, "https://evil.example.com")
+            .body(())
+            .unwrap();
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[test]
+    fn it_allows_insecure_upgrades_in_the_origin_fallback() {
+        let policy = Policy::new(|policy| {
+            policy.missing_metadata_by_user_agent(
+                UserAgentRules::default().non_browsers(MissingMetadata::OriginFallback),
+            );
+        });
+        let request = |uri, origin, upgrade| {
+            http::Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header(http::header::ORIGIN, origin)
+                .header(header::UPGRADE_INSECURE_REQUESTS, upgrade)
+                .body(())
+                .unwrap()
+        };
+
+        for (uri, origin, upgrade, denial) in [
+            ("https://example.com:443/", "http://example.com", "1", None),
+            (
+                "https://example.com:443/",
+                "http://example.com:80",
+                "1",
+                None,
+            ),
+            (
+                "https://example.com:443/",
+                "http://example.com",
+                "0",
+                Some(DenialReason::OriginMismatch),
+            ),
+            (
+                "https://example.com/",
+                "http://example.com:8081",
+                "1",
+                Some(DenialReason::OriginMismatch),
+            ),
+            (
+                "https://example.com:8443/",
+                "http://example.com",
+                "1",
+                Some(DenialReason::OriginMismatch),
+            ),
+            (
+                "https://example.com/",
+                "http://other.example.com",
+                "1",
+                Some(DenialReason::OriginMismatch),
+            ),
+        ] {
+            check!(
+                policy.evaluate(&request(uri, origin, upgrade)).reason() == denial,
+                "{uri} {origin} {upgrade}"
+            );
+        }
+
+        let mut forwarded = request("/", "http://example.com:8081", "1");
+        let headers = forwarded.headers_mut();
+        headers.insert(http::header::HOST, HeaderValue::from_static("example.com"));
+        headers.insert(header::X_FORWARDED_PROTO, HeaderValue::from_static("https"));
+        check!(policy.evaluate(&forwarded) == Verdict::Denied(DenialReason::OriginMismatch));
+    }
+
+    #[test]
+    fn it_does_not_allow_insecure_upgrades_in_go_compat_mode() {
+        let policy = Policy::new(|policy| {
+            policy.go_compat();
+        });
+        let request = http::Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com:443/")
+            .header(http::header::ORIGIN, "http://example.com")
+            .header(header::UPGRADE_INSECURE_REQUESTS, "1")
+            .body(())
+            .unwrap();
+
+        check!(policy.evaluate(&request) == Verdict::Denied(DenialReason::OriginMismatch));
+    }
+
+    #[tokio::test]
+    async fn it_compares_the_origin_with_external_origins_in_go_compat_mode() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .go_compat()
+                .external_origins(["https://app.example.com"]);
+        });
+        let request = |origin| {
+            http::Request::builder()
+                .method(Method::POST)
+                .uri("http://backend.internal:8080/")
+                .header(http::header::ORIGIN, origin)
+                .body(())
+                .unwrap()
+        };
+
+        assert_request!(
+            request("https://app.example.com"),
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer.clone()
+        );
+
+        assert_request!(
+            request("http://backend.internal:8080"),
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[test]
+    fn it_allows_trusted_origins() {
+        let policy = Policy::new(|policy| {
+            policy
+                .trusted_origins(["https://partner.example"])
+                .untrusted_paths(["/uploads/"])
+                .custom(|_, request| {
+                    if request.uri().path() == "/admin" {
+                        return RuleDecision::Deny;
+                    }
+
+                    RuleDecision::Continue
+                });
+        });
+        let request = |path, origin| {
+            let mut request =
+                request!(Method::POST, path, site => "cross-site", mode => "cors", dest => "empty");
+            request
+                .headers_mut()
+                .insert(http::header::ORIGIN, HeaderValue::from_static(origin));
+            request
+        };
+
+        check!(policy.evaluate(&request("/", "https://partner.example")) == Verdict::Allowed);
+        check!(
+            policy.evaluate(&request("/", "https://evil.example"))
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+        check!(
+            policy.evaluate(&request("/admin", "https://partner.example"))
+                == Verdict::Denied(DenialReason::Custom)
+        );
+        check!(
+            Policy::default().evaluate(&request("/", "https://partner.example"))
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_allows_trusted_origins_in_go_compat_mode() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .go_compat()
+                .trusted_origins(["https://partner.example.com"]);
+        });
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request.headers_mut().insert(
+            http::header::ORIGIN,
+            "https://partner.example.com".parse().unwrap(),
+        );
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_ignores_explicitely_authorized_requests() {
+        let layer = SecFetchLayer::default().allowing(["/allowed"]);
+        let request = request!("/allowed", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_accepts_boxed_authorizers() {
+        let authorizers = [
+            BoxAuthorizer::new(PathAuthorizer::new(["/allowed"])),
+            BoxAuthorizer::new(NoopAuthorizer),
+        ];
+
+        for (authorizer, allowed) in authorizers.into_iter().zip([true, false]) {
+            let request =
+                request!("/allowed", site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed);
+                },
+                SecFetchLayer::default().with_authorizer(authorizer)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_boxes_layers_with_different_authorizers() {
+        let layers = [
+            SecFetchLayer::default().allowing(["/allowed"]).boxed(),
+            SecFetchLayer::default().boxed(),
+        ];
+
+        for (layer, allowed) in layers.into_iter().zip([true, false]) {
+            let request =
+                request!("/allowed", site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed);
+                },
+                layer
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_paths_from_runtime_configuration() {
+        let configured = String::from("/allowed,/webhooks/**");
+        let layer = SecFetchLayer::default().allowing(configured.split(',').map(str::to_string));
+        let request =
+            request!("/webhooks/github", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::OK);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_normalizes_paths_if_configured() {
+        for (normalize, allowed) in [(false, false), (true, true)] {
+            let authorizer = PathAuthorizer::new(["/unprotected"]);
+            let authorizer = if normalize {
+                authorizer.normalize_paths()
+            } else {
+                authorizer
+            };
+            let request = request!("/static/%2e%2e/unprotected", site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed);
+                },
+                SecFetchLayer::default().with_authorizer(authorizer)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_ignores_trailing_slashes_if_configured() {
+        let authorizer =
+            PathAuthorizer::new(["/unprotected", "/webhooks/"]).ignore_trailing_slash();
+        let layer = SecFetchLayer::default().with_authorizer(authorizer);
+
+        for path in ["/unprotected", "/unprotected/", "/webhooks", "/webhooks/"] {
+            let request = request!(path, site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status() == StatusCode::OK, "{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_requests_by_host_and_path() {
+        let layer = SecFetchLayer::default().with_authorizer(HostPathAuthorizer::new([(
+            "api.example.com",
+            "/webhooks/*",
+        )]));
+
+        for (host, path, allowed) in [
+            ("api.example.com", "/webhooks/github", true),
+            ("api.example.com:8443", "/webhooks/github", true),
+            ("app.example.com", "/webhooks/github", false),
+            ("api.example.com", "/users", false),
+        ] {
+            let mut request = request!(path, site => "cross-site", mode => "cors", dest => "empty");
+            request
+                .headers_mut()
+                .insert(http::header::HOST, HeaderValue::from_static(host));
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{host}{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_requests_from_internal_ranges() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(CidrAuthorizer::new(["10.0.0.0/8"]).unwrap());
+
+        for (addr, allowed) in [("10.1.2.3:4000", true), ("203.0.113.7:4000", false)] {
+            let mut request = http::Request::new(());
+            request
+                .extensions_mut()
+                .insert(addr.parse::<std::net::SocketAddr>().unwrap());
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{addr}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_reads_the_client_address_from_forwarded_for_if_trusted() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(
+            CidrAuthorizer::new(["10.0.0.0/8"])
+                .unwrap()
+                .trust_forwarded_for(),
+        );
+
+        for (forwarded_for, allowed) in [
+            ("203.0.113.7, 10.1.2.3", true),
+            ("10.1.2.3, 203.0.113.7", false),
+        ] {
+            let mut request = http::Request::new(());
+            request.headers_mut().insert(
+                header::X_FORWARDED_FOR,
+                HeaderValue::from_static(forwarded_for),
+            );
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{forwarded_for}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_requests_carrying_a_header() {
+        for (authorizer, value, allowed) in [
+            (
+                HeaderAuthorizer::new("x-internal-request"),
+                Some("yes"),
+                true,
+            ),
+            (HeaderAuthorizer::new("x-internal-request"), None, false),
+            (
+                HeaderAuthorizer::new("x-internal-request").value("1"),
+                Some("1"),
+                true,
+            ),
+            (
+                HeaderAuthorizer::new("x-internal-request").value("1"),
+                Some("yes"),
+                false,
+            ),
+        ] {
+            let mut request = request!(site => "cross-site", mode => "cors", dest => "empty");
+            if let Some(value) = value {
+                request
+                    .headers_mut()
+                    .insert("x-internal-request", HeaderValue::from_static(value));
+            }
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed);
+                },
+                SecFetchLayer::default().with_authorizer(authorizer)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_requests_with_bearer_tokens() {
+        for (authorization, allowed) in [
+            (Some("Bearer abc.def"), true),
+            (Some("bearer abc.def"), true),
+            (Some("Bearer "), false),
+            (Some("Basic dXNlcjpwYXNz"), false),
+            (None, false),
+        ] {
+            let mut request = request!(site => "cross-site", mode => "cors", dest => "empty");
+            if let Some(authorization) = authorization {
+                request.headers_mut().insert(
+                    http::header::AUTHORIZATION,
+                    HeaderValue::from_static(authorization),
+                );
+            }
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(
+                        response.status().is_success() == allowed,
+                        "{authorization:?}"
+                    );
+                },
+                SecFetchLayer::default().with_authorizer(BearerAuthorizer)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_requests_with_verified_client_certificates() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(ClientCertAuthorizer::<VerifiedClientCertificate>::new());
+
+        for (certificate, allowed) in [(true, true), (false, false)] {
+            let mut request = http::Request::new(());
+            if certificate {
+                request
+                    .extensions_mut()
+                    .insert(VerifiedClientCertificate(vec![0x30, 0x82]));
+            }
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed);
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_marks_requests_allowed_by_the_policy() {
+        let (service, mut handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<()>, _>(SecFetchLayer::default());
+
+        tokio::spawn(async move {
+            let (request, send) = handler.next_request().await.unwrap();
+            check!(request.extensions().get::<SecFetchVerified>().is_some());
+            send.send_response(http::Response::new(()));
+        });
+
+        let request = request!(site => "same-origin", mode => "cors", dest => "empty");
+        let response = service.into_inner().oneshot(request).await.unwrap();
+        check!(response.status() == StatusCode::OK);
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn it_extracts_the_verification_marker() {
+        let router = ::axum::Router::new().route(
+            "/",
+            ::axum::routing::post(async |_: SecFetchVerified| "verified"),
+        );
+        let request = || {
+            request!(Method::POST, "/", site => "same-origin", mode => "cors", dest => "empty")
+                .map(|()| ::axum::body::Body::empty())
+        };
+
+        let response = router.clone().oneshot(request()).await.unwrap();
+        check!(response.status() == StatusCode::FORBIDDEN);
+
+        let response = router
+            .layer(SecFetchLayer::default())
+            .oneshot(request())
+            .await
+            .unwrap();
+        check!(response.status() == StatusCode::OK);
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn it_rejects_denied_requests_like_axum() {
+        let router = ::axum::Router::new()
+            .route("/", ::axum::routing::post(async || "ok"))
+            .layer(SecFetchLayer::default().axum_rejection());
+        let request = |accept: &'static str| {
+            let mut request =
+                request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty")
+                    .map(|()| ::axum::body::Body::empty());
+            request
+                .headers_mut()
+                .insert(http::header::ACCEPT, HeaderValue::from_static(accept));
+            request
+        };
+
+        let response = router.clone().oneshot(request("*/*")).await.unwrap();
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(response.headers()[http::header::CONTENT_TYPE] == "text/plain; charset=utf-8");
+
+        let response = router
+            .oneshot(request("text/html, application/json;q=0.9"))
+            .await
+            .unwrap();
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(response.headers()[http::header::CONTENT_TYPE] == "application/problem+json");
+    }
+
+    #[tokio::test]
+    async fn it_skips_requests_opted_out() {
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request.extensions_mut().insert(SkipSecFetch);
+
+        assert_request!(request, |response: http::Response<()>| {
+            check!(response.status() == StatusCode::OK);
+        });
+    }
+
+    #[tokio::test]
+    async fn it_allows_well_known_paths() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(PathAuthorizer::well_known());
+
+        for (path, allowed) in [
+            ("/.well-known/acme-challenge/token", true),
+            ("/.well-known/security.txt", true),
+            ("/well-known/security.txt", false),
+        ] {
+            let request = http::Request::builder().uri(path).body(()).unwrap();
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_cross_site_static_assets_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.allow_static_assets("/assets/");
+        });
+
+        for (path, dest, allowed) in [
+            ("/assets/logo.png", "image", true),
+            ("/assets/app.css", "style", true),
+            ("/assets/data.json", "empty", false),
+            ("/uploads/logo.png", "image", false),
+        ] {
+            let request = request!(path, site => "cross-site", mode => "no-cors", dest => dest);
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{path} {dest}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_public_reads_with_the_public_preset() {
+        let layer = SecFetchLayer::default().with_policy(Policy::public());
+
+        for (method, path, dest, allowed) in [
+            (Method::GET, "/", "image", true),
+            (Method::GET, "/", "empty", true),
+            (Method::HEAD, "/", "script", true),
+            (Method::POST, "/", "empty", false),
+            (Method::POST, "/any?_method=GET", "empty", false),
+            (Method::GET, "/", "iframe", false),
+        ] {
+            let mut request = request!(method.clone(), path, site => "cross-site", mode => "no-cors", dest => dest);
+            request
+                .headers_mut()
+                .insert("x-http-method-override", HeaderValue::from_static("GET"));
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(
+                        response.status().is_success() == allowed,
+                        "{method} {path} {dest}"
+                    );
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[test]
+    fn it_allows_cross_site_event_streams_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.allow_event_streams(["/events/"]);
+        });
+
+        for (path, accept, denial) in [
+            ("/events/orders", "text/event-stream", None),
+            (
+                "/events/orders",
+                "application/json",
+                Some(DenialReason::CrossSite),
+            ),
+            (
+                "/orders",
+                "text/event-stream",
+                Some(DenialReason::CrossSite),
+            ),
+        ] {
+            let mut request = request!(path, site => "cross-site", mode => "cors", dest => "empty");
+            request
+                .headers_mut()
+                .insert(http::header::ACCEPT, HeaderValue::from_static(accept));
+
+            check!(
+                layer.shared.policy.evaluate(&request).reason() == denial,
+                "{path} {accept}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_evaluates_tunneled_methods_if_configured() {
+        let tunneled = |method: Method, uri: &str, header: Option<&'static str>| {
+            let mut request =
+                request!(method, uri, site => "cross-site", mode => "no-cors", dest => "empty");
+            if let Some(method) = header {
+                request
+                    .headers_mut()
+                    .insert("x-http-method-override", HeaderValue::from_static(method));
+            }
+            request
+        };
+        let policy = |honor: bool| {
+            Policy::new(|policy| {
+                policy.safe_methods([Method::GET, Method::HEAD, Method::OPTIONS, Method::POST]);
+                if honor {
+                    policy.honor_method_override();
+                }
+            })
+        };
+
+        check!(
+            policy(false).evaluate(&tunneled(Method::POST, "/", Some("DELETE")))
+                == Verdict::Allowed
+        );
+        check!(
+            policy(true).evaluate(&tunneled(Method::POST, "/", Some("DELETE")))
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+        check!(
+            policy(true).evaluate(&tunneled(Method::POST, "/?_method=delete", None))
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+        check!(policy(true).evaluate(&tunneled(Method::POST, "/", None)) == Verdict::Allowed);
+        // only POST requests can tunnel another method
+        check!(
+            policy(true).evaluate(&tunneled(Method::GET, "/?_method=DELETE", None))
+                == Verdict::Allowed
+        );
+    }
+
+    #[test]
+    fn it_does_not_relax_checks_with_tunneled_methods() {
+        let tunneled = || request!(Method::POST, "/x?_method=GET", site => "cross-site", mode => "no-cors", dest => "empty");
+
+        let safe_methods = Policy::new(|policy| {
+            policy.allow_safe_methods().honor_method_override();
+        });
+        check!(safe_methods.evaluate(&tunneled()) == Verdict::Denied(DenialReason::CrossSite));
+        check!(safe_methods.verdict(&tunneled()) == Verdict::Denied(DenialReason::CrossSite));
+
+        let go_compat = Policy::new(|policy| {
+            policy.go_compat().honor_method_override();
+        });
+        check!(go_compat.evaluate(&tunneled()) == Verdict::Denied(DenialReason::CrossSite));
+
+        let protected = Policy::new(|policy| {
+            policy
+                .protect_methods([Method::POST, Method::PUT, Method::DELETE])
+                .honor_method_override();
+        });
+        check!(protected.is_protected(&tunneled()));
+        check!(protected.verdict(&tunneled()) == Verdict::Denied(DenialReason::CrossSite));
+
+        let navigation = request!(Method::POST, "/x?_method=GET", site => "cross-site", mode => "navigate", dest => "document");
+        check!(
+            Policy::new(|policy| {
+                policy.honor_method_override();
+            })
+            .evaluate(&navigation)
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+    }
+
+    #[test]
+    fn it_honors_method_overrides_in_go_compat_mode() {
+        let go_compat = Policy::new(|policy| {
+            policy.go_compat().honor_method_override();
+        });
+        let protected = Policy::new(|policy| {
+            policy
+                .protect_methods([Method::DELETE])
+                .honor_method_override();
+        });
+        let tunneled = request!(Method::POST, "/?_method=DELETE", site => "cross-site", mode => "cors", dest => "empty");
+
+        check!(go_compat.evaluate(&tunneled) == Verdict::Denied(DenialReason::CrossSite));
+        check!(protected.is_protected(&tunneled));
+        check!(!protected.is_protected(
+            &request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty")
+        ));
+    }
+
+    #[test]
+    fn it_passes_the_tunneled_method_to_custom_rules() {
+        let policy = Policy::new(|policy| {
+            policy.honor_method_override().custom(|_, request| {
+                if request.method() == Method::POST
+                    && request.tunneled_method() == Some(&Method::DELETE)
+                {
+                    return RuleDecision::Deny;
+                }
+
+                RuleDecision::Allow
+            });
+        });
+        let request = request!(Method::POST, "/?_method=delete", site => "same-origin", mode => "cors", dest => "empty");
+
+        check!(policy.evaluate(&request) == Verdict::Denied(DenialReason::Custom));
+    }
+
+    #[test]
+    fn it_rejects_form_posts_without_metadata_if_configured() {
+        let policy = Policy::new(|policy| {
+            policy.reject_form_posts_without_metadata();
+        });
+        let request = |method: Method, content_type: Option<&str>| {
+            let mut request = http::Request::builder().method(method);
+            if let Some(content_type) = content_type {
+                request = request.header(http::header::CONTENT_TYPE, content_type);
+            }
+            request.body(()).unwrap()
+        };
+
+        for (method, content_type, denied) in [
+            (
+                Method::POST,
+                Some("application/x-www-form-urlencoded"),
+                true,
+            ),
+            (Method::POST, Some("multipart/form-data; boundary=x"), true),
+            (Method::POST, Some("Text/Plain;charset=utf-8"), true),
+            (Method::POST, None, true),
+            (Method::POST, Some("application/json"), false),
+            (
+                Method::PUT,
+                Some("application/x-www-form-urlencoded"),
+                false,
+            ),
+            (Method::GET, None, false),
+        ] {
+            check!(
+                policy
+                    .evaluate(&request(method.clone(), content_type))
+                    .reason()
+                    .is_some()
+                    == denied,
+                "{method} {content_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_handles_missing_metadata_by_user_agent() {
+        const CHROME: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36";
+        const IE: &str = "Mozilla/5.0 (compatible; MSIE 10.0; Windows NT 6.1; Trident/6.0)";
+
+        let policy = Policy::new(|policy| {
+            policy
+                .reject_missing_metadata()
+                .missing_metadata_by_user_agent(
+                    UserAgentRules::default().legacy_browsers(MissingMetadata::Deny),
+                );
+        });
+
+        for (user_agent, origin, denial) in [
+            (Some("curl/8.5.0"), Some("https://evil.example"), None),
+            (None, None, None),
+            (Some(CHROME), Some("https://example.com"), None),
+            (Some(CHROME), None, None),
+            (
+                Some(CHROME),
+                Some("https://evil.example"),
+                Some(DenialReason::OriginMismatch),
+            ),
+            (
+                Some(IE),
+                Some("https://example.com"),
+                Some(DenialReason::MissingMetadata),
+            ),
+        ] {
+            let mut request = http::Request::post("https://example.com/transfer")
+                .body(())
+                .unwrap();
+            if let Some(user_agent) = user_agent {
+                request.headers_mut().insert(
+                    http::header::USER_AGENT,
+                    HeaderValue::from_static(user_agent),
+                );
+            }
+            if let Some(origin) = origin {
+                request
+                    .headers_mut()
+                    .insert(http::header::ORIGIN, HeaderValue::from_static(origin));
+            }
+
+            check!(
+                policy.evaluate(&request).reason() == denial,
+                "{user_agent:?} {origin:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_allows_saml_post_binding() {
+        let policy = Policy::new(|policy| {
+            policy.allow_saml_post_binding(["/saml/*/acs"]);
+        });
+
+        for (method, path, mode, dest, allowed) in [
+            (Method::POST, "/saml/okta/acs", "navigate", "document", true),
+            (Method::POST, "/saml/okta/acs", "navigate", "iframe", false),
+            (Method::POST, "/saml/okta/acs", "cors", "empty", false),
+            (
+                Method::POST,
+                "/saml/okta/logout",
+                "navigate",
+                "document",
+                false,
+            ),
+        ] {
+            let request = request!(method, path, site => "cross-site", mode => mode, dest => dest);
+
+            check!(
+                policy.evaluate(&request).is_allowed() == allowed,
+                "{path} {mode} {dest}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_provides_a_graphql_preset() {
+        let request = |method: Method, site: &str, content_type: &'static str| {
+            let mut request =
+                request!(method, "/graphql", site => site, mode => "cors", dest => "empty");
+            request.headers_mut().insert(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static(content_type),
+            );
+            request
+        };
+        let json = "application/json; charset=utf-8";
+
+        for (policy, method, site, content_type, denial) in [
+            (Policy::graphql(), Method::POST, "same-origin", json, None),
+            (
+                Policy::graphql(),
+                Method::POST,
+                "same-origin",
+                "text/plain",
+                Some(DenialReason::Custom),
+            ),
+            (
+                Policy::graphql(),
+                Method::POST,
+                "cross-site",
+                json,
+                Some(DenialReason::CrossSite),
+            ),
+            (
+                Policy::graphql(),
+                Method::GET,
+                "same-origin",
+                json,
+                Some(DenialReason::Custom),
+            ),
+            (
+                Policy::graphql_with_persisted_queries(),
+                Method::GET,
+                "same-origin",
+                json,
+                None,
+            ),
+            (
+                Policy::graphql_with_persisted_queries(),
+                Method::GET,
+                "same-site",
+                json,
+                Some(DenialReason::Custom),
+            ),
+        ] {
+            check!(
+                policy
+                    .evaluate(&request(method.clone(), site, content_type))
+                    .reason()
+                    == denial,
+                "{method} {site} {content_type}"
+            );
+        }
+
+        check!(
+            Policy::graphql().evaluate(&http::Request::new(()))
+                == Verdict::Denied(DenialReason::MissingMetadata)
+        );
+    }
+
+    #[test]
+    fn it_accepts_missing_metadata_with_a_custom_header_if_configured() {
+        let policy = Policy::new(|policy| {
+            policy
+                .reject_missing_metadata()
+                .accept_missing_metadata_with_header("x-requested-with", Some("XMLHttpRequest"));
+        });
+        let request = |value: Option<&'static str>| {
+            let mut request = http::Request::builder().method(Method::POST);
+            if let Some(value) = value {
+                request = request.header("x-requested-with", value);
+            }
+            request.body(()).unwrap()
+        };
+
+        check!(policy.evaluate(&request(Some("XMLHttpRequest"))) == Verdict::Allowed);
+        check!(
+            policy.evaluate(&request(Some("fetch")))
+                == Verdict::Denied(DenialReason::MissingMetadata)
+        );
+        check!(policy.evaluate(&request(None)) == Verdict::Denied(DenialReason::MissingMetadata));
+    }
+
+    #[test]
+    fn it_resolves_repeated_metadata_headers() {
+        let request = |sites: &[&'static str], modes: &[&'static str]| {
+            let mut request = http::Request::builder().method(Method::POST);
+            for site in sites {
+                request = request.header(header::SEC_FETCH_SITE, *site);
+            }
+            for mode in modes {
+                request = request.header(header::SEC_FETCH_MODE, *mode);
+            }
+            request
+                .header(header::SEC_FETCH_DEST, "empty")
+                .body(())
+                .unwrap()
+        };
+        let policy = Policy::default();
+        let rejecting = Policy::new(|policy| {
+            policy.reject_ambiguous_metadata();
+        });
+
+        for (sites, modes, verdict, rejected) in [
+            (
+                &["same-origin"][..],
+                &["cors"][..],
+                Verdict::Allowed,
+                Verdict::Allowed,
+            ),
+            (
+                &["same-origin", "same-origin"],
+                &["cors"],
+                Verdict::Allowed,
+                Verdict::Allowed,
+            ),
+            (
+                &["same-origin, same-origin"],
+                &["cors"],
+                Verdict::Allowed,
+                Verdict::Allowed,
+            ),
+            (
+                &["same-origin", "cross-site"],
+                &["cors"],
+                Verdict::Denied(DenialReason::CrossSite),
+                Verdict::Denied(DenialReason::AmbiguousMetadata),
+            ),
+            (
+                &["cross-site, same-origin"],
+                &["cors"],
+                Verdict::Denied(DenialReason::CrossSite),
+                Verdict::Denied(DenialReason::AmbiguousMetadata),
+            ),
+            (
+                &["same-origin, same-site"],
+                &["cors"],
+                Verdict::Allowed,
+                Verdict::Denied(DenialReason::AmbiguousMetadata),
+            ),
+            (
+                &["same-origin"],
+                &["cors", "navigate"],
+                Verdict::Allowed,
+                Verdict::Denied(DenialReason::AmbiguousMetadata),
+            ),
+        ] {
+            check!(
+                policy.evaluate(&request(sites, modes)) == verdict,
+                "{sites:?} {modes:?}"
+            );
+            check!(
+                rejecting.evaluate(&request(sites, modes)) == rejected,
+                "{sites:?} {modes:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_reports_conflicting_metadata_if_configured() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_conflicting_metadata();
+        })
+        .with_reporter(FnReporter::new(|request| {
+            check!(
+                request.extensions().get::<DenialReason>()
+                    == Some(&DenialReason::ConflictingMetadata)
+            );
+        }));
+
+        for (sites, allowed) in [
+            (&["same-origin", "same-origin"][..], true),
+            (&["same-origin, same-site"], true),
+            (&["same-origin", "cross-site"], false),
+        ] {
+            let mut request =
+                request!(Method::POST, "/", site => sites[0], mode => "cors", dest => "empty");
+            for site in &sites[1..] {
+                request
+                    .headers_mut()
+                    .append(header::SEC_FETCH_SITE, HeaderValue::from_static(site));
+            }
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{sites:?}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[test]
+    fn it_rejects_unknown_metadata_if_configured() {
+        let policy = Policy::new(|policy| {
+            policy.reject_unknown_metadata();
+        });
+
+        for (site, mode, dest, verdict) in [
+            ("same-origin", "cors", "empty", Verdict::Allowed),
+            ("cross-site", "navigate", "document", Verdict::Allowed),
+            (
+                "same_origin",
+                "cors",
+                "empty",
+                Verdict::Denied(DenialReason::UnknownMetadata),
+            ),
+            (
+                "same-origin",
+                "xhr",
+                "empty",
+                Verdict::Denied(DenialReason::UnknownMetadata),
+            ),
+            (
+                "same-origin",
+                "cors",
+                "emtpy",
+                Verdict::Denied(DenialReason::UnknownMetadata),
+            ),
+        ] {
+            let request = request!(site => site, mode => mode, dest => dest);
+
+            check!(policy.evaluate(&request) == verdict, "{site} {mode} {dest}");
+        }
+
+        let request = request!(site => "same_origin", mode => "cors", dest => "empty");
+        check!(Policy::default().evaluate(&request) == Verdict::Denied(DenialReason::CrossSite));
+    }
+
+    #[test]
+    fn it_compares_metadata_values_exactly() {
+        let policy = Policy::default();
+
+        for (mode, dest, user, allowed) in [
+            ("navigate", "document", "?1", true),
+            ("Navigate", "document", "?1", false),
+            // repeated values are collapsed, see reject_ambiguous_metadata
+            ("navigate, navigate", "document", "?1", true),
+            ("navigate, cors", "document", "?1", false),
+            ("navigate", "DOCUMENT", "?1", false),
+        ] {
+            let mut request = request!(site => "cross-site", mode => mode, dest => dest);
+            request
+                .headers_mut()
+                .insert(header::SEC_FETCH_USER, HeaderValue::from_static(user));
+
+            check!(
+                policy.evaluate(&request).is_allowed() == allowed,
+                "{mode} {dest} {user}"
+            );
+        }
+
+        let activated = Policy::new(|policy| {
+            policy.require_user_activation(["/account"]);
+        });
+        for (user, allowed) in [("?1", true), ("?0", false), ("?1, ?1", false)] {
+            let mut request = request!(Method::GET, "/account", site => "cross-site", mode => "navigate", dest => "document");
+            request
+                .headers_mut()
+                .insert(header::SEC_FETCH_USER, HeaderValue::from_static(user));
+
+            check!(
+                activated.evaluate(&request).is_allowed() == allowed,
+                "{user}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_compares_metadata_ignoring_case_if_configured() {
+        let policy = Policy::new(|policy| {
+            policy
+                .case_insensitive_metadata()
+                .reject_ambiguous_metadata();
+        });
+
+        for (site, mode, dest, exact, case_insensitive) in [
+            (
+                "same-origin",
+                "cors",
+                "empty",
+                Verdict::Allowed,
+                Verdict::Allowed,
+            ),
+            (
+                "SAME-ORIGIN",
+                "CORS",
+                "EMPTY",
+                Verdict::Denied(DenialReason::CrossSite),
+                Verdict::Allowed,
+            ),
+            (
+                "Cross-Site",
+                "Navigate",
+                "Document",
+                Verdict::Denied(DenialReason::CrossSite),
+                Verdict::Allowed,
+            ),
+            (
+                "Cross-Site",
+                "cors",
+                "empty",
+                Verdict::Denied(DenialReason::CrossSite),
+                Verdict::Denied(DenialReason::CrossSite),
+            ),
+            (
+                "Same-Origin, same-origin",
+                "cors",
+                "empty",
+                Verdict::Denied(DenialReason::CrossSite),
+                Verdict::Allowed,
+            ),
+        ] {
+            let request = request!(site => site, mode => mode, dest => dest);
+
+            check!(
+                Policy::default().evaluate(&request) == exact,
+                "{site} {mode} {dest}"
+            );
+            check!(
+                policy.evaluate(&request) == case_insensitive,
+                "{site} {mode} {dest}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_allows_cors_preflights_if_configured() {
+        let preflight = || {
+            let mut request = request!(Method::OPTIONS, "/", site => "cross-site", mode => "cors", dest => "empty");
+            request.headers_mut().insert(
+                http::header::ACCESS_CONTROL_REQUEST_METHOD,
+                HeaderValue::from_static("PUT"),
+            );
+            request
+        };
+        let policy = Policy::new(|policy| {
+            policy.allow_preflights();
+        });
+
+        check!(
+            Policy::default().evaluate(&preflight()) == Verdict::Denied(DenialReason::CrossSite)
+        );
+        check!(policy.evaluate(&preflight()) == Verdict::Allowed);
+        check!(
+            policy.evaluate(&request!(Method::OPTIONS, "/", site => "cross-site", mode => "cors", dest => "empty"))
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_evaluates_websocket_handshakes() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .same_origin_websockets()
+                .allow_websocket_origins(["https://partner.example"]);
+        });
+
+        for (site, origin, denial) in [
+            ("same-origin", "https://example.com", None),
+            (
+                "same-site",
+                "https://app.example.com",
+                Some("websocket-origin"),
+            ),
+            ("cross-site", "https://partner.example", None),
+            (
+                "cross-site",
+                "https://evil.example",
+                Some("websocket-origin"),
+            ),
+        ] {
+            let mut request =
+                request!("/live", site => site, mode => "websocket", dest => "websocket");
+            request
+                .headers_mut()
+                .insert(http::header::ORIGIN, HeaderValue::from_static(origin));
+
+            check!(
+                layer
+                    .shared
+                    .policy
+                    .evaluate(&request)
+                    .reason()
+                    .map(|reason| reason.as_str())
+                    == denial,
+                "{site} {origin}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_grpc_requests() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(GrpcAuthorizer);
+
+        for (content_type, allowed) in [
+            ("application/grpc", true),
+            ("application/grpc+proto", true),
+            ("application/grpc-web-text", true),
+            ("application/json", false),
+        ] {
+            let request = http::Request::builder()
+                .method(Method::POST)
+                .header(http::header::CONTENT_TYPE, content_type)
+                .body(())
+                .unwrap();
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{content_type}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_oauth_callbacks() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(OAuthCallbackAuthorizer::new(["/auth/callback"]).require_state());
+
+        for (method, path, mode, allowed) in [
+            (
+                Method::GET,
+                "/auth/callback?code=abc&state=xyz",
+                "navigate",
+                true,
+            ),
+            (Method::POST, "/auth/callback", "navigate", true),
+            (Method::GET, "/auth/callback?code=abc", "navigate", false),
+            (
+                Method::GET,
+                "/auth/callback?code=abc&state=",
+                "navigate",
+                false,
+            ),
+            (Method::POST, "/auth/callback", "cors", false),
+            (Method::POST, "/auth/logout", "navigate", false),
+        ] {
+            let request = request!(method.clone(), path, site => "cross-site", mode => mode, dest => "document");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(
+                        response.status().is_success() == allowed,
+                        "{method} {path} {mode}"
+                    );
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_verifies_webhook_signatures() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .with_authorizer(WebhookAuthorizer::new(["/webhooks/*"]).verify(|request| {
+            request
+                .headers
+                .get("x-signature")
+                .is_some_and(|value| value == "valid")
+        }));
+
+        for (path, signature, allowed) in [
+            ("/webhooks/github", Some("valid"), true),
+            ("/webhooks/github", Some("forged"), false),
+            ("/webhooks/github", None, false),
+            ("/transfer", Some("valid"), false),
+        ] {
+            let mut request = http::Request::post(path).body(()).unwrap();
+            if let Some(signature) = signature {
+                request
+                    .headers_mut()
+                    .insert("x-signature", HeaderValue::from_static(signature));
+            }
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(
+                        response.status().is_success() == allowed,
+                        "{path} {signature:?}"
+                    );
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_paths_by_prefix() {
+        let layer = SecFetchLayer::default().allowing_prefix(["/api/webhooks/"]);
+
+        for (path, allowed) in [
+            ("/api/webhooks/github-1234", true),
+            ("/api/webhooks/stripe/events", true),
+            ("/api/webhooks", false),
+            ("/api/other", false),
+        ] {
+            let request = request!(path, site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[cfg(feature = "router")]
+    #[tokio::test]
+    async fn it_allows_routed_paths() {
+        let authorizer =
+            RouterAuthorizer::new(["/users/{id}/avatar", "/webhooks/{*provider}"]).unwrap();
+        let layer = SecFetchLayer::default().with_authorizer(authorizer);
+
+        for (path, allowed) in [
+            ("/users/42/avatar", true),
+            ("/webhooks/github/events", true),
+            ("/users/42", false),
+            ("/users/42/avatar/large", false),
+        ] {
+            let request = request!(path, site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{path}");
+                },
+                layer.clone()
+            );
+        }
+
+        check!(RouterAuthorizer::new(["/users/{id}", "/users/{name}"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn it_matches_versioned_paths() {
+        let layer = SecFetchLayer::default().allowing(["/api/{v}/webhooks"]);
+
+        for (path, allowed) in [
+            ("/api/v1/webhooks", true),
+            ("/api/v12/webhooks", true),
+            ("/api/v/webhooks", false),
+            ("/api/beta/webhooks", false),
+            ("/api/v1/webhooks/github", false),
+        ] {
+            let request = request!(path, site => "cross-site", mode => "cors", dest => "empty");
+
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(response.status().is_success() == allowed, "{path}");
+                },
+                layer.clone()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn it_allows_denied_requests_if_enforcement_is_turned_off() {
+        let layer = SecFetchLayer::default().no_enforce();
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+    }
+
+    #[derive(Default)]
+    struct TestReporter {
+        called: AtomicBool,
+    }
+
+    impl SecFetchReporter for TestReporter {
+        fn on_request_denied<B>(&self, _: &http::Request<B>) {
+            self.called.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_reports_a_denied_requests() {
+        let reporter = Arc::new(TestReporter::default());
+        let layer = SecFetchLayer::default().with_reporter(reporter.clone());
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+
+        let called = reporter.called.load(Ordering::SeqCst);
+        check!(
+            called,
+            "reporter was not called despite the request being rejected"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_accepts_closure_reporters() {
+        let called = Arc::new(AtomicBool::new(false));
+        let reporter = FnReporter::new({
+            let called = called.clone();
+            move |request| {
+                check!(request.uri().path() == "/");
+                called.store(true, Ordering::SeqCst);
+            }
+        });
+        let layer = SecFetchLayer::default().with_reporter(reporter);
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+
+        check!(called.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_accepts_boxed_reporters() {
+        let reporter = Arc::new(TestReporter::default());
+        let layer = SecFetchLayer::default().with_reporter(BoxReporter::new(reporter.clone()));
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+
+        check!(reporter.called.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn it_lends_the_request_head_to_boxed_authorizers_and_reporters() {
+        let called = Arc::new(AtomicBool::new(false));
+        let reporter = FnReporter::new({
+            let called = called.clone();
+            move |request| {
+                check!(request.method() == Method::POST);
+                check!(request.uri().path() == "/submit");
+                check!(request.headers()[header::SEC_FETCH_SITE] == "cross-site");
+                called.store(true, Ordering::SeqCst);
+            }
+        });
+        let layer = SecFetchLayer::default()
+            .no_enforce()
+            .allowing(["/webhooks"])
+            .with_reporter(reporter)
+            .boxed();
+        let request = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+
+        let (service, mut handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<()>, _>(layer);
+
+        tokio::spawn(async move {
+            let_assert!(Some((request, send)) = handler.next_request().await);
+            // the head is given back to the request after the boxed calls
+            check!(request.method() == Method::POST);
+            check!(request.uri().path() == "/submit");
+            check!(request.headers()[header::SEC_FETCH_SITE] == "cross-site");
+            check!(request.extensions().get::<DenialReason>() == Some(&DenialReason::CrossSite));
+            send.send_response(http::Response::new(()));
+        });
+
+        let response = service.into_inner().oneshot(request).await.unwrap();
+
+        check!(response.status().is_success());
+        check!(called.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "chaos")]
+    #[tokio::test(start_paused = true)]
+    async fn it_delays_responses_asynchronously_in_chaos_mode() {
+        let delay = std::time::Duration::from_secs(60);
+        let layer = SecFetchLayer::default().with_chaos(Chaos::new(42).delay_rate(1.0, delay));
+        let request = request!(site => "same-origin", mode => "cors", dest => "empty");
+        let start = tokio::time::Instant::now();
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
+
+        // the paused clock only advances while the runtime waits on an async timer
+        check!(start.elapsed() >= delay);
+    }
+
+    #[cfg(feature = "chaos")]
+    #[tokio::test]
+    async fn it_flips_verdicts_in_chaos_mode() {
+        let layer = SecFetchLayer::default().with_chaos(Chaos::new(42).flip_rate(1.0));
+        let request = request!(site => "same-site", mode => "navigate", dest => "document");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[tokio::test]
+    async fn it_collects_stats_of_denied_requests() {
+        let stats = StatsCollector::new();
+        let layer = SecFetchLayer::default().with_reporter(stats.clone());
+        let request = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+
+        let response = stats
+            .service()
+            .oneshot(http::Request::new(()))
+            .await
+            .unwrap();
+        check!(
+            response.body()
+                == r#"{"denied":1,"by_method":{"POST":1},"by_path":{"/submit":1},"evaluated":1,"missing_metadata":0}"#
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[tokio::test]
+    async fn it_collects_stats_of_requests_missing_metadata() {
+        let stats = StatsCollector::new();
+        let layer = SecFetchLayer::default().with_reporter(stats.clone());
+
+        let request = request!(Method::POST, "/submit", site => "same-origin", mode => "cors", dest => "empty");
+        assert_request!(request, |_| {}, layer.clone());
+
+        let request = http::Request::post("/submit")
+            .header(http::header::USER_AGENT, "curl/8.5.0")
+            .body(())
+            .unwrap();
+        assert_request!(request, |_| {}, layer);
+
+        let snapshot = stats.snapshot();
+        check!(snapshot.evaluated == 2);
+        check!(snapshot.missing_metadata == 1);
+        check!(snapshot.missing_metadata_ratio() == 0.5);
+        check!(snapshot.missing_metadata_by_path["/submit"] == 1);
+        check!(snapshot.missing_metadata_by_user_agent["non-browser"] == 1);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn it_caps_the_number_of_paths_in_the_stats() {
+        let stats = StatsCollector::new().max_keys(2);
+        for path in ["/a", "/b", "/c", "/d", "/a"] {
+            stats.on_request_denied(&http::Request::post(path).body(()).unwrap());
+        }
+
+        let snapshot = stats.snapshot();
+        check!(snapshot.denied == 5);
+        check!(snapshot.by_path.len() == 3);
+        check!(snapshot.by_path["/a"] == 2);
+        check!(snapshot.by_path["/b"] == 1);
+        check!(snapshot.by_path[StatsCollector::OTHER] == 2);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn it_collects_stats_by_route() {
+        let stats = StatsCollector::new().routes(["/users/*"]);
+        for path in ["/users/1", "/users/2", "/random-1", "/random-2"] {
+            stats.on_request_denied(&http::Request::post(path).body(()).unwrap());
+        }
+
+        let snapshot = stats.snapshot();
+        check!(snapshot.by_path.len() == 2);
+        check!(snapshot.by_path["/users/*"] == 2);
+        check!(snapshot.by_path[StatsCollector::OTHER] == 2);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn it_caps_the_number_of_paths_missing_metadata_in_the_stats() {
+        let stats = StatsCollector::new().max_keys(1);
+        for path in ["/a", "/b", "/c"] {
+            stats.on_request_evaluated(&http::Request::post(path).body(()).unwrap());
+        }
+
+        let snapshot = stats.snapshot();
+        check!(snapshot.evaluated == 3);
+        check!(snapshot.missing_metadata == 3);
+        check!(snapshot.missing_metadata_by_path.len() == 2);
+        check!(snapshot.missing_metadata_by_path["/a"] == 1);
+        check!(snapshot.missing_metadata_by_path[StatsCollector::OTHER] == 2);
+    }
+
+    #[tokio::test]
+    async fn it_uses_the_custom_denial_response() {
+        let layer = SecFetchLayer::default().on_denied(|denial: &DenialContext<'_>| {
+            http::Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .header("x-denied-path", denial.uri().path())
+                .body(())
+                .unwrap()
+        });
+        let request = request!("/secret", site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::NOT_FOUND);
+                check!(response.headers()["x-denied-path"] == "/secret");
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_redirects_denied_navigations_if_configured() {
+        let layer = SecFetchLayer::default().redirect_on_deny("/blocked");
+        let request = request!(site => "cross-site", mode => "navigate", dest => "iframe");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::SEE_OTHER);
+                check!(response.headers()[http::header::LOCATION] == "/blocked");
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_does_not_redirect_denied_subresource_requests() {
+        let layer = SecFetchLayer::default().redirect_on_deny("/blocked");
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_forwards_denied_requests_to_the_fallback_service() {
+        let fallback = tower::service_fn(async |request: http::Request<()>| {
+            let reason = request.extensions().get::<DenialReason>().copied();
+            check!(reason == Some(DenialReason::CrossSite));
+
+            Ok(http::Response::builder()
+                .status(StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS)
+                .body(())
+                .unwrap())
+        });
+        let layer = SecFetchLayer::default().with_denied_service(fallback);
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_uses_the_status_configured_for_the_denial_reason() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.reject_missing_metadata();
+        })
+        .status_for(|reason| match reason {
+            DenialReason::MissingMetadata => StatusCode::BAD_REQUEST,
+            _ => StatusCode::FORBIDDEN,
+        });
+
+        assert_request!(
+            http::Request::new(()),
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::BAD_REQUEST);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_denial_id_if_configured() {
+        let layer = SecFetchLayer::default().denial_ids();
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+                let_assert!(Some(id) = response.headers().get(header::X_DENIAL_ID));
+                check!(id.len() == 32);
+            },
+            layer
+        );
+    }
+
+    #[tokio::test]
+    async fn it_classifies_denials_by_severity() {
+        #[derive(Default)]
+        struct SeverityReporter(std::sync::Mutex<Option<Severity>>);
+
+        impl SecFetchReporter for SeverityReporter {
+            fn on_request_denied<B>(&self, request: &http::Request<B>) {
+                *self.0.lock().unwrap() = request.extensions().get::<Severity>().copied();
+            }
+        }
+
+        let reporter = Arc::new(SeverityReporter::default());
+        let layer = SecFetchLayer::default()
+            .severities(
+                Severities::default().rule(
+                    SeverityRule::new(Severity::Critical)
+                        .reason(DenialReason::CrossSite)
+                        .method(Method::POST),
+                ),
+            )
+            .with_reporter(reporter.clone());
+
+        let request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer.clone()
+        );
+        check!(*reporter.0.lock().unwrap() == Some(Severity::Critical));
+
+        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
+        check!(*reporter.0.lock().unwrap() == Some(Severity::Warn));
+    }
+
+    #[test]
+    fn it_simulates_requests() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.protect_methods([Method::POST]);
+        });
+        let headers = [
+            ("sec-fetch-site", "cross-site"),
+            ("sec-fetch-mode", "cors"),
+            ("sec-fetch-dest", "empty"),
+        ];
+
+        check!(
+            layer.simulate("POST", "/", &headers).unwrap()
+                == Verdict::Denied(DenialReason::CrossSite)
+        );
+        check!(layer.simulate("PUT", "/", &headers).unwrap() == Verdict::Allowed);
+        check!(layer.simulate("POST", "/", &[]).unwrap() == Verdict::Allowed);
+        check!(layer.simulate("POST", "not a path", &headers).is_err());
     }
 
-    fn call(&mut self, request: http::Request<ReqB>) -> Self::Future {
-        #[cfg(feature = "tracing")]
-        tracing::debug!(
-            method = %request.method(),
-            path = request.uri().path(),
-            "processing request",
+    #[test]
+    fn it_simulates_safe_methods_allowed_after_report() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .allow_safe_methods()
+                .safe_methods_order(SafeMethodsOrder::AfterReport);
+        });
+        let headers = [
+            ("sec-fetch-site", "cross-site"),
+            ("sec-fetch-mode", "cors"),
+            ("sec-fetch-dest", "empty"),
+        ];
+
+        let verdict = layer.simulate("GET", "/", &headers).unwrap();
+        check!(verdict == Verdict::Reported(DenialReason::CrossSite));
+        check!(verdict.is_allowed());
+        check!(
+            layer.simulate("POST", "/", &headers).unwrap()
+                == Verdict::Denied(DenialReason::CrossSite)
         );
+        check!(layer.decision_table().decisions().iter().any(|decision| {
+            decision.method == Method::GET
+                && decision.verdict == Verdict::Reported(DenialReason::CrossSite)
+        }));
+    }
 
-        let mut allow = |request: http::Request<ReqB>| {
-            #[cfg(feature = "tracing")]
-            tracing::debug!(
-                method = %request.method(),
-                path = request.uri().path(),
-                "request allowed",
+    #[test]
+    fn it_exports_the_decision_table() {
+        let verdict = |table: &DecisionTable, site, mode, dest, user, method| {
+            let_assert!(
+                Some(decision) = table.decisions().iter().find(|decision| {
+                    decision.site == site
+                        && decision.mode == mode
+                        && decision.dest == dest
+                        && decision.user == user
+                        && decision.method == method
+                })
             );
-
-            Either::Left(self.inner.call(request))
+            decision.verdict
+        };
+        let table = SecFetchLayer::default().decision_table();
+        let denial = |site, mode, dest, method| {
+            verdict(&table, Some(site), Some(mode), Some(dest), false, method).reason()
         };
 
-        let deny = || {
-            #[cfg(feature = "tracing")]
-            tracing::debug!(
-                method = %request.method(),
-                path = request.uri().path(),
-                "request denied",
-            );
+        check!(
+            table.decisions().len()
+                == (Site::ALL.len() + 1)
+                    * (Mode::ALL.len() + 2)
+                    * (Dest::ALL.len() + 1)
+                    * DecisionTable::METHODS.len()
+        );
+        check!(denial(Site::SameSite, Mode::Cors, Dest::Empty, Method::POST) == None);
+        check!(denial(Site::CrossSite, Mode::Navigate, Dest::Document, Method::GET) == None);
+        check!(
+            denial(
+                Site::CrossSite,
+                Mode::Navigate,
+                Dest::Document,
+                Method::POST
+            ) == Some(DenialReason::CrossSite)
+        );
+        check!(
+            denial(Site::CrossSite, Mode::Navigate, Dest::Iframe, Method::GET)
+                == Some(DenialReason::CrossSite)
+        );
+        check!(verdict(&table, None, None, None, false, Method::POST) == Verdict::Allowed);
+    }
 
-            Either::Right(future::ready(Ok(http::Response::builder()
-                .status(StatusCode::FORBIDDEN)
-                .body(ResB::default())
-                .expect("valid response"))))
+    #[test]
+    fn it_exports_the_decision_table_as_applied_by_the_layer() {
+        let table = SecFetchLayer::new(|policy| {
+            policy
+                .reject_missing_metadata()
+                .protect_methods([Method::GET, Method::POST])
+                .require_user_activation(["/**"]);
+        })
+        .decision_table();
+        let verdict = |site, mode, dest, user, method| {
+            let_assert!(
+                Some(decision) = table.decisions().iter().find(|decision| {
+                    decision.site == site
+                        && decision.mode == mode
+                        && decision.dest == dest
+                        && decision.user == user
+                        && decision.method == method
+                })
+            );
+            decision.verdict
         };
 
-        match self.authorizer.authorize(&request) {
-            AuthorizationDecision::Allowed => return allow(request),
-            AuthorizationDecision::Denied => return deny(),
-            AuthorizationDecision::Continue => {}
-        }
-
-        if self.policy.allow(&request) {
-            return allow(request);
-        }
+        check!(
+            verdict(None, None, None, false, Method::POST)
+                == Verdict::Denied(DenialReason::MissingMetadata)
+        );
+        // unprotected methods are allowed like the layer does
+        check!(verdict(None, None, None, false, Method::PUT) == Verdict::Allowed);
+        check!(
+            verdict(
+                Some(Site::CrossSite),
+                Some(Mode::Navigate),
+                Some(Dest::Document),
+                false,
+                Method::GET
+            ) == Verdict::Denied(DenialReason::MissingUserActivation)
+        );
+        check!(
+            verdict(
+                Some(Site::CrossSite),
+                Some(Mode::Navigate),
+                Some(Dest::Document),
+                true,
+                Method::GET
+            ) == Verdict::Allowed
+        );
+    }
 
-        self.reporter.on_request_denied(&request);
+    #[test]
+    fn it_describes_the_pipeline() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy.protect_methods([Method::POST]);
+        })
+        .problem_json();
 
-        // the request was denied, but we are not enforcing it
-        // we report the failure and let the request continue
-        if !self.enforce {
-            return allow(request);
-        }
+        let expected = Pipeline::new()
+            .step(Step::Skip)
+            .step(Step::Protect {
+                methods: Some(vec![String::from("POST")]),
+                only_with_credentials: false,
+                session_cookie: None,
+            })
+            .step(Step::Authorizer {
+                name: String::from("tower_sec_fetch::authorizer::NoopAuthorizer"),
+            })
+            .step(Step::Policy {
+                reject_missing_metadata: false,
+                go_compat: false,
+            })
+            .step(Step::Report {
+                name: String::from("tower_sec_fetch::reporter::NoopReporter"),
+            })
+            .step(Step::Respond {
+                name: String::from("tower_sec_fetch::responder::ProblemJsonResponder"),
+            });
 
-        deny()
+        check!(layer.pipeline() == expected);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::atomic::{AtomicBool, Ordering};
+    #[test]
+    fn it_describes_every_step_of_the_pipeline() {
+        let layer = SecFetchLayer::new(|policy| {
+            policy
+                .untrusted_paths(["/uploads/"])
+                .deny_navigation_to(["/logout"])
+                .allow_preflights()
+                .allow_safe_methods()
+                .safe_methods_order(SafeMethodsOrder::AfterReport)
+                .canonical_origin("https://www.example.com");
+        })
+        .report_only_paths(["/beta/**"])
+        .with_denied_service(tower::service_fn(|_: http::Request<()>| async {
+            Ok::<_, std::convert::Infallible>(http::Response::new(()))
+        }));
 
-    use assert2::{check, let_assert};
-    use http::Method;
-    use tower::ServiceExt;
-    use tower_test::mock;
+        let steps: Vec<_> = layer
+            .pipeline()
+            .steps()
+            .iter()
+            .map(|step| match step {
+                Step::Fallback { .. } => String::from("fallback"),
+                step => step.to_string(),
+            })
+            .collect();
 
-    use super::*;
+        check!(
+            steps
+                == [
+                    "skip requests opted out with SkipSecFetch",
+                    "authorize with tower_sec_fetch::authorizer::NoopAuthorizer",
+                    "deny embedding untrusted paths /uploads/",
+                    "deny navigating to sensitive paths /logout",
+                    "allow CORS preflights",
+                    "evaluate policy (reject missing metadata: false, go compat: false)",
+                    "report denials to tower_sec_fetch::reporter::NoopReporter",
+                    "allow safe methods (GET, HEAD, OPTIONS)",
+                    "allow denied requests to /beta/**",
+                    "let denied requests be redirected to https://www.example.com/",
+                    "fallback",
+                    "respond to denied requests with tower_sec_fetch::responder::ForbiddenResponder",
+                ]
+        );
+    }
 
-    macro_rules! request {
-        (site => $site:expr, mode => $mode:expr, dest => $dest:expr) => {
-            request!(::http::Method::GET, "/", site => $site, mode => $mode, dest => $dest)
-        };
+    #[tokio::test]
+    async fn it_provides_an_api_default() {
+        let (service, _handler) = mock::spawn_layer::<http::Request<()>, http::Response<String>, _>(
+            SecFetchLayer::api_default(),
+        );
+        let request = ::http::Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/")
+            .body(())
+            .unwrap();
 
-        ($path:expr, site => $site:expr, mode => $mode:expr, dest => $dest:expr) => {
-            request!(::http::Method::GET, $path, site => $site, mode => $mode, dest => $dest)
-        };
+        let response = service.into_inner().oneshot(request).await.unwrap();
 
-        ($method:expr, $path:expr, site => $site:expr, mode => $mode:expr, dest => $dest:expr) => {
-            ::http::Request::builder()
-                .method($method)
-                .uri(format!("https://example.com{}", $path))
-                .header(header::SEC_FETCH_SITE, $site)
-                .header(header::SEC_FETCH_MODE, $mode)
-                .header(header::SEC_FETCH_DEST, $dest)
-                .body(())
-                .unwrap()
-        };
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(response.headers()[http::header::CONTENT_TYPE] == "application/problem+json");
     }
 
-    macro_rules! assert_request {
-        ($req:expr, $assert_resp:expr) => {
-            assert_request!($req, $assert_resp, SecFetchLayer::default())
-        };
+    #[tokio::test]
+    async fn it_closes_the_connection_on_large_denied_bodies() {
+        let layer = SecFetchLayer::default().close_above(1024);
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(http::header::CONTENT_LENGTH, "4096".parse().unwrap());
 
-        ($req:expr, $assert_resp:expr, $layer:expr) => {
-            let (service, mut handler) =
-                mock::spawn_layer::<http::Request<()>, http::Response<()>, _>($layer);
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+                check!(response.headers()[http::header::CONNECTION] == "close");
+            },
+            layer.clone()
+        );
 
-            tokio::spawn(async move {
-                let_assert!(Some((_, send)) = handler.next_request().await);
-                send.send_response(http::Response::new(()));
-            });
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        *request.version_mut() = http::Version::HTTP_2;
+        request
+            .headers_mut()
+            .insert(http::header::CONTENT_LENGTH, "4096".parse().unwrap());
 
-            let response = service.into_inner().oneshot($req).await.unwrap();
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.headers().get(http::header::CONNECTION) == None);
+                check!(response.extensions().get::<AbortStream>().is_some());
+            },
+            layer.clone()
+        );
 
-            ($assert_resp)(response);
-        };
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(http::header::CONTENT_LENGTH, "512".parse().unwrap());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.headers().get(http::header::CONNECTION) == None);
+            },
+            layer
+        );
     }
 
     #[tokio::test]
-    async fn it_allows_requests_missing_the_fetch_metadata() {
-        let request = http::Request::new(());
+    async fn it_closes_the_connection_on_denied_bodies_of_unknown_length() {
+        let layer = SecFetchLayer::default().close_above(1024);
 
-        assert_request!(request, |response: http::Response<()>| {
-            check!(response.status().is_success());
-        });
-    }
+        for (header, value) in [
+            (http::header::TRANSFER_ENCODING, "chunked"),
+            (http::header::CONTENT_LENGTH, "not a number"),
+        ] {
+            let mut request =
+                request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+            request
+                .headers_mut()
+                .insert(header.clone(), value.parse().unwrap());
 
-    #[tokio::test]
-    async fn it_rejects_requests_missing_the_fetch_metadata_if_configured() {
-        let layer = SecFetchLayer::new(|policy| {
-            policy.reject_missing_metadata();
-        });
-        let request = http::Request::new(());
+            assert_request!(
+                request,
+                |response: http::Response<()>| {
+                    check!(
+                        response.headers()[http::header::CONNECTION] == "close",
+                        "{header}"
+                    );
+                },
+                layer.clone()
+            );
+        }
+
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        *request.version_mut() = http::Version::HTTP_2;
 
         assert_request!(
             request,
+            |response: http::Response<()>| {
+                check!(response.extensions().get::<AbortStream>().is_some());
+            },
+            layer.clone()
+        );
+
+        assert_request!(
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty"),
             |response: http::Response<()>| {
                 check!(response.status() == StatusCode::FORBIDDEN);
+                check!(response.headers().get(http::header::CONNECTION) == None);
             },
             layer
         );
     }
 
     #[tokio::test]
-    async fn it_allows_same_site_requests() {
+    #[should_panic(expected = "already been evaluated")]
+    #[cfg(debug_assertions)]
+    async fn it_panics_on_duplicate_layers_if_configured() {
+        let layer = tower::ServiceBuilder::new()
+            .layer(SecFetchLayer::default())
+            .layer(SecFetchLayer::default().panic_on_duplicate());
         let request = request!(site => "same-site", mode => "navigate", dest => "document");
 
-        assert_request!(request, |response: http::Response<()>| {
-            check!(response.status().is_success());
-        });
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status().is_success());
+            },
+            layer
+        );
     }
 
     #[tokio::test]
-    async fn it_rejects_cross_origin_requests() {
+    async fn it_responds_with_problem_json_if_configured() {
+        let layer = SecFetchLayer::default().problem_json();
         let request = request!(site => "cross-site", mode => "cors", dest => "empty");
 
-        assert_request!(request, |response: http::Response<()>| {
-            check!(response.status() == StatusCode::FORBIDDEN);
-        });
+        let (service, _handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<String>, _>(layer);
+
+        let response = service.into_inner().oneshot(request).await.unwrap();
+
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(response.headers()[http::header::CONTENT_TYPE] == "application/problem+json");
+        check!(response.body().contains(r#""reason":"cross-site""#));
     }
 
+    #[cfg(feature = "template")]
     #[tokio::test]
-    async fn it_allows_cross_origin_requests_safe_methods_if_configured() {
-        let layer = SecFetchLayer::new(|policy| {
-            policy.allow_safe_methods();
-        });
+    async fn it_renders_the_html_template_for_denied_navigations() {
+        let layer = SecFetchLayer::default().on_denied(HtmlTemplateResponder::new(
+            "<p>{{ method }} {{path}}: {{ reason_id }}{{ unknown }}</p>",
+        ));
         let request =
-            request!(Method::GET, "/", site => "cross-site", mode => "cors", dest => "empty");
+            request!("/embed", site => "cross-site", mode => "navigate", dest => "iframe");
+
+        let (service, _handler) =
+            mock::spawn_layer::<http::Request<()>, http::Response<String>, _>(layer);
+
+        let response = service.into_inner().oneshot(request).await.unwrap();
+
+        check!(response.status() == StatusCode::FORBIDDEN);
+        check!(response.headers()[http::header::CONTENT_TYPE] == "text/html; charset=utf-8");
+        check!(response.body() == "<p>GET /embed: cross-site</p>");
+    }
+
+    #[cfg(feature = "gateway")]
+    #[tokio::test]
+    async fn it_allows_requests_signed_by_the_gateway() {
+        let authorizer = GatewayAuthorizer::new(b"secret".to_vec());
+        let mut request = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+        authorizer.sign(&mut request, SystemTime::now());
 
         assert_request!(
             request,
             |response: http::Response<()>| {
                 check!(response.status().is_success());
             },
-            layer
+            SecFetchLayer::default().with_authorizer(authorizer)
         );
     }
 
+    #[cfg(feature = "gateway")]
     #[tokio::test]
-    async fn it_allows_navigation_requests() {
-        let request = request!(site => "cross-site", mode => "navigate", dest => "document");
+    async fn it_rejects_replayed_gateway_signatures() {
+        let authorizer = GatewayAuthorizer::new(b"secret".to_vec());
+        let mut request = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+        authorizer.sign(&mut request, SystemTime::now());
+        let mut replayed = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+        *replayed.headers_mut() = request.headers().clone();
 
-        assert_request!(request, |response: http::Response<()>| {
-            check!(response.status().is_success());
-        });
+        check!(matches!(
+            authorizer.authorize(&request),
+            AuthorizationDecision::Allowed
+        ));
+        check!(matches!(
+            authorizer.authorize(&replayed),
+            AuthorizationDecision::Denied
+        ));
     }
 
+    #[cfg(feature = "gateway")]
     #[tokio::test]
-    async fn it_rejects_navigation_requests_resulting_from_embedding() {
-        let request = request!(site => "cross-site", mode => "navigate", dest => "iframe");
+    async fn it_rejects_gateway_signatures_outside_the_skew_window() {
+        let authorizer =
+            GatewayAuthorizer::new(b"secret".to_vec()).max_skew(std::time::Duration::from_secs(30));
+        let mut stale = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+        authorizer.sign(
+            &mut stale,
+            SystemTime::now() - std::time::Duration::from_secs(60),
+        );
+        let mut future = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
+        authorizer.sign(
+            &mut future,
+            SystemTime::now() + std::time::Duration::from_secs(60),
+        );
 
-        assert_request!(request, |response: http::Response<()>| {
-            check!(response.status() == StatusCode::FORBIDDEN);
-        });
+        check!(matches!(
+            authorizer.authorize(&stale),
+            AuthorizationDecision::Denied
+        ));
+        check!(matches!(
+            authorizer.authorize(&future),
+            AuthorizationDecision::Denied
+        ));
     }
 
+    #[cfg(feature = "gateway")]
     #[tokio::test]
-    async fn it_ignores_explicitely_authorized_requests() {
-        let layer = SecFetchLayer::default().allowing(["/allowed"]);
-        let request = request!("/allowed", site => "cross-site", mode => "cors", dest => "empty");
+    async fn it_rejects_requests_with_an_invalid_gateway_signature() {
+        let layer =
+            SecFetchLayer::default().with_authorizer(GatewayAuthorizer::new(b"secret".to_vec()));
+        let mut request = request!(site => "same-origin", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(DEFAULT_SIGNATURE_HEADER, "deadbeef".parse().unwrap());
 
         assert_request!(
             request,
             |response: http::Response<()>| {
-                check!(response.status().is_success());
+                check!(response.status() == StatusCode::FORBIDDEN);
             },
             layer
         );
     }
 
+    #[cfg(feature = "exemptions")]
     #[tokio::test]
-    async fn it_allows_denied_requests_if_enforcement_is_turned_off() {
-        let layer = SecFetchLayer::default().no_enforce();
-        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+    async fn it_allows_requests_with_a_valid_exemption_token() {
+        let exemptions = ExemptionTokens::new(b"secret".to_vec());
+        let token = exemptions.mint("partner", std::time::Duration::from_secs(60));
+        let layer = SecFetchLayer::default().with_exemptions(exemptions);
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(DEFAULT_EXEMPTION_HEADER, token.parse().unwrap());
 
         assert_request!(
             request,
@@ -494,22 +5167,56 @@ mod tests {
         );
     }
 
-    #[derive(Default)]
-    struct TestReporter {
-        called: AtomicBool,
+    #[cfg(feature = "exemptions")]
+    #[tokio::test]
+    async fn it_ignores_expired_exemption_tokens() {
+        let exemptions = ExemptionTokens::new(b"secret".to_vec());
+        let token = exemptions.mint("partner", std::time::Duration::ZERO);
+        let layer = SecFetchLayer::default().with_exemptions(exemptions);
+        let mut request =
+            request!(Method::POST, "/", site => "cross-site", mode => "cors", dest => "empty");
+        request
+            .headers_mut()
+            .insert(DEFAULT_EXEMPTION_HEADER, token.parse().unwrap());
+
+        assert_request!(
+            request,
+            |response: http::Response<()>| {
+                check!(response.status() == StatusCode::FORBIDDEN);
+            },
+            layer
+        );
     }
 
-    impl SecFetchReporter for TestReporter {
-        fn on_request_denied<B>(&self, _: &http::Request<B>) {
-            self.called.store(true, Ordering::SeqCst);
-        }
+    #[cfg(feature = "exemptions")]
+    #[tokio::test]
+    async fn it_accepts_exemption_tokens_signed_with_rotated_keys() {
+        let keyring = Keyring::new("old", b"old-secret".to_vec());
+        let exemptions = ExemptionTokens::new(keyring.clone());
+        let token = exemptions.mint("partner", std::time::Duration::from_secs(60));
+        keyring.rotate("new", b"new-secret".to_vec());
+
+        let now = std::time::SystemTime::now();
+        check!(exemptions.verify(&token, now).is_some());
+
+        let rotated = exemptions.mint("partner", std::time::Duration::from_secs(60));
+        check!(rotated.contains(".new."));
+
+        keyring.retire("old");
+        check!(exemptions.verify(&token, now).is_none());
+        check!(exemptions.verify(&rotated, now).is_some());
     }
 
+    #[cfg(feature = "reports")]
     #[tokio::test]
-    async fn it_reports_a_denied_requests() {
-        let reporter = Arc::new(TestReporter::default());
-        let layer = SecFetchLayer::default().with_reporter(reporter.clone());
-        let request = request!(site => "cross-site", mode => "cors", dest => "empty");
+    async fn it_signs_violation_reports() {
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let reporter = SignedReporter::new(ReportSigner::new(b"secret".to_vec()), move |report| {
+            sink.lock().unwrap().push(report);
+        });
+        let layer = SecFetchLayer::default().with_reporter(reporter);
+        let request = request!(Method::POST, "/submit", site => "cross-site", mode => "cors", dest => "empty");
 
         assert_request!(
             request,
@@ -519,10 +5226,174 @@ mod tests {
             layer
         );
 
-        let called = reporter.called.load(Ordering::SeqCst);
-        check!(
-            called,
-            "reporter was not called despite the request being rejected"
-        );
+        let signer = ReportSigner::new(b"secret".to_vec());
+        let mut reports = reports.lock().unwrap();
+        let_assert!([report] = reports.as_mut_slice());
+        check!(report.payload.contains(r#""path":"/submit""#));
+        check!(signer.verify(report));
+
+        report.payload = report.payload.replace("/submit", "/other");
+        check!(!signer.verify(report));
+    }
+
+    /// Records the integer fields recorded on spans, and counts events, keeping the level and boolean fields of each
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Recorder {
+        events: Arc<std::sync::atomic::AtomicUsize>,
+        logged: Arc<std::sync::Mutex<Vec<(tracing::Level, EventFields)>>>,
+        spans: Arc<std::sync::Mutex<Vec<&'static tracing::Metadata<'static>>>>,
+        entered: Arc<std::sync::Mutex<Vec<u64>>>,
+        fields: Arc<std::sync::Mutex<Vec<(&'static str, i64)>>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Debug, Default)]
+    struct EventFields(Vec<(&'static str, bool)>);
+
+    #[cfg(feature = "tracing")]
+    impl EventFields {
+        fn get(&self, name: &str) -> Option<bool> {
+            self.0
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| *value)
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for EventFields {
+        fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+            self.0.push((field.name(), value));
+        }
+
+        fn record_debug(&mut self, _: &tracing::field::Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for Recorder {
+        fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+            self.fields.lock().unwrap().push((field.name(), value));
+        }
+
+        fn record_debug(&mut self, _: &tracing::field::Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata());
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = EventFields::default();
+            event.record(&mut fields);
+            self.logged
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields));
+            self.events.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn enter(&self, span: &tracing::span::Id) {
+            self.entered.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _: &tracing::span::Id) {
+            self.entered.lock().unwrap().pop();
+        }
+
+        fn current_span(&self) -> tracing_core::span::Current {
+            match self.entered.lock().unwrap().last() {
+                Some(&id) => tracing_core::span::Current::new(
+                    tracing::span::Id::from_u64(id),
+                    self.spans.lock().unwrap()[id as usize - 1],
+                ),
+                None => tracing_core::span::Current::none(),
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn it_records_the_sampling_priority_of_denied_requests() {
+        use tracing::Instrument;
+
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+        let layer = SecFetchLayer::default().sampling_priority(SamplingPriority::default());
+
+        for (site, denied) in [("same-origin", false), ("cross-site", true)] {
+            recorder.fields.lock().unwrap().clear();
+            let request =
+                request!(Method::POST, "/", site => site, mode => "cors", dest => "empty");
+            let span = tracing::info_span!("request", sampling.priority = tracing::field::Empty);
+
+            let (service, mut handler) =
+                mock::spawn_layer::<http::Request<()>, http::Response<()>, _>(layer.clone());
+            if !denied {
+                tokio::spawn(async move {
+                    let_assert!(Some((_, send)) = handler.next_request().await);
+                    send.send_response(http::Response::new(()));
+                });
+            }
+            let response = service
+                .into_inner()
+                .oneshot(request)
+                .instrument(span)
+                .await
+                .unwrap();
+
+            check!(response.status().is_success() == !denied);
+            let fields = recorder.fields.lock().unwrap().clone();
+            if denied {
+                check!(fields == [("sampling.priority", 2)]);
+            } else {
+                check!(fields.is_empty());
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn it_logs_the_configuration_once_per_layer() {
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+        let layer = SecFetchLayer::default();
+
+        // clones share the configuration, e.g. when routes are layered separately
+        for layer in [&layer, &layer.clone(), &layer.clone()] {
+            let _ = layer.layer(tower::service_fn(|_: http::Request<()>| async {
+                Ok::<_, std::convert::Infallible>(http::Response::new(()))
+            }));
+        }
+        check!(recorder.events.load(Ordering::SeqCst) == 1);
+
+        // a reconfigured layer logs its own configuration
+        let layer = layer.clone().no_enforce();
+        let _ = layer.layer(tower::service_fn(|_: http::Request<()>| async {
+            Ok::<_, std::convert::Infallible>(http::Response::new(()))
+        }));
+        check!(recorder.events.load(Ordering::SeqCst) == 2);
+
+        let logged = recorder.logged.lock().unwrap();
+        let_assert!([(first, enforced), (second, reconfigured)] = logged.as_slice());
+        check!(*first == tracing::Level::INFO);
+        check!(*second == tracing::Level::INFO);
+        check!(enforced.get("enforce") == Some(true));
+        check!(enforced.get("allow_safe_methods") == Some(false));
+        check!(reconfigured.get("enforce") == Some(false));
     }
 }
@@ -0,0 +1,384 @@
+use std::{collections::BTreeMap, fmt};
+
+use http::HeaderValue;
+
+/// Decision table of the evaluation policy, mapping the `sec-fetch-dest`, `sec-fetch-mode`, and `sec-fetch-site` headers
+/// of a request to an [Action]
+///
+/// The [default](Matrix::default) matrix allows all same-origin, same-site, and user-initiated requests,
+/// allows cross-site `GET` navigations to `empty` and `document` destinations, and denies all other cross-site requests.
+///
+/// Entries set for a [Mode] with [set_mode](Matrix::set_mode) take precedence over the entries of the destination.
+/// Combinations without an explicit entry use the action of their [Site], see [set_site](Matrix::set_site).
+/// A [Deny](Action::Deny) still defers to the allow rules of the policy, such as [allow_embedding](crate::PolicyBuilder::allow_embedding).
+///
+/// ```
+/// use tower_sec_fetch::{Action, Dest, Matrix, Mode, SecFetchLayer, Site};
+///
+/// let matrix = Matrix::default()
+///     .set(Dest::Iframe, Site::SameSite, Action::Deny)
+///     .set(Dest::Image, Site::CrossSite, Action::Allow)
+///     .set_mode(Mode::Cors, Site::CrossSite, Action::Allow);
+///
+/// SecFetchLayer::new(|policy| {
+///     policy.matrix(matrix);
+/// });
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix {
+    sites: BTreeMap<Site, Action>,
+    modes: BTreeMap<(Mode, Site), Action>,
+    entries: BTreeMap<(Dest, Site), Action>,
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self {
+            sites: BTreeMap::new(),
+            modes: BTreeMap::new(),
+            entries: BTreeMap::new(),
+        }
+        .set_site(Site::SameOrigin, Action::Allow)
+        .set_site(Site::SameSite, Action::Allow)
+        .set_site(Site::None, Action::Allow)
+        .set_site(Site::CrossSite, Action::Deny)
+        .set(Dest::Empty, Site::CrossSite, Action::AllowNavigation)
+        .set(Dest::Document, Site::CrossSite, Action::AllowNavigation)
+    }
+}
+
+impl Matrix {
+    /// Sets the action for requests loading `dest` from `site`
+    pub fn set(mut self, dest: Dest, site: Site, action: Action) -> Self {
+        self.entries.insert((dest, site), action);
+        self
+    }
+
+    /// Sets the action for requests using `mode` from `site`, regardless of their destination
+    pub fn set_mode(mut self, mode: Mode, site: Site, action: Action) -> Self {
+        self.modes.insert((mode, site), action);
+        self
+    }
+
+    /// Sets the action for requests from `site` without an explicit entry for their destination
+    pub fn set_site(mut self, site: Site, action: Action) -> Self {
+        self.sites.insert(site, action);
+        self
+    }
+
+    /// The action for requests loading `dest` using `mode` from `site`, where `None` is an unknown destination or mode
+    pub fn action(&self, dest: Option<Dest>, mode: Option<Mode>, site: Site) -> Action {
+        mode.and_then(|mode| self.modes.get(&(mode, site)))
+            .or_else(|| dest.and_then(|dest| self.entries.get(&(dest, site))))
+            .or_else(|| self.sites.get(&site))
+            .copied()
+            .unwrap_or(Action::Deny)
+    }
+
+    /// Replaces the destinations allowed for cross-site navigations
+    pub(crate) fn navigation_destinations(&mut self, destinations: impl IntoIterator<Item = Dest>) {
+        self.entries.retain(|(_, site), action| {
+            *site != Site::CrossSite || *action != Action::AllowNavigation
+        });
+
+        for dest in destinations {
+            self.entries
+                .insert((dest, Site::CrossSite), Action::AllowNavigation);
+        }
+    }
+}
+
+/// The outcome of a [Matrix] entry
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Action {
+    /// The request is allowed
+    Allow,
+    /// The request is allowed if it is a `GET` navigation
+    AllowNavigation,
+    /// The request is denied, unless allowed by another rule of the policy
+    Deny,
+}
+
+/// The value of the `sec-fetch-site` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Site {
+    SameOrigin,
+    SameSite,
+    CrossSite,
+    /// The request was initiated by the user, e.g. by entering the address
+    None,
+}
+
+impl Site {
+    pub const ALL: [Self; 4] = [
+        Self::SameOrigin,
+        Self::SameSite,
+        Self::CrossSite,
+        Self::None,
+    ];
+
+    /// The header value of the site
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SameOrigin => "same-origin",
+            Self::SameSite => "same-site",
+            Self::CrossSite => "cross-site",
+            Self::None => "none",
+        }
+    }
+
+    /// Parses the header value, treating unknown values as [CrossSite](Site::CrossSite)
+    pub(crate) fn from_header(value: &HeaderValue) -> Self {
+        Self::from_bytes(value.as_bytes())
+    }
+
+    pub(crate) fn from_bytes(value: &[u8]) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|site| value == site.as_str().as_bytes())
+            .unwrap_or(Self::CrossSite)
+    }
+}
+
+impl fmt::Display for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The value of the `sec-fetch-mode` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Mode {
+    Cors,
+    Navigate,
+    NoCors,
+    SameOrigin,
+    Websocket,
+}
+
+impl Mode {
+    pub const ALL: &[Self] = &[
+        Self::Cors,
+        Self::Navigate,
+        Self::NoCors,
+        Self::SameOrigin,
+        Self::Websocket,
+    ];
+
+    /// The header value of the mode
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cors => "cors",
+            Self::Navigate => "navigate",
+            Self::NoCors => "no-cors",
+            Self::SameOrigin => "same-origin",
+            Self::Websocket => "websocket",
+        }
+    }
+
+    /// Parses the header value, returning `None` for unknown modes
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.as_str() == value)
+    }
+
+    pub(crate) fn from_header(value: &HeaderValue) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.as_str().as_bytes() == value.as_bytes())
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+macro_rules! dest {
+    ($($variant:ident => $value:literal,)*) => {
+        /// The value of the `sec-fetch-dest` header
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[non_exhaustive]
+        pub enum Dest {
+            $($variant,)*
+        }
+
+        impl Dest {
+            pub const ALL: &[Self] = &[$(Self::$variant,)*];
+
+            /// The header value of the destination
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $value,)*
+                }
+            }
+        }
+    };
+}
+
+dest! {
+    Audio => "audio",
+    AudioWorklet => "audioworklet",
+    Document => "document",
+    Embed => "embed",
+    Empty => "empty",
+    Fencedframe => "fencedframe",
+    Font => "font",
+    Frame => "frame",
+    Iframe => "iframe",
+    Image => "image",
+    Json => "json",
+    Manifest => "manifest",
+    Object => "object",
+    PaintWorklet => "paintworklet",
+    Report => "report",
+    Script => "script",
+    ServiceWorker => "serviceworker",
+    SharedWorker => "sharedworker",
+    Style => "style",
+    Track => "track",
+    Video => "video",
+    Websocket => "websocket",
+    WebIdentity => "webidentity",
+    Worker => "worker",
+    Xslt => "xslt",
+}
+
+impl Dest {
+    /// Parses the header value, returning `None` for unknown destinations
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|dest| dest.as_str() == value)
+    }
+
+    pub(crate) fn from_header(value: &HeaderValue) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|dest| dest.as_str().as_bytes() == value.as_bytes())
+    }
+}
+
+impl fmt::Display for Dest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_encodes_the_default_policy() {
+        let matrix = Matrix::default();
+
+        for dest in Dest::ALL.iter().copied().map(Some).chain([None]) {
+            for mode in Mode::ALL.iter().copied().map(Some).chain([None]) {
+                check!(matrix.action(dest, mode, Site::SameOrigin) == Action::Allow);
+                check!(matrix.action(dest, mode, Site::SameSite) == Action::Allow);
+                check!(matrix.action(dest, mode, Site::None) == Action::Allow);
+            }
+        }
+
+        let navigate = Some(Mode::Navigate);
+        check!(
+            matrix.action(Some(Dest::Document), navigate, Site::CrossSite)
+                == Action::AllowNavigation
+        );
+        check!(
+            matrix.action(Some(Dest::Empty), navigate, Site::CrossSite) == Action::AllowNavigation
+        );
+        check!(matrix.action(Some(Dest::Iframe), navigate, Site::CrossSite) == Action::Deny);
+        check!(matrix.action(None, navigate, Site::CrossSite) == Action::Deny);
+    }
+
+    #[test]
+    fn it_replaces_navigation_destinations() {
+        let mut matrix = Matrix::default();
+        matrix.navigation_destinations([Dest::Frame]);
+
+        check!(matrix.action(Some(Dest::Frame), None, Site::CrossSite) == Action::AllowNavigation);
+        check!(matrix.action(Some(Dest::Document), None, Site::CrossSite) == Action::Deny);
+    }
+
+    #[test]
+    fn it_prefers_mode_entries() {
+        let matrix = Matrix::default()
+            .set_mode(Mode::Cors, Site::CrossSite, Action::Allow)
+            .set_mode(Mode::Websocket, Site::SameSite, Action::Deny);
+
+        check!(
+            matrix.action(Some(Dest::Empty), Some(Mode::Cors), Site::CrossSite) == Action::Allow
+        );
+        check!(
+            matrix.action(Some(Dest::Empty), Some(Mode::NoCors), Site::CrossSite)
+                == Action::AllowNavigation
+        );
+        check!(
+            matrix.action(Some(Dest::Websocket), Some(Mode::Websocket), Site::SameSite)
+                == Action::Deny
+        );
+    }
+
+    #[test]
+    fn it_parses_header_values() {
+        check!(Dest::parse("iframe") == Some(Dest::Iframe));
+        check!(Dest::parse("unknown") == None);
+        check!(Mode::parse("no-cors") == Some(Mode::NoCors));
+        check!(Site::from_header(&HeaderValue::from_static("same-site")) == Site::SameSite);
+        check!(Site::from_header(&HeaderValue::from_static("unknown")) == Site::CrossSite);
+    }
+
+    #[test]
+    fn it_parses_header_bytes_like_strings() {
+        let values = Site::ALL
+            .iter()
+            .map(Site::as_str)
+            .chain(Mode::ALL.iter().map(Mode::as_str))
+            .chain(Dest::ALL.iter().map(Dest::as_str))
+            .flat_map(|value| {
+                [
+                    value.to_string(),
+                    value.to_uppercase(),
+                    format!("{value}, {value}"),
+                    format!("{value} "),
+                ]
+            })
+            .chain(["".to_string(), "unknown".to_string()]);
+
+        for value in values {
+            let header = HeaderValue::from_str(&value).unwrap();
+            let site = Site::ALL
+                .into_iter()
+                .find(|site| site.as_str() == value)
+                .unwrap_or(Site::CrossSite);
+
+            check!(Site::from_header(&header) == site, "{value:?}");
+            check!(
+                Mode::from_header(&header) == Mode::parse(&value),
+                "{value:?}"
+            );
+            check!(
+                Dest::from_header(&header) == Dest::parse(&value),
+                "{value:?}"
+            );
+        }
+
+        // values that are not valid UTF-8 were not parsed before comparing bytes either
+        let invalid = HeaderValue::from_bytes(b"cors\xff").unwrap();
+        check!(Site::from_header(&invalid) == Site::CrossSite);
+        check!(Mode::from_header(&invalid) == None);
+        check!(Dest::from_header(&invalid) == None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+
+/// Ordered description of the steps executed by [SecFetch](crate::SecFetch) for every request
+///
+/// It is returned by [SecFetchLayer::pipeline](crate::SecFetchLayer::pipeline) to audit complex setups,
+/// and can be built programmatically to assert on the expected configuration.
+///
+/// ```
+/// use tower_sec_fetch::{Pipeline, SecFetchLayer, Step};
+///
+/// let pipeline = SecFetchLayer::default().no_enforce().pipeline();
+///
+/// assert!(pipeline.steps().contains(&Step::ReportOnly));
+/// println!("{pipeline}");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step to the pipeline
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// The steps of the pipeline, in execution order
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{}. {step}", index + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single step of a [Pipeline]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "step", rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum Step {
+    /// Requests carrying [SkipSecFetch](crate::SkipSecFetch) in their extensions are allowed without evaluation
+    Skip,
+    /// Requests not using one of `methods`, without credentials when `only_with_credentials` is set,
+    /// or without the `session_cookie` when set, are allowed without evaluation
+    Protect {
+        methods: Option<Vec<String>>,
+        only_with_credentials: bool,
+        session_cookie: Option<String>,
+    },
+    /// Requests carrying a valid exemption token are allowed
+    Exemptions,
+    /// The authorizer allows or denies the request, or continues to the next step
+    Authorizer { name: String },
+    /// The policy is replaced by the one configured for the request host, if any
+    HostPolicies { hosts: usize },
+    /// The policy is replaced by the one of the active time window, if any
+    Schedule { windows: usize },
+    /// Cross-site `document` and `iframe` requests to paths starting with any of `paths` are denied
+    UntrustedPaths { paths: Vec<String> },
+    /// Cross-site navigations to any of `paths` are denied
+    SensitivePaths { paths: Vec<String> },
+    /// CORS preflight requests are allowed, to be answered by the CORS layer
+    Preflights,
+    /// Requests using one of `methods` are allowed
+    SafeMethods { methods: Vec<String> },
+    /// Requests from any of `origins` are allowed, unless denied by a custom rule
+    TrustedOrigins { origins: Vec<String> },
+    /// The evaluation policy allows or denies the request
+    Policy {
+        reject_missing_metadata: bool,
+        go_compat: bool,
+    },
+    /// The verdict is randomly flipped or delayed
+    Chaos,
+    /// Denied requests are reported
+    Report { name: String },
+    /// Denied requests are allowed after being reported
+    ReportOnly,
+    /// Denied requests to any of `paths` are allowed after being reported
+    ReportOnlyPaths { paths: Vec<String> },
+    /// Denied requests are allowed after being reported until `unix_seconds`, and denied afterwards
+    ReportOnlyUntil { unix_seconds: u64 },
+    /// Denied requests to an origin other than `origin` are let through, and denied unless they are redirected
+    Canonicalization { origin: String },
+    /// Denied requests are forwarded to the fallback
+    Fallback { name: String },
+    /// Denied requests receive the response built by the responder
+    Respond { name: String },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Protect {
+                methods,
+                only_with_credentials,
+                session_cookie,
+            } => {
+                write!(f, "skip unprotected requests")?;
+                if let Some(methods) = methods {
+                    write!(f, " (methods: {})", methods.join(", "))?;
+                }
+                if *only_with_credentials {
+                    write!(f, " (only with credentials)")?;
+                }
+                if let Some(name) = session_cookie {
+                    write!(f, " (only with session cookie {name})")?;
+                }
+                Ok(())
+            }
+            Self::Skip => write!(f, "skip requests opted out with SkipSecFetch"),
+            Self::Exemptions => write!(f, "allow requests with exemption tokens"),
+            Self::Authorizer { name } => write!(f, "authorize with {name}"),
+            Self::HostPolicies { hosts } => write!(f, "select policy from {hosts} hosts"),
+            Self::Schedule { windows } => write!(f, "select policy from {windows} time windows"),
+            Self::UntrustedPaths { paths } => {
+                write!(f, "deny embedding untrusted paths {}", paths.join(", "))
+            }
+            Self::SensitivePaths { paths } => {
+                write!(f, "deny navigating to sensitive paths {}", paths.join(", "))
+            }
+            Self::Preflights => write!(f, "allow CORS preflights"),
+            Self::SafeMethods { methods } => {
+                write!(f, "allow safe methods ({})", methods.join(", "))
+            }
+            Self::TrustedOrigins { origins } => {
+                write!(f, "allow trusted origins {}", origins.join(", "))
+            }
+            Self::Policy {
+                reject_missing_metadata,
+                go_compat,
+            } => write!(
+                f,
+                "evaluate policy (reject missing metadata: {reject_missing_metadata}, go compat: {go_compat})"
+            ),
+            Self::Chaos => write!(f, "flip or delay verdicts"),
+            Self::Report { name } => write!(f, "report denials to {name}"),
+            Self::ReportOnly => write!(f, "allow denied requests"),
+            Self::ReportOnlyPaths { paths } => {
+                write!(f, "allow denied requests to {}", paths.join(", "))
+            }
+            Self::ReportOnlyUntil { unix_seconds } => {
+                write!(f, "allow denied requests until {unix_seconds} (unix time)")
+            }
+            Self::Canonicalization { origin } => {
+                write!(f, "let denied requests be redirected to {origin}")
+            }
+            Self::Fallback { name } => write!(f, "forward denied requests to {name}"),
+            Self::Respond { name } => write!(f, "respond to denied requests with {name}"),
+        }
+    }
+}
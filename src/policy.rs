@@ -1,22 +1,275 @@
-use http::{HeaderValue, Method};
+use std::{borrow::Cow, fmt, sync::Arc};
 
-use crate::header;
+use http::{HeaderMap, HeaderName, HeaderValue, Method, Uri, uri::Authority};
 
-#[derive(Copy, Clone, Default)]
+use crate::{
+    Step, authorizer, header,
+    matrix::{Action, Dest, Matrix, Mode, Site},
+    rule::{FetchMetadata, RequestHead, Rule, RuleDecision},
+    user_agent::{MissingMetadata, UserAgent, UserAgentRules},
+};
+
+/// Evaluation policy of the [SecFetch](crate::SecFetch) middleware, configured with a [PolicyBuilder]
+///
+/// The presets [website](Policy::website), [api](Policy::api), and [strict](Policy::strict)
+/// are well-reasoned baselines, and can be applied with [with_policy](crate::SecFetchLayer::with_policy).
+#[derive(Clone)]
 pub struct Policy {
-    reject_missing_metadata: bool,
-    allow_safe_methods: bool,
+    pub(crate) reject_missing_metadata: bool,
+    pub(crate) reject_form_posts_without_metadata: bool,
+    pub(crate) missing_metadata_header: Option<(HeaderName, Option<HeaderValue>)>,
+    pub(crate) user_agent_rules: Option<UserAgentRules>,
+    pub(crate) reject_ambiguous_metadata: bool,
+    pub(crate) reject_conflicting_metadata: bool,
+    pub(crate) reject_unknown_metadata: bool,
+    pub(crate) case_insensitive_metadata: bool,
+    pub(crate) allow_safe_methods: bool,
+    pub(crate) allow_preflights: bool,
+    pub(crate) honor_method_override: bool,
+    pub(crate) safe_methods: Arc<[Method]>,
+    pub(crate) safe_methods_order: SafeMethodsOrder,
+    pub(crate) protected_methods: Option<Arc<[Method]>>,
+    pub(crate) only_with_credentials: bool,
+    pub(crate) session_cookie: Option<&'static str>,
+    pub(crate) untrusted_paths: Arc<[&'static str]>,
+    pub(crate) sensitive_paths: Arc<[&'static str]>,
+    pub(crate) user_activated_paths: Arc<[&'static str]>,
+    pub(crate) embeddable: Arc<[Embeddable]>,
+    pub(crate) matrix: Matrix,
+    pub(crate) rules: Arc<[Rule]>,
+    pub(crate) allowed_destinations: Arc<[AllowedDestinations]>,
+    pub(crate) denied_destinations: Arc<[&'static str]>,
+    pub(crate) go_compat: bool,
+    pub(crate) trusted_origins: Arc<[&'static str]>,
+    pub(crate) canonical_origin: Option<Uri>,
+    pub(crate) external_origins: Arc<[&'static str]>,
+    pub(crate) websocket_origins: Arc<[&'static str]>,
+    pub(crate) event_streams: Arc<[&'static str]>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Embeddable {
+    prefix: &'static str,
+    origins: Arc<[&'static str]>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AllowedDestinations {
+    prefix: &'static str,
+    destinations: Arc<[&'static str]>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        PolicyBuilder::new().build()
+    }
 }
 
 impl Policy {
+    /// Builds a policy that can be shared by many layers with [with_policy](crate::SecFetchLayer::with_policy)
+    ///
+    /// ```
+    /// use tower_sec_fetch::{Policy, SecFetchLayer};
+    ///
+    /// let policy = Policy::new(|policy| {
+    ///     policy.reject_missing_metadata();
+    /// });
+    ///
+    /// let api = SecFetchLayer::default().with_policy(policy.clone());
+    /// let admin = SecFetchLayer::default().with_policy(policy);
+    /// ```
+    pub fn new<F>(make_policy: F) -> Self
+    where
+        F: FnOnce(&mut PolicyBuilder),
+    {
+        let mut builder = PolicyBuilder::new();
+        make_policy(&mut builder);
+        builder.build()
+    }
+
+    /// Customizes a copy of this policy, such as one of the presets, with a [PolicyBuilder]
+    ///
+    /// ```
+    /// use tower_sec_fetch::{Policy, SecFetchLayer};
+    ///
+    /// let policy = Policy::strict().extend(|policy| {
+    ///     policy.untrusted_paths(["/uploads/"]);
+    /// });
+    ///
+    /// SecFetchLayer::default().with_policy(policy);
+    /// ```
+    pub fn extend<F>(self, extend_policy: F) -> Self
+    where
+        F: FnOnce(&mut PolicyBuilder),
+    {
+        let mut builder = PolicyBuilder::from_policy(self);
+        extend_policy(&mut builder);
+        builder.build()
+    }
+
+    /// The default policy, suited for websites: same-site and user-initiated requests are allowed,
+    /// as well as cross-site `GET` navigations that are not embeds
+    pub fn website() -> Self {
+        Self::default()
+    }
+
+    /// A policy for APIs that are never navigated to: requests without Fetch Metadata headers are rejected,
+    /// and cross-site navigations are denied
+    pub fn api() -> Self {
+        let mut builder = PolicyBuilder::new();
+        builder
+            .reject_missing_metadata()
+            .navigation_destinations([]);
+        builder.build()
+    }
+
+    /// Only same-origin and user-initiated requests are allowed: requests without Fetch Metadata headers are rejected,
+    /// and both same-site and cross-site requests are denied, including navigations
+    pub fn strict() -> Self {
+        Self::new(|policy| {
+            policy.reject_missing_metadata().matrix(
+                Matrix::default()
+                    .set_site(Site::SameSite, Action::Deny)
+                    .set(Dest::Empty, Site::CrossSite, Action::Deny)
+                    .set(Dest::Document, Site::CrossSite, Action::Deny),
+            );
+        })
+    }
+
+    /// A policy for publicly embeddable resources, such as those served by a CDN: cross-site `GET` and `HEAD` requests
+    /// are allowed for subresource and `fetch` destinations, while state-changing requests keep the default protection
+    ///
+    /// Unlike [allow_safe_methods](PolicyBuilder::allow_safe_methods), cross-site requests to embed the resources
+    /// in frames or objects are still denied. Reads are recognized by the method of the request only,
+    /// so a `POST` tunneling a `GET` with a [method override](PolicyBuilder::honor_method_override) is not a read.
+    pub fn public() -> Self {
+        const PUBLIC_DESTINATIONS: [Dest; 9] = [
+            Dest::Empty,
+            Dest::Image,
+            Dest::Font,
+            Dest::Style,
+            Dest::Script,
+            Dest::Audio,
+            Dest::Video,
+            Dest::Track,
+            Dest::Manifest,
+        ];
+
+        Self::new(|policy| {
+            policy.custom(|metadata, request| {
+                let read = request.method() == Method::GET || request.method() == Method::HEAD;
+                let public = metadata
+                    .dest
+                    .is_some_and(|dest| PUBLIC_DESTINATIONS.contains(&dest));
+
+                if read && public {
+                    RuleDecision::Allow
+                } else {
+                    RuleDecision::Continue
+                }
+            });
+        })
+    }
+
+    /// A policy for a single GraphQL endpoint: requests without Fetch Metadata headers are rejected,
+    /// cross-site navigations are denied, `POST` requests must use the `application/json` content type,
+    /// and `GET` requests are denied
+    pub fn graphql() -> Self {
+        Self::graphql_policy(false)
+    }
+
+    /// Like [graphql](Policy::graphql), but same-origin `GET` requests are allowed for persisted queries
+    pub fn graphql_with_persisted_queries() -> Self {
+        Self::graphql_policy(true)
+    }
+
+    fn graphql_policy(persisted_queries: bool) -> Self {
+        Self::new(|policy| {
+            policy
+                .reject_missing_metadata()
+                .navigation_destinations([])
+                .custom(move |metadata, request| match *request.method() {
+                    Method::POST if !is_json(request) => RuleDecision::Deny,
+                    Method::GET if !persisted_queries || metadata.site != Site::SameOrigin => {
+                        RuleDecision::Deny
+                    }
+                    _ => RuleDecision::Continue,
+                });
+        })
+    }
+
+    /// Evaluates the request, returning [Verdict::Allowed], or [Verdict::Denied] with the [DenialReason]
+    ///
+    /// It can be used outside of a [SecFetch](crate::SecFetch) middleware, e.g. in custom handlers or log processors.
+    /// The authorizer, reporter, and exemptions of the layer are not involved, and requests that are not
+    /// [protected](Policy::is_protected) are evaluated like any other.
+    /// Denied safe methods are not [reported](Verdict::Reported): use [verdict](Policy::verdict) for the verdict
+    /// actually applied by the middleware.
+    ///
+    /// ```
+    /// use tower_sec_fetch::{DenialReason, Policy, Verdict};
+    ///
+    /// let request = http::Request::post("/transfer")
+    ///     .header("sec-fetch-site", "cross-site")
+    ///     .header("sec-fetch-mode", "cors")
+    ///     .header("sec-fetch-dest", "empty")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let verdict = Policy::default().evaluate(&request);
+    /// assert_eq!(verdict, Verdict::Denied(DenialReason::CrossSite));
+    /// assert_eq!(verdict.reason(), Some(DenialReason::CrossSite));
+    /// ```
+    pub fn evaluate<B>(&self, request: &http::Request<B>) -> Verdict {
+        match self.check(request) {
+            Ok(()) => Verdict::Allowed,
+            Err(reason) => Verdict::Denied(reason),
+        }
+    }
+
+    /// Evaluates the request like [evaluate](Policy::evaluate), returning the [DenialReason] if it is denied
     // Resource Isolation Policy
     // Implemented following https://web.dev/articles/fetch-metadata
-    pub fn allow<B>(&self, request: &http::Request<B>) -> bool {
-        if self.allow_safe_methods
-            && method_in(
-                request.method(),
-                [Method::GET, Method::HEAD, Method::OPTIONS],
-            )
+    pub(crate) fn check<B>(&self, request: &http::Request<B>) -> Result<(), DenialReason> {
+        if self.go_compat {
+            return self.evaluate_go_compat(request);
+        }
+
+        if self.is_untrusted_content(request) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is embedding or navigating to untrusted content: denied",
+            );
+
+            return Err(DenialReason::UntrustedPath);
+        }
+
+        // checked before any shortcut, so that safe methods cannot be used to navigate to sensitive paths
+        if self.is_sensitive_navigation(request) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is a cross-site navigation to a sensitive path: denied",
+            );
+
+            return Err(DenialReason::CrossSite);
+        }
+
+        if self.allow_preflights && is_preflight(request) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is a CORS preflight: allowed",
+            );
+
+            return Ok(());
+        }
+
+        if self.safe_methods_order == SafeMethodsOrder::BeforePolicy && self.is_safe_method(request)
         {
             #[cfg(feature = "tracing")]
             tracing::trace!(
@@ -25,45 +278,231 @@ impl Policy {
                 "request uses a safe method: allowed",
             );
 
-            return true;
+            return Ok(());
         }
 
-        let sec_fetch_site = request.headers().get(header::SEC_FETCH_SITE);
-        let sec_fetch_mode = request.headers().get(header::SEC_FETCH_MODE);
-        let sec_fetch_dest = request.headers().get(header::SEC_FETCH_DEST);
+        let sec_fetch_site = self.metadata_header(request.headers(), header::SEC_FETCH_SITE);
+        let sec_fetch_mode = self.metadata_header(request.headers(), header::SEC_FETCH_MODE);
+        let sec_fetch_dest = self.metadata_header(request.headers(), header::SEC_FETCH_DEST);
 
-        let sec_fetch = zip3(sec_fetch_site, sec_fetch_mode, sec_fetch_dest);
+        if self.reject_conflicting_metadata
+            && [&sec_fetch_site, &sec_fetch_mode, &sec_fetch_dest]
+                .into_iter()
+                .flatten()
+                .any(|header| header.conflicting)
+        {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request has conflicting fetch metadata headers: denied",
+            );
 
-        let Some((sec_fetch_site, sec_fetch_mode, sec_fetch_dest)) = sec_fetch else {
+            return Err(DenialReason::ConflictingMetadata);
+        }
+
+        if self.reject_ambiguous_metadata
+            && [&sec_fetch_site, &sec_fetch_mode, &sec_fetch_dest]
+                .into_iter()
+                .flatten()
+                .any(|header| header.ambiguous)
+        {
             #[cfg(feature = "tracing")]
             tracing::trace!(
                 method = %request.method(),
                 path = request.uri().path(),
-                "request is missing fetch metadata: {}",
-                if self.reject_missing_metadata { "denied" } else { "allowed" },
+                "request has ambiguous fetch metadata: denied",
             );
 
+            return Err(DenialReason::AmbiguousMetadata);
+        }
+
+        if self.reject_unknown_metadata
+            && [&sec_fetch_site, &sec_fetch_mode, &sec_fetch_dest]
+                .into_iter()
+                .flatten()
+                .any(|header| !header.ambiguous && !header.is_known())
+        {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request has unknown fetch metadata values: denied",
+            );
+
+            return Err(DenialReason::UnknownMetadata);
+        }
+
+        let sec_fetch = zip3(
+            sec_fetch_site.as_ref().map(MetadataHeader::value),
+            sec_fetch_mode.as_ref().map(MetadataHeader::value),
+            sec_fetch_dest.as_ref().map(MetadataHeader::value),
+        );
+
+        let Some((sec_fetch_site, sec_fetch_mode, sec_fetch_dest)) = sec_fetch else {
             // Fetch metadata headers are missing.
             // Either the request doesn't come from a browser, or the browser is too old.
-            return !self.reject_missing_metadata;
+            // Old browsers can only send form posts cross-site without a CORS preflight.
+            // Cross-site form posts cannot set custom headers, and other requests need a CORS preflight to do so.
+            let user_agent_rules = self
+                .user_agent_rules
+                .filter(|_| !self.has_missing_metadata_header(request));
+            if let Some(rules) = user_agent_rules {
+                let user_agent =
+                    UserAgent::classify(request.headers().get(http::header::USER_AGENT));
+                let outcome = match rules.outcome(user_agent) {
+                    MissingMetadata::Allow => Ok(()),
+                    MissingMetadata::Deny => Err(DenialReason::MissingMetadata),
+                    MissingMetadata::OriginFallback => self.evaluate_origin(request),
+                };
+
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    method = %request.method(),
+                    path = request.uri().path(),
+                    ?user_agent,
+                    "request is missing fetch metadata: {}",
+                    if outcome.is_err() { "denied" } else { "allowed" },
+                );
+
+                return outcome;
+            }
+
+            let denied = (self.reject_missing_metadata
+                || (self.reject_form_posts_without_metadata
+                    && is_form_post(request, request.method())))
+                && !self.has_missing_metadata_header(request);
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is missing fetch metadata: {}",
+                if denied { "denied" } else { "allowed" },
+            );
+
+            if denied {
+                return Err(DenialReason::MissingMetadata);
+            }
+
+            return Ok(());
         };
 
-        if header_in(sec_fetch_site, ["same-origin", "same-site", "none"]) {
+        if !self.rules.is_empty() {
+            let metadata = FetchMetadata::new(
+                sec_fetch_site,
+                sec_fetch_mode,
+                sec_fetch_dest,
+                request.headers(),
+            );
+            let tunneled = self.tunneled_method(request);
+            let head = RequestHead::new(request).with_tunneled_method(tunneled.as_ref());
+
+            for rule in self.rules.iter() {
+                match rule(&metadata, &head) {
+                    RuleDecision::Allow => {
+                        #[cfg(feature = "tracing")]
+                        tracing::trace!(
+                            method = %request.method(),
+                            path = request.uri().path(),
+                            "request is allowed by a custom rule: allowed",
+                        );
+
+                        return Ok(());
+                    }
+                    RuleDecision::Deny => {
+                        #[cfg(feature = "tracing")]
+                        tracing::trace!(
+                            method = %request.method(),
+                            path = request.uri().path(),
+                            "request is denied by a custom rule: denied",
+                        );
+
+                        return Err(DenialReason::Custom);
+                    }
+                    RuleDecision::Continue => {}
+                }
+            }
+        }
+
+        if self.is_trusted_origin(request) {
             #[cfg(feature = "tracing")]
             tracing::trace!(
                 method = %request.method(),
                 path = request.uri().path(),
-                "request is same-site or user initiated: allowed",
+                "request is from a trusted origin: allowed",
             );
 
-            // request is same-site or user initiated
-            return true;
+            return Ok(());
+        }
+
+        let action = self.matrix.action(
+            Dest::from_header(sec_fetch_dest),
+            Mode::from_header(sec_fetch_mode),
+            Site::from_header(sec_fetch_site),
+        );
+
+        if action == Action::Allow {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is allowed by the decision matrix: allowed",
+            );
+
+            // by default, request is same-site or user initiated
+            return Ok(());
         }
 
-        if sec_fetch_mode == "navigate"
-            && request.method() == Method::GET
-            && header_in(sec_fetch_dest, ["empty", "document"])
+        if header_in(sec_fetch_dest, self.denied_destinations.iter().copied()) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is loading a denied destination: denied",
+            );
+
+            return Err(DenialReason::CrossSite);
+        }
+
+        if self.is_allowed_destination(request, sec_fetch_dest) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is loading an allowed destination: allowed",
+            );
+
+            return Ok(());
+        }
+
+        if self.is_event_stream(request, sec_fetch_mode, sec_fetch_dest) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is subscribing to an allowed event stream: allowed",
+            );
+
+            return Ok(());
+        }
+
+        if action == Action::AllowNavigation
+            && sec_fetch_mode == header::NAVIGATE
+            && self.all_methods(request, |method| method == Method::GET)
+            && !self.is_sensitive_path(request)
         {
+            if !self.is_user_activated(request) {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    method = %request.method(),
+                    path = request.uri().path(),
+                    "request is a navigation without user activation: denied",
+                );
+
+                return Err(DenialReason::MissingUserActivation);
+            }
+
             #[cfg(feature = "tracing")]
             tracing::trace!(
                 method = %request.method(),
@@ -72,7 +511,45 @@ impl Policy {
             );
 
             // request is a regular navigation event and is not being embedded
-            return true;
+            return Ok(());
+        }
+
+        if sec_fetch_mode == header::NAVIGATE
+            && self.all_methods(request, |method| method == Method::GET)
+            && sec_fetch_dest == header::IFRAME
+            && self.is_embeddable(request)
+        {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is an embed from an allowed origin: allowed",
+            );
+
+            // request is embedding a widget from a partner site
+            return Ok(());
+        }
+
+        if sec_fetch_mode == header::WEBSOCKET {
+            if self.is_allowed_websocket_origin(request) {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    method = %request.method(),
+                    path = request.uri().path(),
+                    "request is a websocket handshake from an allowed origin: allowed",
+                );
+
+                return Ok(());
+            }
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                method = %request.method(),
+                path = request.uri().path(),
+                "request is a websocket handshake from another origin: denied",
+            );
+
+            return Err(DenialReason::WebsocketOrigin);
         }
 
         #[cfg(feature = "tracing")]
@@ -83,21 +560,633 @@ impl Policy {
         );
 
         // request is denied
-        false
+        Err(DenialReason::CrossSite)
+    }
+
+    /// The steps of [evaluate](Policy::evaluate), in evaluation order, see [Pipeline](crate::Pipeline)
+    pub(crate) fn steps(&self) -> Vec<Step> {
+        let paths = |paths: &[&str]| paths.iter().map(|path| path.to_string()).collect();
+        let mut steps = Vec::new();
+
+        if !self.go_compat {
+            if !self.untrusted_paths.is_empty() {
+                steps.push(Step::UntrustedPaths {
+                    paths: paths(&self.untrusted_paths),
+                });
+            }
+            if !self.sensitive_paths.is_empty() {
+                steps.push(Step::SensitivePaths {
+                    paths: paths(&self.sensitive_paths),
+                });
+            }
+            if self.allow_preflights {
+                steps.push(Step::Preflights);
+            }
+            if self.allow_safe_methods && self.safe_methods_order == SafeMethodsOrder::BeforePolicy
+            {
+                steps.push(self.safe_methods_step());
+            }
+            if !self.trusted_origins.is_empty() {
+                steps.push(Step::TrustedOrigins {
+                    origins: paths(&self.trusted_origins),
+                });
+            }
+        }
+
+        steps.push(Step::Policy {
+            reject_missing_metadata: self.reject_missing_metadata,
+            go_compat: self.go_compat,
+        });
+        steps
+    }
+
+    /// The steps applied by the middleware to requests denied by [evaluate](Policy::evaluate), before the layer ones
+    pub(crate) fn denial_steps(&self) -> Vec<Step> {
+        let mut steps = Vec::new();
+
+        if self.allow_safe_methods && self.safe_methods_order == SafeMethodsOrder::AfterReport {
+            steps.push(self.safe_methods_step());
+        }
+        steps
+    }
+
+    /// The steps applied by the middleware to denied requests that are enforced, before they are answered
+    pub(crate) fn enforced_denial_steps(&self) -> Vec<Step> {
+        self.canonical_origin
+            .iter()
+            .map(|origin| Step::Canonicalization {
+                origin: origin.to_string(),
+            })
+            .collect()
+    }
+
+    fn safe_methods_step(&self) -> Step {
+        Step::SafeMethods {
+            methods: self.safe_methods.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// The verdict applied by the [SecFetch](crate::SecFetch) middleware to the request
+    ///
+    /// Unlike [evaluate](Policy::evaluate), requests that are not [protected](Policy::is_protected) are allowed,
+    /// and denied safe methods are [reported](Verdict::Reported) with [SafeMethodsOrder::AfterReport].
+    /// The authorizer, reporter, and exemptions of the layer are not involved.
+    ///
+    /// ```
+    /// use tower_sec_fetch::{DenialReason, Policy, SafeMethodsOrder, Verdict};
+    ///
+    /// let policy = Policy::new(|policy| {
+    ///     policy
+    ///         .allow_safe_methods()
+    ///         .safe_methods_order(SafeMethodsOrder::AfterReport);
+    /// });
+    ///
+    /// let request = http::Request::get("/profile")
+    ///     .header("sec-fetch-site", "cross-site")
+    ///     .header("sec-fetch-mode", "cors")
+    ///     .header("sec-fetch-dest", "empty")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(policy.evaluate(&request), Verdict::Denied(DenialReason::CrossSite));
+    /// assert_eq!(policy.verdict(&request), Verdict::Reported(DenialReason::CrossSite));
+    /// ```
+    pub fn verdict<B>(&self, request: &http::Request<B>) -> Verdict {
+        if !self.is_protected(request) {
+            return Verdict::Allowed;
+        }
+
+        match self.check(request) {
+            Ok(()) => Verdict::Allowed,
+            Err(reason) if self.is_reported(request) => Verdict::Reported(reason),
+            Err(reason) => Verdict::Denied(reason),
+        }
+    }
+
+    /// Whether the denied request is allowed after being reported, see [SafeMethodsOrder::AfterReport]
+    pub(crate) fn is_reported<B>(&self, request: &http::Request<B>) -> bool {
+        self.safe_methods_order == SafeMethodsOrder::AfterReport && self.is_safe_method(request)
+    }
+
+    /// The [trusted_origins](PolicyBuilder::trusted_origins) of the policy, as header values
+    ///
+    /// Feeding them to a CORS layer keeps the CORS and CSRF policies from drifting apart,
+    /// see [allow_origin](Policy::allow_origin) with the `tower-http` feature.
+    /// Origins that are not valid header values are skipped.
+    ///
+    /// ```
+    /// use tower_sec_fetch::Policy;
+    ///
+    /// let policy = Policy::new(|policy| {
+    ///     policy.trusted_origins(["https://partner.example"]);
+    /// });
+    ///
+    /// let allowed: Vec<http::HeaderValue> = policy.trusted_origins();
+    /// assert_eq!(allowed, ["https://partner.example"]);
+    /// ```
+    pub fn trusted_origins(&self) -> Vec<HeaderValue> {
+        self.trusted_origins
+            .iter()
+            .filter_map(|origin| HeaderValue::from_str(origin).ok())
+            .collect()
+    }
+
+    /// Whether the request should be evaluated at all, see [protect_methods](PolicyBuilder::protect_methods)
+    /// [only_with_credentials](PolicyBuilder::only_with_credentials) and
+    /// [only_with_session_cookie](PolicyBuilder::only_with_session_cookie)
+    pub fn is_protected<B>(&self, request: &http::Request<B>) -> bool {
+        let headers = request.headers();
+        let has_credentials = || {
+            headers.contains_key(http::header::COOKIE)
+                || headers.contains_key(http::header::AUTHORIZATION)
+        };
+
+        self.protected_methods
+            .as_ref()
+            .is_none_or(|methods| self.any_method(request, |method| methods.contains(method)))
+            && (!self.only_with_credentials || has_credentials())
+            && self
+                .session_cookie
+                .is_none_or(|name| has_cookie(request, name))
+    }
+
+    pub(crate) fn is_safe_method<B>(&self, request: &http::Request<B>) -> bool {
+        self.allow_safe_methods
+            && self.all_methods(request, |method| self.safe_methods.contains(method))
+            && !self.is_sensitive_navigation(request)
+    }
+
+    /// The method tunneled by a `POST` request, when [honor_method_override](PolicyBuilder::honor_method_override) is set
+    fn tunneled_method<B>(&self, request: &http::Request<B>) -> Option<Method> {
+        if !self.honor_method_override || request.method() != Method::POST {
+            return None;
+        }
+
+        let header = METHOD_OVERRIDE_HEADERS
+            .iter()
+            .find_map(|name| request.headers().get(*name))
+            .and_then(|value| value.to_str().ok());
+        let query = || {
+            request.uri().query()?.split('&').find_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                (name == "_method").then_some(value)
+            })
+        };
+
+        header
+            .or_else(query)
+            .and_then(|method| method.to_ascii_uppercase().parse().ok())
+    }
+
+    /// Whether both the method of the request and the tunneled one match, so that tunneling never relaxes a check
+    fn all_methods<B>(
+        &self,
+        request: &http::Request<B>,
+        matches: impl Fn(&Method) -> bool,
+    ) -> bool {
+        matches(request.method())
+            && self
+                .tunneled_method(request)
+                .is_none_or(|method| matches(&method))
+    }
+
+    /// Whether either the method of the request or the tunneled one match, so that tunneling never relaxes a check
+    fn any_method<B>(&self, request: &http::Request<B>, matches: impl Fn(&Method) -> bool) -> bool {
+        matches(request.method())
+            || self
+                .tunneled_method(request)
+                .is_some_and(|method| matches(&method))
+    }
+
+    // Mirrors Go's net/http CrossOriginProtection
+    // See https://pkg.go.dev/net/http#CrossOriginProtection.Check
+    fn evaluate_go_compat<B>(&self, request: &http::Request<B>) -> Result<(), DenialReason> {
+        if self.all_methods(request, |method| {
+            [Method::GET, Method::HEAD, Method::OPTIONS].contains(method)
+        }) {
+            return Ok(());
+        }
+
+        let headers = request.headers();
+        let is_trusted = || self.is_trusted_origin(request);
+
+        let site = self.metadata_header(headers, header::SEC_FETCH_SITE);
+        match site.as_ref().map(MetadataHeader::value) {
+            // No Sec-Fetch-Site header is present, fall back to the Origin header
+            None => {}
+            Some(site) if header_in(site, [header::SAME_ORIGIN, header::NONE]) => return Ok(()),
+            Some(_) if is_trusted() => return Ok(()),
+            Some(_) => return Err(DenialReason::CrossSite),
+        }
+
+        if is_trusted() {
+            return Ok(());
+        }
+
+        self.evaluate_origin(request)
+    }
+
+    /// Compares the `origin` header with the request host, or with the [external_origins](PolicyBuilder::external_origins) when set
+    fn evaluate_origin<B>(&self, request: &http::Request<B>) -> Result<(), DenialReason> {
+        let Some(origin) = request.headers().get(http::header::ORIGIN) else {
+            // Neither header is present, the request is either same-origin or not from a browser
+            return Ok(());
+        };
+
+        if !self.external_origins.is_empty() {
+            // the request URI and host may have been rewritten, compare with the origins seen by browsers instead
+            if self.external_origins.iter().any(|own| origin == own) {
+                return Ok(());
+            }
+
+            return Err(DenialReason::OriginMismatch);
+        }
+
+        let origin_host = origin
+            .to_str()
+            .ok()
+            .and_then(|origin| origin.parse::<Uri>().ok())
+            .and_then(|origin| origin.authority().map(|authority| authority.to_string()));
+        if origin_host.is_some() && origin_host.as_deref() == request_host(request) {
+            return Ok(());
+        }
+
+        if !self.go_compat && is_insecure_upgrade(request, origin) {
+            // the request is an HTTP to HTTPS upgrade of a same-host page, which Go does not recognize
+            return Ok(());
+        }
+
+        Err(DenialReason::OriginMismatch)
+    }
+
+    fn is_trusted_origin<B>(&self, request: &http::Request<B>) -> bool {
+        request
+            .headers()
+            .get(http::header::ORIGIN)
+            .is_some_and(|origin| self.trusted_origins.iter().any(|trusted| origin == trusted))
+    }
+
+    fn is_allowed_destination<B>(&self, request: &http::Request<B>, dest: &HeaderValue) -> bool {
+        let path = request.uri().path();
+
+        self.allowed_destinations.iter().any(|allowed| {
+            path.starts_with(allowed.prefix)
+                && header_in(dest, allowed.destinations.iter().copied())
+        })
+    }
+
+    fn is_event_stream<B>(
+        &self,
+        request: &http::Request<B>,
+        mode: &HeaderValue,
+        dest: &HeaderValue,
+    ) -> bool {
+        let path = request.uri().path();
+
+        self.all_methods(request, |method| method == Method::GET)
+            && mode == header::CORS
+            && dest == header::EMPTY
+            && request
+                .headers()
+                .get(http::header::ACCEPT)
+                .is_some_and(|accept| accept == "text/event-stream")
+            && self
+                .event_streams
+                .iter()
+                .any(|prefix| path.starts_with(prefix))
+    }
+
+    fn has_missing_metadata_header<B>(&self, request: &http::Request<B>) -> bool {
+        self.missing_metadata_header
+            .as_ref()
+            .is_some_and(|(name, expected)| {
+                request
+                    .headers()
+                    .get_all(name)
+                    .iter()
+                    .any(|value| expected.as_ref().is_none_or(|expected| value == expected))
+            })
+    }
+
+    fn is_allowed_websocket_origin<B>(&self, request: &http::Request<B>) -> bool {
+        request
+            .headers()
+            .get(http::header::ORIGIN)
+            .is_some_and(|origin| {
+                self.websocket_origins
+                    .iter()
+                    .any(|allowed| origin == allowed)
+            })
+    }
+
+    fn is_embeddable<B>(&self, request: &http::Request<B>) -> bool {
+        let path = request.uri().path();
+        let Some(origin) = request_origin(request) else {
+            return false;
+        };
+
+        self.embeddable.iter().any(|embeddable| {
+            path.starts_with(embeddable.prefix) && embeddable.origins.contains(&origin.as_str())
+        })
+    }
+
+    pub(crate) fn is_canonicalization<B>(&self, request: &http::Request<B>) -> bool {
+        let Some(canonical) = &self.canonical_origin else {
+            return false;
+        };
+
+        let differs = |actual: Option<&str>, expected: Option<&str>| {
+            actual.is_some_and(|actual| {
+                expected.is_none_or(|expected| !actual.eq_ignore_ascii_case(expected))
+            })
+        };
+
+        differs(request_scheme(request), canonical.scheme_str())
+            || differs(
+                request_host(request),
+                canonical.authority().map(|authority| authority.as_str()),
+            )
+    }
+
+    fn is_user_activated<B>(&self, request: &http::Request<B>) -> bool {
+        let path = request.uri().path();
+        if !self
+            .user_activated_paths
+            .iter()
+            .any(|pattern| path_matches(pattern, path))
+        {
+            return true;
+        }
+
+        request
+            .headers()
+            .get(header::SEC_FETCH_USER)
+            .is_some_and(|user| user == header::USER_ACTIVATED)
+    }
+
+    fn metadata_header<'a>(
+        &self,
+        headers: &'a HeaderMap,
+        name: HeaderName,
+    ) -> Option<MetadataHeader<'a>> {
+        MetadataHeader::get(headers, name, self.case_insensitive_metadata)
+    }
+
+    fn is_sensitive_path<B>(&self, request: &http::Request<B>) -> bool {
+        let path = normalize_path(request.uri().path());
+
+        self.sensitive_paths
+            .iter()
+            .any(|pattern| path_matches(pattern, &path))
+    }
+
+    fn is_sensitive_navigation<B>(&self, request: &http::Request<B>) -> bool {
+        if !self.is_sensitive_path(request) {
+            return false;
+        }
+
+        let headers = request.headers();
+        let sec_fetch_site = self.metadata_header(headers, header::SEC_FETCH_SITE);
+        let sec_fetch_mode = self.metadata_header(headers, header::SEC_FETCH_MODE);
+
+        matches!(
+            (
+                sec_fetch_site.as_ref().map(MetadataHeader::value),
+                sec_fetch_mode.as_ref().map(MetadataHeader::value),
+            ),
+            (Some(site), Some(mode)) if site == header::CROSS_SITE && mode == header::NAVIGATE
+        )
+    }
+
+    fn is_untrusted_content<B>(&self, request: &http::Request<B>) -> bool {
+        let path = normalize_path(request.uri().path());
+        if !self.untrusted_paths.iter().any(|prefix| {
+            // normalized paths have no trailing slash, so that `/uploads/` also matches `/uploads`
+            path.starts_with(prefix) || prefix.strip_suffix('/') == Some(&*path)
+        }) {
+            return false;
+        }
+
+        let headers = request.headers();
+        let sec_fetch_site = self.metadata_header(headers, header::SEC_FETCH_SITE);
+        let sec_fetch_dest = self.metadata_header(headers, header::SEC_FETCH_DEST);
+
+        matches!(
+            (
+                sec_fetch_site.as_ref().map(MetadataHeader::value),
+                sec_fetch_dest.as_ref().map(MetadataHeader::value),
+            ),
+            (Some(site), Some(dest)) if site == header::CROSS_SITE && header_in(dest, [header::DOCUMENT, header::IFRAME])
+        )
+    }
+}
+
+/// The verdict applied by the [SecFetch](crate::SecFetch) middleware to a request, see [Policy::verdict]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The request is allowed
+    Allowed,
+    /// The request is denied by the policy, but allowed after being reported, see [SafeMethodsOrder::AfterReport]
+    Reported(DenialReason),
+    /// The request is denied
+    Denied(DenialReason),
+}
+
+impl Verdict {
+    /// Whether the request reaches the inner service
+    pub fn is_allowed(&self) -> bool {
+        !matches!(self, Self::Denied(_))
+    }
+
+    /// Why the policy denied the request, even if it was then allowed after being reported
+    pub fn reason(&self) -> Option<DenialReason> {
+        match self {
+            Self::Allowed => None,
+            Self::Reported(reason) | Self::Denied(reason) => Some(*reason),
+        }
+    }
+
+    /// Short machine-readable identifier of the verdict
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Reported(_) => "reported",
+            Self::Denied(_) => "denied",
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason() {
+            Some(reason) => write!(f, "{} ({})", self.as_str(), reason.as_str()),
+            None => f.write_str(self.as_str()),
+        }
+    }
+}
+
+/// When the safe-method shortcut of [allow_safe_methods](PolicyBuilder::allow_safe_methods) is applied
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SafeMethodsOrder {
+    /// Before inspecting the Fetch Metadata headers, so safe requests are never reported
+    #[default]
+    BeforePolicy,
+    /// After the evaluation policy denied the request and the [SecFetchReporter](crate::SecFetchReporter)
+    /// was notified, so safe requests are reported but still allowed
+    AfterReport,
+}
+
+/// The reason why a request was denied
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DenialReason {
+    /// The request does not provide the Fetch Metadata headers
+    MissingMetadata,
+    /// The request is cross-site and is neither a top-level navigation nor an allowed method
+    CrossSite,
+    /// The request is a cross-site document or iframe load of an untrusted path,
+    /// see [untrusted_paths](PolicyBuilder::untrusted_paths)
+    UntrustedPath,
+    /// The request does not provide the Fetch Metadata headers, and its `origin` header does not match its host,
+    /// see [go_compat](PolicyBuilder::go_compat) and [MissingMetadata::OriginFallback]
+    OriginMismatch,
+    /// The request is a cross-site navigation without user activation to a path that requires it,
+    /// see [require_user_activation](PolicyBuilder::require_user_activation)
+    MissingUserActivation,
+    /// The request is a cross-site WebSocket handshake from an origin that is not allowed,
+    /// see [allow_websocket_origins](PolicyBuilder::allow_websocket_origins)
+    WebsocketOrigin,
+    /// The request has conflicting values for a Fetch Metadata header,
+    /// see [reject_ambiguous_metadata](PolicyBuilder::reject_ambiguous_metadata)
+    AmbiguousMetadata,
+    /// The request sends the same Fetch Metadata header multiple times with different values,
+    /// see [reject_conflicting_metadata](PolicyBuilder::reject_conflicting_metadata)
+    ConflictingMetadata,
+    /// The request has an unrecognized Fetch Metadata value,
+    /// see [reject_unknown_metadata](PolicyBuilder::reject_unknown_metadata)
+    UnknownMetadata,
+    /// The request was denied by a [custom](PolicyBuilder::custom) rule
+    Custom,
+    /// The request was denied by the [SecFetchAuthorizer](crate::SecFetchAuthorizer)
+    Authorizer,
+    /// The verdict was flipped by [Chaos](crate::Chaos)
+    #[cfg(feature = "chaos")]
+    Chaos,
+}
+
+impl DenialReason {
+    /// Short machine-readable identifier of the reason
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingMetadata => "missing-metadata",
+            Self::CrossSite => "cross-site",
+            Self::UntrustedPath => "untrusted-path",
+            Self::OriginMismatch => "origin-mismatch",
+            Self::MissingUserActivation => "missing-user-activation",
+            Self::WebsocketOrigin => "websocket-origin",
+            Self::AmbiguousMetadata => "ambiguous-metadata",
+            Self::ConflictingMetadata => "conflicting-metadata",
+            Self::UnknownMetadata => "unknown-metadata",
+            Self::Custom => "custom",
+            Self::Authorizer => "authorizer",
+            #[cfg(feature = "chaos")]
+            Self::Chaos => "chaos",
+        }
+    }
+}
+
+impl fmt::Display for DenialReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Self::MissingMetadata => "the request is missing the Fetch Metadata headers",
+            Self::CrossSite => "cross-site requests are not allowed for this resource",
+            Self::UntrustedPath => "user content cannot be loaded as a cross-site document",
+            Self::OriginMismatch => "the request origin does not match its host",
+            Self::MissingUserActivation => "cross-site navigations must be triggered by the user",
+            Self::WebsocketOrigin => "websocket connections are not allowed from this origin",
+            Self::AmbiguousMetadata => "the request has conflicting Fetch Metadata headers",
+            Self::ConflictingMetadata => {
+                "the request has duplicate Fetch Metadata headers with different values"
+            }
+            Self::UnknownMetadata => "the request has unrecognized Fetch Metadata headers",
+            Self::Custom => "the request was denied by a custom rule",
+            Self::Authorizer => "the request was denied by the authorization logic",
+            #[cfg(feature = "chaos")]
+            Self::Chaos => "the verdict was flipped by chaos testing",
+        };
+
+        f.write_str(description)
     }
 }
 
 /// Allows customizing the behaviour of the default evaluation policy
 pub struct PolicyBuilder {
     reject_missing_metadata: bool,
+    reject_form_posts_without_metadata: bool,
+    missing_metadata_header: Option<(HeaderName, Option<HeaderValue>)>,
+    user_agent_rules: Option<UserAgentRules>,
+    reject_ambiguous_metadata: bool,
+    reject_conflicting_metadata: bool,
+    reject_unknown_metadata: bool,
+    case_insensitive_metadata: bool,
     allow_safe_methods: bool,
+    allow_preflights: bool,
+    honor_method_override: bool,
+    safe_methods: Arc<[Method]>,
+    safe_methods_order: SafeMethodsOrder,
+    protected_methods: Option<Arc<[Method]>>,
+    only_with_credentials: bool,
+    session_cookie: Option<&'static str>,
+    untrusted_paths: Arc<[&'static str]>,
+    sensitive_paths: Arc<[&'static str]>,
+    user_activated_paths: Arc<[&'static str]>,
+    embeddable: Vec<Embeddable>,
+    matrix: Matrix,
+    rules: Vec<Rule>,
+    allowed_destinations: Vec<AllowedDestinations>,
+    denied_destinations: Arc<[&'static str]>,
+    go_compat: bool,
+    trusted_origins: Arc<[&'static str]>,
+    canonical_origin: Option<Uri>,
+    external_origins: Arc<[&'static str]>,
+    websocket_origins: Arc<[&'static str]>,
+    event_streams: Arc<[&'static str]>,
 }
 
 impl PolicyBuilder {
     pub(crate) fn new() -> Self {
         Self {
             reject_missing_metadata: false,
+            reject_form_posts_without_metadata: false,
+            missing_metadata_header: None,
+            user_agent_rules: None,
+            reject_ambiguous_metadata: false,
+            reject_conflicting_metadata: false,
+            reject_unknown_metadata: false,
+            case_insensitive_metadata: false,
             allow_safe_methods: false,
+            allow_preflights: false,
+            honor_method_override: false,
+            safe_methods: Arc::new([Method::GET, Method::HEAD, Method::OPTIONS]),
+            safe_methods_order: SafeMethodsOrder::default(),
+            protected_methods: None,
+            only_with_credentials: false,
+            session_cookie: None,
+            untrusted_paths: Arc::new([]),
+            sensitive_paths: Arc::new([]),
+            user_activated_paths: Arc::new([]),
+            embeddable: Vec::new(),
+            matrix: Matrix::default(),
+            rules: Vec::new(),
+            allowed_destinations: Vec::new(),
+            denied_destinations: Arc::new([]),
+            go_compat: false,
+            trusted_origins: Arc::new([]),
+            canonical_origin: None,
+            external_origins: Arc::new([]),
+            websocket_origins: Arc::new([]),
+            event_streams: Arc::new([]),
         }
     }
 
@@ -108,16 +1197,476 @@ impl PolicyBuilder {
         self
     }
 
-    /// Allow safe requests (`GET`, `HEAD`, and `OPTIONS`) regardless of their origin
+    /// Reject requests without Fetch Metadata headers only when an older browser could send them cross-site
+    /// without a CORS preflight: `POST` requests with an `application/x-www-form-urlencoded`, `multipart/form-data`,
+    /// or `text/plain` content type, or without a content type
+    ///
+    /// Other requests without Fetch Metadata headers, such as `application/json` API calls, are allowed.
+    /// This is a middle ground for services with non-browser clients that cannot enable
+    /// [reject_missing_metadata](PolicyBuilder::reject_missing_metadata).
+    pub fn reject_form_posts_without_metadata(&mut self) -> &mut Self {
+        self.reject_form_posts_without_metadata = true;
+        self
+    }
+
+    /// Accept requests without Fetch Metadata headers when they carry the `name` header, with the `value` value if given,
+    /// even if [reject_missing_metadata](PolicyBuilder::reject_missing_metadata) or
+    /// [reject_form_posts_without_metadata](PolicyBuilder::reject_form_posts_without_metadata) are set,
+    /// and regardless of [missing_metadata_by_user_agent](PolicyBuilder::missing_metadata_by_user_agent)
+    ///
+    /// Cross-site form posts cannot set custom headers, so headers such as `x-requested-with: XMLHttpRequest`
+    /// or `x-csrf: 1` prove that an older browser or webview sent the request from a trusted script.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name, or `value` is not a valid header value.
+    pub fn accept_missing_metadata_with_header(
+        &mut self,
+        name: &'static str,
+        value: Option<&'static str>,
+    ) -> &mut Self {
+        self.missing_metadata_header = Some((
+            HeaderName::from_static(name),
+            value.map(HeaderValue::from_static),
+        ));
+        self
+    }
+
+    /// Decide the outcome of requests without Fetch Metadata headers by classifying their `user-agent` header,
+    /// e.g. allowing non-browser clients while checking the `origin` header of browsers, see [UserAgentRules]
+    ///
+    /// This takes precedence over [reject_missing_metadata](PolicyBuilder::reject_missing_metadata) and
+    /// [reject_form_posts_without_metadata](PolicyBuilder::reject_form_posts_without_metadata).
+    /// The `user-agent` header is controlled by the client, so non-browser clients must not be able to act
+    /// with ambient credentials such as cookies when they are allowed.
+    pub fn missing_metadata_by_user_agent(&mut self, rules: UserAgentRules) -> &mut Self {
+        self.user_agent_rules = Some(rules);
+        self
+    }
+
+    /// Reject requests whose Fetch Metadata headers are ambiguous, with [DenialReason::AmbiguousMetadata]
+    ///
+    /// A header is ambiguous when it is sent multiple times or as a comma-separated list with different values,
+    /// which browsers never do. Otherwise, the most restrictive `sec-fetch-site` value is used,
+    /// and ambiguous `sec-fetch-mode` and `sec-fetch-dest` headers match no known mode or destination.
+    pub fn reject_ambiguous_metadata(&mut self) -> &mut Self {
+        self.reject_ambiguous_metadata = true;
+        self
+    }
+
+    /// Reject requests sending the same Fetch Metadata header multiple times with different values,
+    /// with [DenialReason::ConflictingMetadata]
+    ///
+    /// Browsers send each header once, so conflicting duplicates point to a misbehaving proxy or a forged request.
+    /// Unlike [reject_ambiguous_metadata](PolicyBuilder::reject_ambiguous_metadata), a single header with
+    /// a comma-separated list of values is not denied. When both are set, conflicting duplicates are denied
+    /// with [DenialReason::ConflictingMetadata].
+    pub fn reject_conflicting_metadata(&mut self) -> &mut Self {
+        self.reject_conflicting_metadata = true;
+        self
+    }
+
+    /// Reject requests with unrecognized Fetch Metadata values, such as typos or values mangled by proxies,
+    /// with [DenialReason::UnknownMetadata]
+    ///
+    /// By default, an unknown `sec-fetch-site` is treated as `cross-site`, and an unknown `sec-fetch-mode`
+    /// or `sec-fetch-dest` matches none of the allow rules of the policy, so it is only allowed for
+    /// same-origin and same-site requests. Ambiguous headers are handled by
+    /// [reject_ambiguous_metadata](PolicyBuilder::reject_ambiguous_metadata) instead.
+    pub fn reject_unknown_metadata(&mut self) -> &mut Self {
+        self.reject_unknown_metadata = true;
+        self
+    }
+
+    /// Compare the values of the Fetch Metadata headers ignoring ASCII case, for middleboxes that uppercase them
+    ///
+    /// Browsers always send lowercase values, so they are compared exactly by default.
+    pub fn case_insensitive_metadata(&mut self) -> &mut Self {
+        self.case_insensitive_metadata = true;
+        self
+    }
+
+    /// Allow safe requests (`GET`, `HEAD`, and `OPTIONS` by default) regardless of their origin
     pub fn allow_safe_methods(&mut self) -> &mut Self {
         self.allow_safe_methods = true;
         self
     }
 
+    /// Allow CORS preflight requests regardless of their origin, so that they reach the CORS layer
+    ///
+    /// Preflights are `OPTIONS` requests carrying an `access-control-request-method` header.
+    /// Without this option or [allow_safe_methods](PolicyBuilder::allow_safe_methods), cross-site preflights are denied,
+    /// and browsers report a misleading CORS failure.
+    pub fn allow_preflights(&mut self) -> &mut Self {
+        self.allow_preflights = true;
+        self
+    }
+
+    /// Evaluate `POST` requests tunneling another method as both methods, for servers that honor method overrides
+    ///
+    /// The method is read from the `x-http-method-override`, `x-http-method`, or `x-method-override` headers,
+    /// or from the `_method` query parameter, and is only honored on `POST` requests, like most frameworks do.
+    /// A request is checked against both its own method and the tunneled one, and the stricter outcome applies:
+    /// it is [protected](PolicyBuilder::protect_methods) if either method is, and it is only treated as a
+    /// [safe method](PolicyBuilder::allow_safe_methods), a navigation, or a read in [go_compat](PolicyBuilder::go_compat)
+    /// mode if both methods are. A cross-site `POST` tunneling a `DELETE` is then protected even when only `DELETE` is,
+    /// while a `POST` tunneling a `GET` is never treated as a safe method.
+    /// [Custom](PolicyBuilder::custom) rules see the method of the request, and the tunneled one with
+    /// [RequestHead::tunneled_method](crate::RequestHead::tunneled_method).
+    /// The `_method` field of form bodies cannot be read, so servers reading it should not enable safe methods.
+    pub fn honor_method_override(&mut self) -> &mut Self {
+        self.honor_method_override = true;
+        self
+    }
+
+    /// Allow requests using any of the given methods regardless of their origin,
+    /// replacing the default set of safe methods
+    ///
+    /// Implies [allow_safe_methods](PolicyBuilder::allow_safe_methods).
+    pub fn safe_methods(&mut self, methods: impl Into<Arc<[Method]>>) -> &mut Self {
+        self.allow_safe_methods = true;
+        self.safe_methods = methods.into();
+        self
+    }
+
+    /// Only evaluate requests using any of the given methods, skipping all others entirely
+    ///
+    /// Requests using other methods are passed to the server without running the authorizer or the reporter.
+    pub fn protect_methods(&mut self, methods: impl Into<Arc<[Method]>>) -> &mut Self {
+        self.protected_methods = Some(methods.into());
+        self
+    }
+
+    /// Only evaluate requests carrying ambient credentials, i.e. a `cookie` or `authorization` header,
+    /// skipping all others entirely
+    ///
+    /// Requests without credentials cannot be used for CSRF attacks, as they act with no authority.
+    pub fn only_with_credentials(&mut self) -> &mut Self {
+        self.only_with_credentials = true;
+        self
+    }
+
+    /// Only evaluate requests carrying the session cookie `name`, skipping all others entirely
+    ///
+    /// Unlike [only_with_credentials](Self::only_with_credentials), unrelated cookies such as analytics or
+    /// preference ones do not cause a request to be evaluated. Use the name of the cookie holding the
+    /// session identifier, e.g. `id` for the default configuration of `tower-sessions`.
+    pub fn only_with_session_cookie(&mut self, name: &'static str) -> &mut Self {
+        self.session_cookie = Some(name);
+        self
+    }
+
+    /// Changes when the safe-method shortcut is applied, see [SafeMethodsOrder]
+    ///
+    /// The [SecFetchAuthorizer](crate::SecFetchAuthorizer) always runs first, so requests it allows are never reported.
+    pub fn safe_methods_order(&mut self, order: SafeMethodsOrder) -> &mut Self {
+        self.safe_methods_order = order;
+        self
+    }
+
+    /// Deny cross-site `document` and `iframe` requests to paths starting with any of the given prefixes,
+    /// even if they would otherwise be allowed
+    ///
+    /// Useful to harden subtrees serving uploaded or user-generated content,
+    /// which should never be navigated to or embedded from another site.
+    /// Paths are normalized like in [deny_navigation_to](PolicyBuilder::deny_navigation_to),
+    /// so that `//uploads/x.html` and `/%75ploads/x.html` are denied as well.
+    pub fn untrusted_paths(&mut self, prefixes: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        self.untrusted_paths = prefixes.into();
+        self
+    }
+
+    /// Deny cross-site `GET` navigations to the given paths, which are otherwise allowed
+    ///
+    /// Useful for endpoints that change state on `GET`, such as `/logout`.
+    /// Paths are matched like the patterns of [require_user_activation](PolicyBuilder::require_user_activation),
+    /// after [normalizing](crate::PathAuthorizer::normalize_paths) them and stripping trailing slashes and `;` path parameters,
+    /// so that `/logout/`, `/logout;x` and `/logou%74` are denied as well.
+    /// The check runs before [allow_safe_methods](PolicyBuilder::allow_safe_methods), which cannot bypass it.
+    pub fn deny_navigation_to(&mut self, paths: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        self.sensitive_paths = paths.into();
+        self
+    }
+
+    /// Deny cross-site navigations to the paths matching any of the given patterns,
+    /// unless they were triggered by a user activation (`sec-fetch-user: ?1`)
+    ///
+    /// Script-driven navigations, such as `window.location` assignments, do not carry a user activation.
+    /// In patterns, `*` matches a single path segment, `{v}` matches an API version segment such as `v1` or `v12`,
+    /// and a trailing `**` matches any number of segments, e.g. `/admin/**` matches `/admin` and all paths below it.
+    pub fn require_user_activation(
+        &mut self,
+        patterns: impl Into<Arc<[&'static str]>>,
+    ) -> &mut Self {
+        self.user_activated_paths = patterns.into();
+        self
+    }
+
+    /// Allow cross-site `iframe` embeds of the paths starting with `prefix`, only from the given origins
+    ///
+    /// The embedding origin is read from the `origin` header, or from the `referer` header when missing,
+    /// as browsers do not send the origin for embed navigations.
+    /// Origins must be written as `scheme://host[:port]`, e.g. `https://partner.example.com`.
+    pub fn allow_embedding(
+        &mut self,
+        prefix: &'static str,
+        origins: impl Into<Arc<[&'static str]>>,
+    ) -> &mut Self {
+        self.embeddable.push(Embeddable {
+            prefix,
+            origins: origins.into(),
+        });
+        self
+    }
+
+    /// Allow cross-site `GET` navigations only when `sec-fetch-dest` is one of the given values,
+    /// replacing the default `empty` and `document`
+    ///
+    /// Unknown destinations are ignored.
+    pub fn navigation_destinations(
+        &mut self,
+        destinations: impl Into<Arc<[&'static str]>>,
+    ) -> &mut Self {
+        let destinations: Arc<[&'static str]> = destinations.into();
+        self.matrix
+            .navigation_destinations(destinations.iter().copied().filter_map(Dest::parse));
+        self
+    }
+
+    /// Replaces the decision table mapping destinations, modes, and sites to an [Action], see [Matrix]
+    pub fn matrix(&mut self, matrix: Matrix) -> &mut Self {
+        self.matrix = matrix;
+        self
+    }
+
+    /// Allow cross-site requests to the paths starting with `prefix`,
+    /// when `sec-fetch-dest` is one of the given values (e.g. `image`, `font`)
+    pub fn allow_destinations(
+        &mut self,
+        prefix: &'static str,
+        destinations: impl Into<Arc<[&'static str]>>,
+    ) -> &mut Self {
+        self.allowed_destinations.push(AllowedDestinations {
+            prefix,
+            destinations: destinations.into(),
+        });
+        self
+    }
+
+    /// Allow cross-site subresource requests for static assets (images, fonts, stylesheets, and scripts)
+    /// to the paths starting with `prefix`, so that they can be hotlinked
+    ///
+    /// This is a shorthand for [allow_destinations](PolicyBuilder::allow_destinations)
+    /// with the `image`, `font`, `style`, and `script` destinations.
+    pub fn allow_static_assets(&mut self, prefix: &'static str) -> &mut Self {
+        self.allow_destinations(prefix, ["image", "font", "style", "script"])
+    }
+
+    /// Allow cross-site `EventSource` subscriptions to the paths starting with any of the given prefixes,
+    /// without allowing every cross-site `GET` request with [allow_safe_methods](PolicyBuilder::allow_safe_methods)
+    ///
+    /// `EventSource` requests are `GET` requests in `cors` mode to the `empty` destination,
+    /// accepting `text/event-stream` responses.
+    pub fn allow_event_streams(&mut self, prefixes: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        self.event_streams = prefixes.into();
+        self
+    }
+
+    /// Allow cross-site WebSocket handshakes whose `origin` header is one of the given origins (e.g. `https://partner.example`)
+    ///
+    /// Other cross-site handshakes are denied with [DenialReason::WebsocketOrigin].
+    pub fn allow_websocket_origins(
+        &mut self,
+        origins: impl Into<Arc<[&'static str]>>,
+    ) -> &mut Self {
+        self.websocket_origins = origins.into();
+        self
+    }
+
+    /// Only allow same-origin WebSocket handshakes, denying same-site ones
+    /// unless their origin is allowed by [allow_websocket_origins](PolicyBuilder::allow_websocket_origins)
+    pub fn same_origin_websockets(&mut self) -> &mut Self {
+        self.matrix = std::mem::take(&mut self.matrix).set_mode(
+            Mode::Websocket,
+            Site::SameSite,
+            Action::Deny,
+        );
+        self
+    }
+
+    /// Deny cross-site requests when `sec-fetch-dest` is one of the given values (e.g. `object`, `embed`, `iframe`),
+    /// even if they would otherwise be allowed by [allow_destinations](PolicyBuilder::allow_destinations),
+    /// [allow_embedding](PolicyBuilder::allow_embedding), or the navigation carve-out
+    pub fn deny_destinations(&mut self, destinations: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        self.denied_destinations = destinations.into();
+        self
+    }
+
+    /// Adds a custom rule, evaluated in order with the other custom rules,
+    /// after the Fetch Metadata headers are parsed and before the [Matrix] is applied
+    ///
+    /// Unlike a [SecFetchAuthorizer](crate::SecFetchAuthorizer), custom rules can base their decision on the
+    /// [FetchMetadata] of the request. Requests without Fetch Metadata headers never reach custom rules.
+    pub fn custom<F>(&mut self, rule: F) -> &mut Self
+    where
+        F: Fn(&FetchMetadata, &RequestHead<'_>) -> RuleDecision + Send + Sync + 'static,
+    {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Allow the cross-site `POST` navigations to the `document` destination made by the SAML HTTP-POST binding,
+    /// when the path matches any of the given Assertion Consumer Service paths
+    ///
+    /// Paths can be patterns, where `*` matches a single path segment and a trailing `**` matches any number of segments.
+    /// Other requests to these paths are evaluated as usual. This is a shorthand for a [custom](PolicyBuilder::custom) rule.
+    pub fn allow_saml_post_binding(&mut self, paths: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        let paths = paths.into();
+        self.custom(move |metadata, request| {
+            let acs = request.method() == Method::POST
+                && metadata.mode == Some(Mode::Navigate)
+                && metadata.dest == Some(Dest::Document)
+                && paths
+                    .iter()
+                    .any(|pattern| path_matches(pattern, request.uri().path()));
+
+            if acs {
+                RuleDecision::Allow
+            } else {
+                RuleDecision::Continue
+            }
+        })
+    }
+
+    /// Adds the rules of a [RuleSet](crate::RuleSet) loaded from a configuration file,
+    /// evaluated like a [custom](PolicyBuilder::custom) rule
+    #[cfg(feature = "config")]
+    pub fn rules(&mut self, rules: crate::RuleSet) -> &mut Self {
+        self.custom(move |metadata, request| rules.decide(metadata, request))
+    }
+
+    /// Evaluate requests exactly like Go's [`http.CrossOriginProtection`](https://pkg.go.dev/net/http#CrossOriginProtection),
+    /// so that mixed Go and Rust deployments make identical decisions
+    ///
+    /// - `GET`, `HEAD`, and `OPTIONS` requests are always allowed
+    /// - requests with `sec-fetch-site` set to `same-origin` or `none` are allowed, other values are denied
+    /// - requests without `sec-fetch-site` are allowed if they have no `origin` header, or if its host matches the request host,
+    ///   or if it is one of the [external_origins](PolicyBuilder::external_origins) when set
+    /// - cross-origin requests from [trusted_origins](PolicyBuilder::trusted_origins) are allowed
+    ///
+    /// All other options of the policy are ignored, except [honor_method_override](PolicyBuilder::honor_method_override).
+    /// Go's bypass patterns correspond to [allowing](crate::SecFetchLayer::allowing).
+    pub fn go_compat(&mut self) -> &mut Self {
+        self.go_compat = true;
+        self
+    }
+
+    /// Origins allowed to make cross-origin requests, written as `scheme://host[:port]`
+    ///
+    /// Requests whose `origin` header is one of `origins` are allowed, unless they are denied by a
+    /// [custom](PolicyBuilder::custom) rule, an [untrusted path](PolicyBuilder::untrusted_paths),
+    /// or a [sensitive path](PolicyBuilder::deny_navigation_to), and in [go_compat](PolicyBuilder::go_compat)
+    /// mode like in Go. With the `tower-http` feature, the same list configures a `CorsLayer`,
+    /// see [SecFetchLayer::cors_layer](crate::SecFetchLayer::cors_layer).
+    pub fn trusted_origins(&mut self, origins: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        self.trusted_origins = origins.into();
+        self
+    }
+
+    /// The origins of the service as seen by browsers, written as `scheme://host[:port]`
+    ///
+    /// When set, the `origin` header is compared with them instead of the request host, which may have been rewritten
+    /// by TLS termination or a reverse proxy, e.g. by the origin fallback of [go_compat](PolicyBuilder::go_compat).
+    pub fn external_origins(&mut self, origins: impl Into<Arc<[&'static str]>>) -> &mut Self {
+        self.external_origins = origins.into();
+        self
+    }
+
+    /// Let denied requests whose scheme or host differ from `origin`, written as `scheme://host[:port]`, through to the inner service,
+    /// so that an HTTP to HTTPS or host canonicalization redirect middleware placed below this layer can redirect them
+    ///
+    /// Only `3xx` responses are returned for these requests: any other response of the inner service is replaced
+    /// with the denial response, so non-canonical origins that are not redirected stay protected.
+    /// The redirected request is evaluated again once it reaches the canonical origin.
+    ///
+    /// The scheme is read from the request URI, or from the `x-forwarded-proto` header when missing.
+    /// Schemes and hosts are compared case-insensitively.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `origin` is not a valid URI.
+    pub fn canonical_origin(&mut self, origin: &'static str) -> &mut Self {
+        self.canonical_origin = Some(origin.parse().expect("canonical origin is a valid URI"));
+        self
+    }
+
     pub(crate) fn build(self) -> Policy {
         Policy {
             reject_missing_metadata: self.reject_missing_metadata,
+            reject_form_posts_without_metadata: self.reject_form_posts_without_metadata,
+            missing_metadata_header: self.missing_metadata_header,
+            user_agent_rules: self.user_agent_rules,
+            reject_ambiguous_metadata: self.reject_ambiguous_metadata,
+            reject_conflicting_metadata: self.reject_conflicting_metadata,
+            reject_unknown_metadata: self.reject_unknown_metadata,
+            case_insensitive_metadata: self.case_insensitive_metadata,
             allow_safe_methods: self.allow_safe_methods,
+            allow_preflights: self.allow_preflights,
+            honor_method_override: self.honor_method_override,
+            safe_methods: self.safe_methods,
+            safe_methods_order: self.safe_methods_order,
+            protected_methods: self.protected_methods,
+            only_with_credentials: self.only_with_credentials,
+            session_cookie: self.session_cookie,
+            untrusted_paths: self.untrusted_paths,
+            sensitive_paths: self.sensitive_paths,
+            user_activated_paths: self.user_activated_paths,
+            embeddable: self.embeddable.into(),
+            matrix: self.matrix,
+            rules: self.rules.into(),
+            allowed_destinations: self.allowed_destinations.into(),
+            denied_destinations: self.denied_destinations,
+            go_compat: self.go_compat,
+            trusted_origins: self.trusted_origins,
+            canonical_origin: self.canonical_origin,
+            external_origins: self.external_origins,
+            websocket_origins: self.websocket_origins,
+            event_streams: self.event_streams,
+        }
+    }
+
+    fn from_policy(policy: Policy) -> Self {
+        Self {
+            reject_missing_metadata: policy.reject_missing_metadata,
+            reject_form_posts_without_metadata: policy.reject_form_posts_without_metadata,
+            missing_metadata_header: policy.missing_metadata_header,
+            user_agent_rules: policy.user_agent_rules,
+            reject_ambiguous_metadata: policy.reject_ambiguous_metadata,
+            reject_conflicting_metadata: policy.reject_conflicting_metadata,
+            reject_unknown_metadata: policy.reject_unknown_metadata,
+            case_insensitive_metadata: policy.case_insensitive_metadata,
+            allow_safe_methods: policy.allow_safe_methods,
+            allow_preflights: policy.allow_preflights,
+            honor_method_override: policy.honor_method_override,
+            safe_methods: policy.safe_methods,
+            safe_methods_order: policy.safe_methods_order,
+            protected_methods: policy.protected_methods,
+            only_with_credentials: policy.only_with_credentials,
+            session_cookie: policy.session_cookie,
+            untrusted_paths: policy.untrusted_paths,
+            sensitive_paths: policy.sensitive_paths,
+            user_activated_paths: policy.user_activated_paths,
+            embeddable: policy.embeddable.to_vec(),
+            matrix: policy.matrix,
+            rules: policy.rules.to_vec(),
+            allowed_destinations: policy.allowed_destinations.to_vec(),
+            denied_destinations: policy.denied_destinations,
+            go_compat: policy.go_compat,
+            trusted_origins: policy.trusted_origins,
+            canonical_origin: policy.canonical_origin,
+            external_origins: policy.external_origins,
+            websocket_origins: policy.websocket_origins,
+            event_streams: policy.event_streams,
         }
     }
 }
@@ -129,13 +1678,295 @@ fn zip3<T1, T2, T3>(a: Option<T1>, b: Option<T2>, c: Option<T3>) -> Option<(T1,
     }
 }
 
-fn header_in(header: &HeaderValue, values: impl IntoIterator<Item = &'static str>) -> bool {
+/// A Fetch Metadata header of a request, resolving the values of headers sent multiple times
+/// or as a comma-separated list
+struct MetadataHeader<'a> {
+    name: HeaderName,
+    value: Cow<'a, HeaderValue>,
+    /// Whether the header has different values
+    ambiguous: bool,
+    /// Whether the header is sent multiple times with different values
+    conflicting: bool,
+}
+
+impl<'a> MetadataHeader<'a> {
+    /// Replaces ambiguous `sec-fetch-mode` and `sec-fetch-dest` headers, so that they match no known value
+    const AMBIGUOUS: HeaderValue = HeaderValue::from_static("ambiguous");
+
+    /// Resolves the `name` header, lowercasing its values if `case_insensitive`
+    fn get(headers: &'a HeaderMap, name: HeaderName, case_insensitive: bool) -> Option<Self> {
+        let lowercase = |value: &'a [u8]| {
+            if case_insensitive && value.iter().any(u8::is_ascii_uppercase) {
+                Cow::Owned(value.to_ascii_lowercase())
+            } else {
+                Cow::Borrowed(value)
+            }
+        };
+
+        let mut all = headers.get_all(&name).iter();
+        let first = all.next()?;
+        if all.next().is_none() && !first.as_bytes().contains(&b',') {
+            let value = match lowercase(first.as_bytes()) {
+                Cow::Borrowed(_) => Cow::Borrowed(first),
+                Cow::Owned(value) => Cow::Owned(HeaderValue::from_bytes(&value).ok()?),
+            };
+
+            return Some(Self {
+                name: name.clone(),
+                value,
+                ambiguous: false,
+                conflicting: false,
+            });
+        }
+
+        let values = || {
+            headers
+                .get_all(&name)
+                .iter()
+                .flat_map(|value| value.as_bytes().split(|byte| *byte == b','))
+                .map(<[u8]>::trim_ascii)
+                .filter(|value| !value.is_empty())
+                .map(lowercase)
+        };
+
+        let Some(value) = values().next() else {
+            return Some(Self {
+                name: name.clone(),
+                value: Cow::Borrowed(first),
+                ambiguous: false,
+                conflicting: false,
+            });
+        };
+
+        if values().all(|other| other == value) {
+            return Some(Self {
+                name: name.clone(),
+                value: Cow::Owned(HeaderValue::from_bytes(&value).ok()?),
+                ambiguous: false,
+                conflicting: false,
+            });
+        }
+
+        let value = if name == header::SEC_FETCH_SITE {
+            let site = values()
+                .map(|value| Site::from_bytes(&value))
+                .max_by_key(|site| match site {
+                    Site::SameOrigin => 0,
+                    Site::None => 1,
+                    Site::SameSite => 2,
+                    Site::CrossSite => 3,
+                })
+                .unwrap_or(Site::CrossSite);
+            HeaderValue::from_static(site.as_str())
+        } else {
+            Self::AMBIGUOUS
+        };
+
+        let mut lines = headers
+            .get_all(&name)
+            .iter()
+            .map(|value| lowercase(value.as_bytes().trim_ascii()));
+        let first = lines.next();
+        let conflicting = lines.any(|line| Some(line) != first);
+
+        Some(Self {
+            name: name.clone(),
+            value: Cow::Owned(value),
+            ambiguous: true,
+            conflicting,
+        })
+    }
+
+    fn value(&self) -> &HeaderValue {
+        &self.value
+    }
+
+    /// Whether the value is a known site, mode, or destination, depending on the header
+    fn is_known(&self) -> bool {
+        if self.name == header::SEC_FETCH_SITE {
+            Site::ALL
+                .iter()
+                .any(|site| site.as_str().as_bytes() == self.value.as_bytes())
+        } else if self.name == header::SEC_FETCH_MODE {
+            Mode::from_header(&self.value).is_some()
+        } else {
+            Dest::from_header(&self.value).is_some()
+        }
+    }
+}
+
+fn header_in<V: AsRef<[u8]>>(header: &HeaderValue, values: impl IntoIterator<Item = V>) -> bool {
     values
         .into_iter()
-        .map(HeaderValue::from_static)
-        .any(|value| value == header)
+        .any(|value| value.as_ref() == header.as_bytes())
+}
+
+pub(crate) fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut segments = path.trim_start_matches('/').split('/');
+    let is_version = |segment: &str| {
+        segment.strip_prefix('v').is_some_and(|version| {
+            !version.is_empty() && version.bytes().all(|digit| digit.is_ascii_digit())
+        })
+    };
+
+    for expected in pattern.trim_start_matches('/').split('/') {
+        if expected == "**" {
+            return true;
+        }
+
+        match segments.next() {
+            Some(segment)
+                if expected == "*"
+                    || expected == segment
+                    || (expected == "{v}" && is_version(segment)) => {}
+            _ => return false,
+        }
+    }
+
+    segments.next().is_none()
 }
 
-fn method_in(method: &Method, values: impl IntoIterator<Item = Method>) -> bool {
-    values.into_iter().any(|value| value == method)
+/// Normalizes the path like [normalize_paths](crate::PathAuthorizer::normalize_paths),
+/// then strips the `;` parameters of every segment and the trailing slash
+fn normalize_path(path: &str) -> Cow<'_, str> {
+    let path = authorizer::normalize_path(path);
+    if !path.contains(';') && (path.len() <= 1 || !path.ends_with('/')) {
+        return path;
+    }
+
+    let mut path = path
+        .split('/')
+        .map(|segment| segment.split(';').next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("/");
+    path.truncate(path.trim_end_matches('/').len());
+    if path.is_empty() {
+        return Cow::Borrowed("/");
+    }
+
+    Cow::Owned(path)
+}
+
+const METHOD_OVERRIDE_HEADERS: [&str; 3] = [
+    "x-http-method-override",
+    "x-http-method",
+    "x-method-override",
+];
+
+fn is_form_post<B>(request: &http::Request<B>, method: &Method) -> bool {
+    let content_type = request
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        });
+
+    *method == Method::POST
+        && content_type.is_none_or(|content_type| {
+            [
+                "application/x-www-form-urlencoded",
+                "multipart/form-data",
+                "text/plain",
+            ]
+            .contains(&content_type.as_str())
+        })
+}
+
+fn is_json(request: &RequestHead<'_>) -> bool {
+    request
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|content_type| content_type.trim().eq_ignore_ascii_case("application/json"))
+}
+
+fn has_cookie<B>(request: &http::Request<B>, name: &str) -> bool {
+    request
+        .headers()
+        .get_all(http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.split_once('='))
+        .any(|(cookie, _)| cookie.trim() == name)
+}
+
+// Preflights are sent by browsers to the URL of the actual request, including any method override,
+// so they are recognized by their own method
+fn is_preflight<B>(request: &http::Request<B>) -> bool {
+    request.method() == Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(http::header::ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+pub(crate) fn request_host<B>(request: &http::Request<B>) -> Option<&str> {
+    match request.headers().get(http::header::HOST) {
+        Some(host) => host.to_str().ok(),
+        None => request
+            .uri()
+            .authority()
+            .map(|authority| authority.as_str()),
+    }
+}
+
+fn request_scheme<B>(request: &http::Request<B>) -> Option<&str> {
+    request.uri().scheme_str().or_else(|| {
+        request
+            .headers()
+            .get(header::X_FORWARDED_PROTO)
+            .and_then(|proto| proto.to_str().ok())
+    })
+}
+
+/// Whether the request was upgraded to HTTPS by a page served over HTTP from the same host, both on their default port,
+/// see [Upgrade-Insecure-Requests](https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Headers/Upgrade-Insecure-Requests)
+fn is_insecure_upgrade<B>(request: &http::Request<B>, origin: &HeaderValue) -> bool {
+    let upgrades = request
+        .headers()
+        .get(header::UPGRADE_INSECURE_REQUESTS)
+        .is_some_and(|upgrade| upgrade == "1");
+    let Some(origin) = origin
+        .to_str()
+        .ok()
+        .and_then(|origin| origin.parse::<Uri>().ok())
+    else {
+        return false;
+    };
+    let Some(host) = request_host(request).and_then(|host| host.parse::<Authority>().ok()) else {
+        return false;
+    };
+
+    upgrades
+        && origin.scheme_str() == Some("http")
+        && origin.port_u16().unwrap_or(80) == 80
+        && request_scheme(request) == Some("https")
+        && host.port_u16().unwrap_or(443) == 443
+        && origin
+            .host()
+            .is_some_and(|origin| origin.eq_ignore_ascii_case(host.host()))
+}
+
+fn request_origin<B>(request: &http::Request<B>) -> Option<String> {
+    let headers = request.headers();
+
+    if let Some(origin) = headers.get(http::header::ORIGIN) {
+        return origin.to_str().ok().map(str::to_string);
+    }
+
+    let referer: Uri = headers
+        .get(http::header::REFERER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+
+    Some(format!("{}://{}", referer.scheme()?, referer.authority()?))
 }
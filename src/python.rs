@@ -0,0 +1,302 @@
+//! Python bindings to the evaluation policy, to run what-if analyses on access logs with the same implementation as the middleware
+//!
+//! Build an extension module with `cargo rustc --release --features pyo3,pyo3/extension-module --crate-type cdylib`,
+//! and import `libtower_sec_fetch.so` renamed to `tower_sec_fetch.so`:
+//!
+//! ```python
+//! from tower_sec_fetch import Policy
+//!
+//! policy = Policy(allow_safe_methods=True)
+//! verdict = policy.simulate("POST", "/submit", {"sec-fetch-site": "cross-site", "sec-fetch-mode": "cors", "sec-fetch-dest": "empty"})
+//! print(verdict.verdict, verdict.reason, verdict.explanation)
+//! print(policy.pipeline())
+//! ```
+
+use std::collections::HashMap;
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+use crate::{SafeMethodsOrder, SecFetchLayer, Verdict};
+
+/// An evaluation policy, configured with the same flags as the [ffi](crate::ffi) interface or with a preset
+#[pyclass(name = "Policy", module = "tower_sec_fetch", frozen)]
+struct PyPolicy {
+    layer: SecFetchLayer,
+}
+
+#[pymethods]
+impl PyPolicy {
+    #[new]
+    #[pyo3(signature = (*, reject_missing_metadata = false, allow_safe_methods = false, safe_methods_after_report = false, go_compat = false))]
+    fn new(
+        reject_missing_metadata: bool,
+        allow_safe_methods: bool,
+        safe_methods_after_report: bool,
+        go_compat: bool,
+    ) -> Self {
+        let layer = SecFetchLayer::new(|policy| {
+            if reject_missing_metadata {
+                policy.reject_missing_metadata();
+            }
+            if allow_safe_methods {
+                policy.allow_safe_methods();
+            }
+            if safe_methods_after_report {
+                policy.safe_methods_order(SafeMethodsOrder::AfterReport);
+            }
+            if go_compat {
+                policy.go_compat();
+            }
+        });
+
+        Self { layer }
+    }
+
+    /// The [website](crate::Policy::website) preset
+    #[staticmethod]
+    fn website() -> Self {
+        Self::preset(crate::Policy::website())
+    }
+
+    /// The [api](crate::Policy::api) preset
+    #[staticmethod]
+    fn api() -> Self {
+        Self::preset(crate::Policy::api())
+    }
+
+    /// The [strict](crate::Policy::strict) preset
+    #[staticmethod]
+    fn strict() -> Self {
+        Self::preset(crate::Policy::strict())
+    }
+
+    /// The [public](crate::Policy::public) preset
+    #[staticmethod]
+    fn public() -> Self {
+        Self::preset(crate::Policy::public())
+    }
+
+    /// The [graphql](crate::Policy::graphql) preset
+    #[staticmethod]
+    fn graphql() -> Self {
+        Self::preset(crate::Policy::graphql())
+    }
+
+    /// Evaluates a request described by its method, path, and headers, see [SecFetchLayer::simulate]
+    ///
+    /// The headers are either a `dict` or a list of `(name, value)` tuples, to repeat a header.
+    fn simulate(&self, method: &str, path: &str, headers: Headers) -> PyResult<PyVerdict> {
+        let headers = headers.into_vec();
+        let headers: Vec<_> = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        self.layer
+            .simulate(method, path, &headers)
+            .map(PyVerdict)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// The verdicts for every combination of Fetch Metadata headers and method, see [SecFetchLayer::decision_table]
+    ///
+    /// Each decision is a `dict` with the same keys as its serialized form, so that it can be loaded into a data frame.
+    fn decision_table<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.layer
+            .decision_table()
+            .decisions()
+            .iter()
+            .map(|decision| {
+                let row = PyDict::new(py);
+                row.set_item("site", decision.site.map(|site| site.as_str()))?;
+                row.set_item("mode", decision.mode.map(|mode| mode.as_str()))?;
+                row.set_item("dest", decision.dest.map(|dest| dest.as_str()))?;
+                row.set_item("user", decision.user)?;
+                row.set_item("method", decision.method.as_str())?;
+                row.set_item("verdict", decision.verdict.as_str())?;
+                row.set_item(
+                    "denial",
+                    decision.verdict.reason().map(|reason| reason.as_str()),
+                )?;
+                Ok(row)
+            })
+            .collect()
+    }
+
+    /// Describes the steps executed for every request, see [SecFetchLayer::pipeline]
+    fn pipeline(&self) -> String {
+        self.layer.pipeline().to_string()
+    }
+}
+
+impl PyPolicy {
+    fn preset(policy: crate::Policy) -> Self {
+        Self {
+            layer: SecFetchLayer::default().with_policy(policy),
+        }
+    }
+}
+
+#[derive(FromPyObject)]
+enum Headers {
+    Map(HashMap<String, String>),
+    List(Vec<(String, String)>),
+}
+
+impl Headers {
+    fn into_vec(self) -> Vec<(String, String)> {
+        match self {
+            Self::Map(headers) => headers.into_iter().collect(),
+            Self::List(headers) => headers,
+        }
+    }
+}
+
+/// The [Verdict] of a simulated request
+#[pyclass(name = "Verdict", module = "tower_sec_fetch", frozen)]
+struct PyVerdict(Verdict);
+
+#[pymethods]
+impl PyVerdict {
+    /// `allowed`, `reported`, or `denied`, see [Verdict::as_str]
+    #[getter]
+    fn verdict(&self) -> &'static str {
+        self.0.as_str()
+    }
+
+    /// Whether the request reaches the inner service, see [Verdict::is_allowed]
+    #[getter]
+    fn allowed(&self) -> bool {
+        self.0.is_allowed()
+    }
+
+    /// The identifier of the denial reason, or `None` if the request is allowed
+    #[getter]
+    fn reason(&self) -> Option<&'static str> {
+        self.0.reason().map(|reason| reason.as_str())
+    }
+
+    /// Why the request was denied, in plain English, or `None` if the request is allowed
+    #[getter]
+    fn explanation(&self) -> Option<String> {
+        self.0.reason().map(|reason| reason.to_string())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Verdict {}>", self.0)
+    }
+}
+
+#[pymodule]
+fn tower_sec_fetch(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyPolicy>()?;
+    module.add_class::<PyVerdict>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+    use pyo3::{ffi::c_str, types::IntoPyDict};
+
+    use super::*;
+
+    fn run<T>(code: &std::ffi::CStr, f: impl FnOnce(&Bound<'_, PyDict>) -> T) -> T {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(tower_sec_fetch)(py);
+            let locals = [("tower_sec_fetch", module)].into_py_dict(py).unwrap();
+            py.run(code, None, Some(&locals)).unwrap();
+            f(&locals)
+        })
+    }
+
+    fn get<T: for<'py> FromPyObject<'py>>(locals: &Bound<'_, PyDict>, name: &str) -> T {
+        locals.get_item(name).unwrap().unwrap().extract().unwrap()
+    }
+
+    #[test]
+    fn it_simulates_requests() {
+        run(
+            c_str!(
+                r#"
+policy = tower_sec_fetch.Policy()
+denied = policy.simulate("POST", "/submit", {"sec-fetch-site": "cross-site", "sec-fetch-mode": "cors", "sec-fetch-dest": "empty"})
+allowed = policy.simulate("POST", "/submit", [("sec-fetch-site", "same-origin")])
+reported = tower_sec_fetch.Policy(allow_safe_methods=True, safe_methods_after_report=True).simulate(
+    "GET", "/", {"sec-fetch-site": "cross-site", "sec-fetch-mode": "cors", "sec-fetch-dest": "empty"}
+)
+denied = (denied.verdict, denied.allowed, denied.reason, denied.explanation)
+allowed = (allowed.verdict, allowed.allowed, allowed.reason, allowed.explanation)
+reported = (reported.verdict, reported.allowed, reported.reason, reported.explanation)
+"#
+            ),
+            |locals| {
+                type Verdict = (String, bool, Option<String>, Option<String>);
+                let denied: Verdict = get(locals, "denied");
+                let allowed: Verdict = get(locals, "allowed");
+                let reported: Verdict = get(locals, "reported");
+
+                check!(
+                    denied
+                        == (
+                            "denied".into(),
+                            false,
+                            Some("cross-site".into()),
+                            Some("cross-site requests are not allowed for this resource".into()),
+                        )
+                );
+                check!(allowed == ("allowed".into(), true, None, None));
+                check!(reported.0 == "reported");
+                check!(reported.1);
+                check!(reported.2 == Some("cross-site".into()));
+            },
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_requests() {
+        run(
+            c_str!(
+                r#"
+try:
+    tower_sec_fetch.Policy().simulate("NOT A METHOD", "/", {})
+    result = None
+except ValueError as err:
+    result = "ValueError"
+"#
+            ),
+            |locals| {
+                check!(get::<Option<String>>(locals, "result") == Some("ValueError".into()));
+            },
+        );
+    }
+
+    #[test]
+    fn it_explains_presets() {
+        run(
+            c_str!(
+                r#"
+policy = tower_sec_fetch.Policy.api()
+missing = policy.simulate("POST", "/", {}).reason
+table = policy.decision_table()
+rows = len(table)
+missing_rows = sorted({row["denial"] for row in table if row["site"] is None})
+pipeline = policy.pipeline()
+"#
+            ),
+            |locals| {
+                let layer = SecFetchLayer::default().with_policy(crate::Policy::api());
+
+                check!(get::<String>(locals, "missing") == "missing-metadata");
+                check!(get::<usize>(locals, "rows") == layer.decision_table().decisions().len());
+                check!(get::<Vec<String>>(locals, "missing_rows") == vec!["missing-metadata"]);
+                check!(get::<String>(locals, "pipeline") == layer.pipeline().to_string());
+            },
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::{
+    DenialId, DenialReason, SecFetchReporter, Severity, header,
+    hmac::{self, HmacAlgorithm, Keyring},
+};
+
+/// Serializable description of a denied request
+#[derive(Clone, Debug, Serialize)]
+pub struct ViolationReport {
+    /// The method of the denied request
+    pub method: String,
+    /// The path of the denied request
+    pub path: String,
+    /// The `sec-fetch-site` header, if present
+    pub site: Option<String>,
+    /// The `sec-fetch-mode` header, if present
+    pub mode: Option<String>,
+    /// The `sec-fetch-dest` header, if present
+    pub dest: Option<String>,
+    /// The [DenialId] of the request, if enabled
+    pub denial_id: Option<String>,
+    /// Why the request was denied
+    pub reason: Option<String>,
+    /// The [Severity] of the denial, if [severities](crate::SecFetchLayer::severities) are configured
+    pub severity: Option<String>,
+    /// When the request was denied, as a Unix timestamp in seconds
+    pub timestamp: u64,
+}
+
+impl ViolationReport {
+    pub fn from_request<B>(request: &http::Request<B>) -> Self {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Self {
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+            site: header(header::SEC_FETCH_SITE),
+            mode: header(header::SEC_FETCH_MODE),
+            dest: header(header::SEC_FETCH_DEST),
+            denial_id: request
+                .extensions()
+                .get::<DenialId>()
+                .map(DenialId::to_string),
+            reason: request
+                .extensions()
+                .get::<DenialReason>()
+                .map(|reason| reason.as_str().to_string()),
+            severity: request
+                .extensions()
+                .get::<Severity>()
+                .map(|severity| severity.as_str().to_string()),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        }
+    }
+}
+
+/// A [ViolationReport] serialized and signed by a [ReportSigner]
+///
+/// The signature covers the exact bytes of `payload`, so downstream pipelines can detect forged or altered reports.
+#[derive(Clone, Debug, Serialize)]
+pub struct SignedReport {
+    /// The JSON-serialized [ViolationReport]
+    pub payload: String,
+    /// The id of the [Keyring] key that signed the report
+    pub key_id: String,
+    /// The hex-encoded HMAC of the payload
+    pub signature: String,
+}
+
+/// Signs [ViolationReport]s for tamper evidence
+pub struct ReportSigner {
+    keyring: Keyring,
+    algorithm: HmacAlgorithm,
+}
+
+impl ReportSigner {
+    pub fn new(keyring: impl Into<Keyring>) -> Self {
+        Self {
+            keyring: keyring.into(),
+            algorithm: HmacAlgorithm::default(),
+        }
+    }
+
+    /// The HMAC algorithm used to sign reports, defaults to [HmacAlgorithm::Sha256]
+    pub fn algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Serializes and signs the report with the newest key of the keyring
+    pub fn sign(&self, report: &ViolationReport) -> SignedReport {
+        let payload = serde_json::to_string(report).expect("report is serializable");
+        let (key_id, key) = self.keyring.signing_key();
+
+        SignedReport {
+            signature: hmac::hex_encode(&self.algorithm.sign(&key, payload.as_bytes())),
+            payload,
+            key_id: key_id.to_string(),
+        }
+    }
+
+    /// Whether the report was signed by a key of the keyring and was not altered
+    pub fn verify(&self, report: &SignedReport) -> bool {
+        let Some(key) = self.keyring.key(&report.key_id) else {
+            return false;
+        };
+        let Some(signature) = hmac::hex_decode(report.signature.as_bytes()) else {
+            return false;
+        };
+
+        self.algorithm
+            .verify(&key, report.payload.as_bytes(), &signature)
+    }
+}
+
+/// A [SecFetchReporter] that passes a [SignedReport] of every denied request to a sink,
+/// such as a log pipeline
+///
+/// ```
+/// use tower_sec_fetch::{ReportSigner, SecFetchLayer, SignedReporter};
+///
+/// let reporter = SignedReporter::new(ReportSigner::new(b"report-secret".to_vec()), |report| {
+///     println!("{}", serde_json::to_string(&report).unwrap());
+/// });
+///
+/// SecFetchLayer::default().with_reporter(reporter);
+/// ```
+pub struct SignedReporter<F> {
+    signer: ReportSigner,
+    sink: F,
+}
+
+impl<F> SignedReporter<F>
+where
+    F: Fn(SignedReport),
+{
+    pub fn new(signer: ReportSigner, sink: F) -> Self {
+        Self { signer, sink }
+    }
+}
+
+impl<F> SecFetchReporter for SignedReporter<F>
+where
+    F: Fn(SignedReport),
+{
+    fn on_request_denied<B>(&self, request: &http::Request<B>) {
+        (self.sink)(self.signer.sign(&ViolationReport::from_request(request)));
+    }
+}
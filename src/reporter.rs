@@ -1,8 +1,51 @@
 use std::ops::Deref;
 
+use crate::rule::{self, Sealed};
+
 /// Notifies of requests being blocked by this middleware
+///
+/// When [denial_ids](crate::SecFetchLayer::denial_ids) is enabled, the [DenialId](crate::DenialId)
+/// of the request can be read from its extensions.
 pub trait SecFetchReporter {
     fn on_request_denied<B>(&self, request: &http::Request<B>);
+
+    /// Called for every request before it is evaluated by the policy, whatever the verdict,
+    /// e.g. to measure the fraction of requests arriving without Fetch Metadata headers
+    fn on_request_evaluated<B>(&self, request: &http::Request<B>) {
+        let _ = request;
+    }
+
+    /// Called when a request bypasses the evaluation policy with a valid exemption token
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used<B>(&self, request: &http::Request<B>, exemption: &crate::Exemption) {
+        let _ = (request, exemption);
+    }
+
+    /// Reports a denied request owned by the middleware, so that [BoxReporter] can erase its body type without copying its head
+    ///
+    /// Sealed, so that only [BoxReporter] and [FnReporter] can borrow the request mutably.
+    #[doc(hidden)]
+    fn on_request_denied_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) {
+        self.on_request_denied(request);
+    }
+
+    /// Reports an evaluated request owned by the middleware, like [on_request_denied_mut](SecFetchReporter::on_request_denied_mut)
+    #[doc(hidden)]
+    fn on_request_evaluated_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) {
+        self.on_request_evaluated(request);
+    }
+
+    /// Reports an exemption used by a request owned by the middleware, like [on_request_denied_mut](SecFetchReporter::on_request_denied_mut)
+    #[cfg(feature = "exemptions")]
+    #[doc(hidden)]
+    fn on_exemption_used_mut<B>(
+        &self,
+        request: &mut http::Request<B>,
+        exemption: &crate::Exemption,
+        _: Sealed,
+    ) {
+        self.on_exemption_used(request, exemption);
+    }
 }
 
 impl<T, R> SecFetchReporter for T
@@ -13,6 +56,187 @@ where
     fn on_request_denied<B>(&self, request: &http::Request<B>) {
         self.deref().on_request_denied(request);
     }
+
+    fn on_request_evaluated<B>(&self, request: &http::Request<B>) {
+        self.deref().on_request_evaluated(request);
+    }
+
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used<B>(&self, request: &http::Request<B>, exemption: &crate::Exemption) {
+        self.deref().on_exemption_used(request, exemption);
+    }
+
+    fn on_request_denied_mut<B>(&self, request: &mut http::Request<B>, sealed: Sealed) {
+        self.deref().on_request_denied_mut(request, sealed);
+    }
+
+    fn on_request_evaluated_mut<B>(&self, request: &mut http::Request<B>, sealed: Sealed) {
+        self.deref().on_request_evaluated_mut(request, sealed);
+    }
+
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used_mut<B>(
+        &self,
+        request: &mut http::Request<B>,
+        exemption: &crate::Exemption,
+        sealed: Sealed,
+    ) {
+        self.deref()
+            .on_exemption_used_mut(request, exemption, sealed);
+    }
+}
+
+/// A type-erased [SecFetchReporter], so that reporters can be chosen at runtime
+///
+/// [SecFetchReporter::on_request_denied] is generic over the request body, so the trait cannot be used as `dyn SecFetchReporter`.
+/// The middleware lends the head of the request to the inner reporter without copying it,
+/// while calling the [SecFetchReporter] methods directly copies the head to erase its body type.
+///
+/// ```
+/// use tower_sec_fetch::{BoxReporter, SecFetchLayer, SecFetchReporter};
+///
+/// struct StderrReporter;
+///
+/// impl SecFetchReporter for StderrReporter {
+///     fn on_request_denied<B>(&self, request: &http::Request<B>) {
+///         eprintln!("request was denied: {} {}", request.method(), request.uri());
+///     }
+/// }
+///
+/// struct StdoutReporter;
+///
+/// impl SecFetchReporter for StdoutReporter {
+///     fn on_request_denied<B>(&self, request: &http::Request<B>) {
+///         println!("request was denied: {} {}", request.method(), request.uri());
+///     }
+/// }
+///
+/// let use_stderr = true;
+/// let reporter = if use_stderr {
+///     BoxReporter::new(StderrReporter)
+/// } else {
+///     BoxReporter::new(StdoutReporter)
+/// };
+///
+/// SecFetchLayer::default().with_reporter(reporter);
+/// ```
+pub struct BoxReporter(Box<dyn ErasedReporter + Send + Sync>);
+
+impl BoxReporter {
+    pub fn new<R>(reporter: R) -> Self
+    where
+        R: SecFetchReporter + Send + Sync + 'static,
+    {
+        Self(Box::new(reporter))
+    }
+}
+
+impl SecFetchReporter for BoxReporter {
+    fn on_request_denied<B>(&self, request: &http::Request<B>) {
+        self.0
+            .on_request_denied_erased(&mut http::Request::from_parts(rule::parts(request), ()));
+    }
+
+    fn on_request_evaluated<B>(&self, request: &http::Request<B>) {
+        self.0
+            .on_request_evaluated_erased(&mut http::Request::from_parts(rule::parts(request), ()));
+    }
+
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used<B>(&self, request: &http::Request<B>, exemption: &crate::Exemption) {
+        self.0.on_exemption_used_erased(
+            &mut http::Request::from_parts(rule::parts(request), ()),
+            exemption,
+        );
+    }
+
+    fn on_request_denied_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) {
+        rule::with_head(request, |head| self.0.on_request_denied_erased(head));
+    }
+
+    fn on_request_evaluated_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) {
+        rule::with_head(request, |head| self.0.on_request_evaluated_erased(head));
+    }
+
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used_mut<B>(
+        &self,
+        request: &mut http::Request<B>,
+        exemption: &crate::Exemption,
+        _: Sealed,
+    ) {
+        rule::with_head(request, |head| {
+            self.0.on_exemption_used_erased(head, exemption);
+        });
+    }
+}
+
+trait ErasedReporter {
+    fn on_request_denied_erased(&self, request: &mut http::Request<()>);
+
+    fn on_request_evaluated_erased(&self, request: &mut http::Request<()>);
+
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used_erased(
+        &self,
+        request: &mut http::Request<()>,
+        exemption: &crate::Exemption,
+    );
+}
+
+impl<R: SecFetchReporter> ErasedReporter for R {
+    fn on_request_denied_erased(&self, request: &mut http::Request<()>) {
+        self.on_request_denied_mut(request, Sealed);
+    }
+
+    fn on_request_evaluated_erased(&self, request: &mut http::Request<()>) {
+        self.on_request_evaluated_mut(request, Sealed);
+    }
+
+    #[cfg(feature = "exemptions")]
+    fn on_exemption_used_erased(
+        &self,
+        request: &mut http::Request<()>,
+        exemption: &crate::Exemption,
+    ) {
+        self.on_exemption_used_mut(request, exemption, Sealed);
+    }
+}
+
+/// A [SecFetchReporter] calling a closure with the head of every denied request
+///
+/// Closures cannot be generic over the request body, so the body type is erased like in [BoxReporter].
+///
+/// ```
+/// use tower_sec_fetch::{FnReporter, SecFetchLayer};
+///
+/// SecFetchLayer::default().with_reporter(FnReporter::new(|request| {
+///     eprintln!("request was denied: {} {}", request.method(), request.uri());
+/// }));
+/// ```
+#[derive(Clone)]
+pub struct FnReporter<F>(F);
+
+impl<F> FnReporter<F>
+where
+    F: Fn(&http::Request<()>),
+{
+    pub fn new(report: F) -> Self {
+        Self(report)
+    }
+}
+
+impl<F> SecFetchReporter for FnReporter<F>
+where
+    F: Fn(&http::Request<()>),
+{
+    fn on_request_denied<B>(&self, request: &http::Request<B>) {
+        (self.0)(&http::Request::from_parts(rule::parts(request), ()));
+    }
+
+    fn on_request_denied_mut<B>(&self, request: &mut http::Request<B>, _: Sealed) {
+        rule::with_head(request, |head| (self.0)(head));
+    }
 }
 
 #[doc(hidden)]
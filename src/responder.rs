@@ -0,0 +1,255 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use http::{
+    HeaderMap, HeaderValue, Method, StatusCode, Uri,
+    header::{CONTENT_TYPE, LOCATION},
+};
+
+use crate::{DenialId, DenialReason, header};
+
+/// Builds the response returned to the client when a request is denied
+pub trait SecFetchResponder<B> {
+    /// Builds the response for the denied request
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B>;
+}
+
+/// Information about a request that has been denied
+pub struct DenialContext<'a> {
+    method: &'a Method,
+    uri: &'a Uri,
+    headers: &'a HeaderMap,
+    reason: DenialReason,
+    status: StatusCode,
+    id: Option<DenialId>,
+}
+
+impl<'a> DenialContext<'a> {
+    pub(crate) fn new<B>(
+        request: &'a http::Request<B>,
+        reason: DenialReason,
+        status: StatusCode,
+    ) -> Self {
+        Self {
+            method: request.method(),
+            uri: request.uri(),
+            headers: request.headers(),
+            reason,
+            status,
+            id: request.extensions().get().copied(),
+        }
+    }
+
+    /// The method of the denied request
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    /// The URI of the denied request
+    pub fn uri(&self) -> &Uri {
+        self.uri
+    }
+
+    /// The headers of the denied request
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+
+    /// Why the request was denied
+    pub fn reason(&self) -> DenialReason {
+        self.reason
+    }
+
+    /// The unique id of the denial, see [denial_ids](crate::SecFetchLayer::denial_ids)
+    pub fn id(&self) -> Option<DenialId> {
+        self.id
+    }
+
+    /// The status code configured for the denial reason, see [status_for](crate::SecFetchLayer::status_for)
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl<F, B> SecFetchResponder<B> for F
+where
+    F: Fn(&DenialContext<'_>) -> http::Response<B>,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B> {
+        self(denial)
+    }
+}
+
+/// Responds with an empty `403 Forbidden`, or the status configured for the denial reason
+#[doc(hidden)]
+pub struct ForbiddenResponder;
+
+impl<B> SecFetchResponder<B> for ForbiddenResponder
+where
+    B: Default,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B> {
+        http::Response::builder()
+            .status(denial.status())
+            .body(B::default())
+            .expect("valid response")
+    }
+}
+
+/// Builds the body of denial responses, see [deny_body](crate::SecFetchLayer::deny_body)
+///
+/// It is implemented by closures taking a [DenialContext], and can be implemented for a type
+/// building a body of each type used by the services it wraps.
+pub trait MakeDenyBody<B> {
+    /// Builds the body of the response for the denied request
+    fn make_body(&self, denial: &DenialContext<'_>) -> B;
+}
+
+impl<F, B> MakeDenyBody<B> for F
+where
+    F: Fn(&DenialContext<'_>) -> B,
+{
+    fn make_body(&self, denial: &DenialContext<'_>) -> B {
+        self(denial)
+    }
+}
+
+/// Responds with the status configured for the denial reason and a body built by a [MakeDenyBody]
+pub struct DenyBodyResponder<M>(M);
+
+impl<M> DenyBodyResponder<M> {
+    pub fn new(make_body: M) -> Self {
+        Self(make_body)
+    }
+}
+
+impl<M, B> SecFetchResponder<B> for DenyBodyResponder<M>
+where
+    M: MakeDenyBody<B>,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B> {
+        http::Response::builder()
+            .status(denial.status())
+            .body(self.0.make_body(denial))
+            .expect("valid response")
+    }
+}
+
+/// Responds with a `403 Forbidden` and an [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457) `application/problem+json` body
+/// describing why the request was denied
+///
+/// ```json
+/// {
+///   "type": "about:blank",
+///   "title": "Forbidden",
+///   "status": 403,
+///   "detail": "cross-site requests are not allowed for this resource",
+///   "reason": "cross-site"
+/// }
+/// ```
+pub struct ProblemJsonResponder;
+
+impl<B> SecFetchResponder<B> for ProblemJsonResponder
+where
+    B: From<String>,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B> {
+        http::Response::builder()
+            .status(denial.status())
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/problem+json"),
+            )
+            .body(B::from(problem_json(denial.status(), denial.reason())))
+            .expect("valid response")
+    }
+}
+
+pub(crate) fn problem_json(status: StatusCode, reason: DenialReason) -> String {
+    format!(
+        r#"{{"type":"about:blank","title":"{title}","status":{status},"detail":"{reason}","reason":"{id}"}}"#,
+        title = status.canonical_reason().unwrap_or_default(),
+        status = status.as_u16(),
+        id = reason.as_str(),
+    )
+}
+
+/// Converts the body of the responses built by another [SecFetchResponder], see [map_denied_body](crate::SecFetchLayer::map_denied_body)
+///
+/// It lets the middleware wrap services whose response body cannot be built by the responders, e.g. bodies
+/// without a [Default] implementation, by converting the body the responder builds into the one of the service.
+pub struct MapBodyResponder<D, F, B> {
+    responder: Arc<D>,
+    map: F,
+    _body: PhantomData<fn() -> B>,
+}
+
+impl<D, F, B> MapBodyResponder<D, F, B> {
+    /// Converts the body of the responses built by `responder` with `map`
+    pub fn new(responder: D, map: F) -> Self {
+        Self::shared(Arc::new(responder), map)
+    }
+
+    pub(crate) fn shared(responder: Arc<D>, map: F) -> Self {
+        Self {
+            responder,
+            map,
+            _body: PhantomData,
+        }
+    }
+}
+
+impl<D, F, B, MappedB> SecFetchResponder<MappedB> for MapBodyResponder<D, F, B>
+where
+    D: SecFetchResponder<B>,
+    F: Fn(B) -> MappedB,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<MappedB> {
+        self.responder.respond(denial).map(&self.map)
+    }
+}
+
+/// Redirects denied navigation requests (`sec-fetch-mode: navigate`) to a configurable page,
+/// so that users land somewhere helpful instead of an empty error
+///
+/// Other requests, such as subresources or XHR, receive an empty `403 Forbidden`, or the status configured for the denial reason.
+pub struct RedirectResponder {
+    location: HeaderValue,
+    status: StatusCode,
+}
+
+impl RedirectResponder {
+    /// Redirects to `location` with a `303 See Other`
+    pub fn new(location: HeaderValue) -> Self {
+        Self {
+            location,
+            status: StatusCode::SEE_OTHER,
+        }
+    }
+
+    /// Redirects with a `302 Found` instead of a `303 See Other`
+    pub fn found(mut self) -> Self {
+        self.status = StatusCode::FOUND;
+        self
+    }
+}
+
+impl<B> SecFetchResponder<B> for RedirectResponder
+where
+    B: Default,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B> {
+        if denial
+            .headers()
+            .get(header::SEC_FETCH_MODE)
+            .is_none_or(|mode| mode != header::NAVIGATE)
+        {
+            return ForbiddenResponder.respond(denial);
+        }
+
+        http::Response::builder()
+            .status(self.status)
+            .header(LOCATION, self.location.clone())
+            .body(B::default())
+            .expect("valid response")
+    }
+}
@@ -0,0 +1,34 @@
+use http::{HeaderMap, HeaderValue};
+
+use crate::{DenialReason, header};
+
+/// Headers added by the [SecFetch](crate::SecFetch) middleware to its responses
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ResponseHeaders {
+    /// Reason of a denial let through in report-only mode, see [would_block_header](crate::SecFetchLayer::would_block_header)
+    pub(crate) would_block: Option<DenialReason>,
+    /// Whether the verdict depended on the Fetch Metadata headers, see [vary](crate::SecFetchLayer::vary)
+    pub(crate) vary: bool,
+}
+
+impl ResponseHeaders {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.would_block.is_none() && !self.vary
+    }
+
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(reason) = self.would_block {
+            headers.insert(
+                header::X_SEC_FETCH_WOULD_BLOCK,
+                HeaderValue::from_static(reason.as_str()),
+            );
+        }
+
+        if self.vary {
+            headers.append(
+                http::header::VARY,
+                HeaderValue::from_static("sec-fetch-site, sec-fetch-mode, sec-fetch-dest"),
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::{error::Error, fmt};
+
+use crate::{AuthorizationDecision, SecFetchAuthorizer};
+
+/// A [SecFetchAuthorizer] that allows requests matching a set of routes, with lookups
+/// proportional to the length of the path rather than to the number of routes
+///
+/// Routes use the [matchit] syntax: `{name}` matches a single path segment,
+/// and a trailing `{*name}` matches the rest of the path.
+///
+/// ```
+/// use tower_sec_fetch::{RouterAuthorizer, SecFetchLayer};
+///
+/// let authorizer =
+///     RouterAuthorizer::new(["/users/{id}/avatar", "/webhooks/{*provider}"]).unwrap();
+///
+/// SecFetchLayer::default().with_authorizer(authorizer);
+/// ```
+#[derive(Clone)]
+pub struct RouterAuthorizer {
+    router: matchit::Router<()>,
+    len: usize,
+}
+
+impl RouterAuthorizer {
+    pub fn new<R>(routes: impl IntoIterator<Item = R>) -> Result<Self, InvalidRoute>
+    where
+        R: Into<String>,
+    {
+        let mut router = matchit::Router::new();
+        let mut len = 0;
+
+        for route in routes {
+            let route = route.into();
+            router
+                .insert(route.as_str(), ())
+                .map_err(|error| InvalidRoute(route, error.to_string()))?;
+            len += 1;
+        }
+
+        Ok(Self { router, len })
+    }
+}
+
+impl SecFetchAuthorizer for RouterAuthorizer {
+    fn authorize<B>(&self, request: &http::Request<B>) -> AuthorizationDecision {
+        if self.router.at(request.uri().path()).is_ok() {
+            return AuthorizationDecision::Allowed;
+        }
+
+        AuthorizationDecision::Continue
+    }
+
+    fn exemptions(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// The error returned by [RouterAuthorizer::new] when a route is malformed or conflicts with another one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidRoute(String, String);
+
+impl fmt::Display for InvalidRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid route {:?}: {}", self.0, self.1)
+    }
+}
+
+impl Error for InvalidRoute {}
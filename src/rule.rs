@@ -0,0 +1,160 @@
+use std::{mem, sync::Arc};
+
+use http::{Extensions, HeaderMap, HeaderValue, Method, Uri, Version, request::Parts};
+
+use crate::{Dest, Mode, Site, header};
+
+/// The parsed Fetch Metadata headers of a request
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FetchMetadata {
+    /// The `sec-fetch-site` header, unknown values are treated as [CrossSite](Site::CrossSite)
+    pub site: Site,
+    /// The `sec-fetch-mode` header, or `None` if unknown
+    pub mode: Option<Mode>,
+    /// The `sec-fetch-dest` header, or `None` if unknown
+    pub dest: Option<Dest>,
+    /// Whether the `sec-fetch-user` header is `?1`
+    pub user: bool,
+    /// Whether the `upgrade-insecure-requests` header is `1`
+    pub upgrade_insecure_requests: bool,
+}
+
+impl FetchMetadata {
+    pub(crate) fn new(
+        site: &HeaderValue,
+        mode: &HeaderValue,
+        dest: &HeaderValue,
+        headers: &http::HeaderMap,
+    ) -> Self {
+        Self {
+            site: Site::from_header(site),
+            mode: Mode::from_header(mode),
+            dest: Dest::from_header(dest),
+            user: headers
+                .get(header::SEC_FETCH_USER)
+                .is_some_and(|user| user == header::USER_ACTIVATED),
+            upgrade_insecure_requests: headers
+                .get(header::UPGRADE_INSECURE_REQUESTS)
+                .is_some_and(|upgrade| upgrade == "1"),
+        }
+    }
+}
+
+/// The decision made by a [custom](crate::PolicyBuilder::custom) rule
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum RuleDecision {
+    /// The request is allowed
+    /// Short-circuits the evaluation policy
+    Allow,
+    /// The request is denied with [DenialReason::Custom](crate::DenialReason::Custom)
+    /// Short-circuits the evaluation policy
+    Deny,
+    /// The request is neither denied nor allowed, deferring
+    /// to the next rules of the evaluation policy
+    Continue,
+}
+
+/// The head of the request evaluated by a [custom](crate::PolicyBuilder::custom) rule, borrowed from the request
+#[derive(Copy, Clone, Debug)]
+pub struct RequestHead<'a> {
+    method: &'a Method,
+    tunneled_method: Option<&'a Method>,
+    uri: &'a Uri,
+    version: Version,
+    headers: &'a HeaderMap,
+    extensions: &'a Extensions,
+}
+
+impl<'a> RequestHead<'a> {
+    pub(crate) fn new<B>(request: &'a http::Request<B>) -> Self {
+        Self {
+            method: request.method(),
+            tunneled_method: None,
+            uri: request.uri(),
+            version: request.version(),
+            headers: request.headers(),
+            extensions: request.extensions(),
+        }
+    }
+
+    pub(crate) fn with_tunneled_method(mut self, method: Option<&'a Method>) -> Self {
+        self.tunneled_method = method;
+        self
+    }
+
+    /// The method of the request
+    pub fn method(&self) -> &'a Method {
+        self.method
+    }
+
+    /// The method tunneled by a `POST` request, when [honor_method_override](crate::PolicyBuilder::honor_method_override) is set
+    pub fn tunneled_method(&self) -> Option<&'a Method> {
+        self.tunneled_method
+    }
+
+    /// The URI of the request
+    pub fn uri(&self) -> &'a Uri {
+        self.uri
+    }
+
+    /// The HTTP version of the request
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The headers of the request
+    pub fn headers(&self) -> &'a HeaderMap {
+        self.headers
+    }
+
+    /// The extensions of the request
+    pub fn extensions(&self) -> &'a Extensions {
+        self.extensions
+    }
+}
+
+pub(crate) type Rule = Arc<dyn Fn(&FetchMetadata, &RequestHead<'_>) -> RuleDecision + Send + Sync>;
+
+/// Argument of the hidden trait methods lending a request owned by the middleware to
+/// [BoxAuthorizer](crate::BoxAuthorizer) and [BoxReporter](crate::BoxReporter)
+///
+/// It cannot be named outside of this crate, so those methods can neither be overridden to modify the request,
+/// nor called.
+pub struct Sealed;
+
+/// Calls `f` with the head of the request, moved into a request without a body, to erase the body type
+/// without copying the head
+pub(crate) fn with_head<B, T>(
+    request: &mut http::Request<B>,
+    f: impl FnOnce(&mut http::Request<()>) -> T,
+) -> T {
+    let mut head = http::Request::new(());
+    swap_head(request, &mut head);
+    let result = f(&mut head);
+    swap_head(request, &mut head);
+    result
+}
+
+fn swap_head<B>(request: &mut http::Request<B>, head: &mut http::Request<()>) {
+    mem::swap(request.method_mut(), head.method_mut());
+    mem::swap(request.uri_mut(), head.uri_mut());
+    mem::swap(request.version_mut(), head.version_mut());
+    mem::swap(request.headers_mut(), head.headers_mut());
+    mem::swap(request.extensions_mut(), head.extensions_mut());
+}
+
+/// Copies the head of the request, to erase the body type of a request that cannot be lent with [with_head]
+pub(crate) fn parts<B>(request: &http::Request<B>) -> Parts {
+    let (mut parts, ()) = http::Request::new(()).into_parts();
+    parts.method = request.method().clone();
+    parts.uri = request.uri().clone();
+    parts.version = request.version();
+    parts.headers = request.headers().clone();
+    parts.extensions = request.extensions().clone();
+    parts
+}
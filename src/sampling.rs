@@ -0,0 +1,37 @@
+/// Field recorded on the current [tracing] span of denied requests
+///
+/// Tail-sampling trace backends can use it to always retain traces of denied requests,
+/// even under aggressive sampling. The default is `sampling.priority = 2`,
+/// which Datadog and OpenTelemetry-based samplers interpret as "user keep".
+///
+/// [tracing] only records fields declared when the span is created,
+/// so the span wrapping the middleware must declare the field, for example with [tracing::field::Empty].
+///
+/// ```
+/// use tower_sec_fetch::{SamplingPriority, SecFetchLayer};
+///
+/// let span = tracing::info_span!("request", sampling.priority = tracing::field::Empty);
+///
+/// SecFetchLayer::default().sampling_priority(SamplingPriority::default());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SamplingPriority {
+    field: &'static str,
+    value: i64,
+}
+
+impl SamplingPriority {
+    pub fn new(field: &'static str, value: i64) -> Self {
+        Self { field, value }
+    }
+
+    pub(crate) fn record(self) {
+        tracing::Span::current().record(self.field, self.value);
+    }
+}
+
+impl Default for SamplingPriority {
+    fn default() -> Self {
+        Self::new("sampling.priority", 2)
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    error::Error,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{PolicyBuilder, policy::Policy};
+
+/// Switches the evaluation policy during recurring time windows
+///
+/// When attached to a [SecFetchLayer](crate::SecFetchLayer) with [with_schedule](crate::SecFetchLayer::with_schedule),
+/// the policy of the first active [TimeWindow] is used instead of the layer's own policy.
+/// Outside of any window, the layer's policy applies.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tower_sec_fetch::{Schedule, SecFetchLayer, TimeWindow};
+///
+/// // stricter settings during the Saturday maintenance window, from 02:00 to 04:00 UTC
+/// let maintenance = TimeWindow::new("0 2 * * 6", Duration::from_secs(2 * 60 * 60)).unwrap();
+///
+/// let schedule = Schedule::new().window(maintenance, |policy| {
+///     policy.reject_missing_metadata();
+/// });
+///
+/// SecFetchLayer::default().with_schedule(schedule);
+/// ```
+#[derive(Default)]
+pub struct Schedule {
+    windows: Vec<(TimeWindow, Policy)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies the policy configured by `make_policy` while `window` is active
+    pub fn window<F>(mut self, window: TimeWindow, make_policy: F) -> Self
+    where
+        F: FnOnce(&mut PolicyBuilder),
+    {
+        let mut builder = PolicyBuilder::new();
+        make_policy(&mut builder);
+        self.windows.push((window, builder.build()));
+        self
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub(crate) fn policy_at(&self, time: SystemTime) -> Option<&Policy> {
+        self.windows
+            .iter()
+            .find(|(window, _)| window.contains(time))
+            .map(|(_, policy)| policy)
+    }
+}
+
+/// A recurring time window, starting at every match of a cron expression and lasting for a fixed duration
+///
+/// The expression uses the classic five fields `minute hour day-of-month month day-of-week`,
+/// evaluated in UTC. Each field accepts `*`, single values, ranges (`1-5`), lists (`1,3,5`), and steps (`*/15`, `0-30/10`).
+/// Day of week goes from `0` (Sunday) to `7` (Sunday again).
+#[derive(Clone, Debug)]
+pub struct TimeWindow {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    duration: Duration,
+}
+
+impl TimeWindow {
+    pub fn new(cron: &str, duration: Duration) -> Result<Self, InvalidCron> {
+        let fields: Vec<&str> = cron.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(InvalidCron(cron.to_string()));
+        };
+
+        let parse = |field, min, max| {
+            parse_field(field, min, max).ok_or_else(|| InvalidCron(cron.to_string()))
+        };
+
+        let mut days_of_week = parse(days_of_week, 0, 7)?;
+        // 7 is an alias for Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            minutes: parse(minutes, 0, 59)?,
+            hours: parse(hours, 0, 23)?,
+            days_of_month: parse(days_of_month, 1, 31)?,
+            months: parse(months, 1, 12)?,
+            days_of_week,
+            duration,
+        })
+    }
+
+    /// Whether the window is active at the given time
+    pub fn contains(&self, time: SystemTime) -> bool {
+        let Ok(since_epoch) = time.duration_since(UNIX_EPOCH) else {
+            return false;
+        };
+
+        let now = since_epoch.as_secs() / 60;
+        let span = self.duration.as_secs().div_ceil(60);
+        if span == 0 {
+            return false;
+        }
+
+        // only the latest start can be the one of an active window,
+        // so look for it on each day the window could have started on, most recent first
+        let today = now / MINUTES_PER_DAY;
+        let first_day = now.saturating_sub(span - 1) / MINUTES_PER_DAY;
+        (first_day..=today)
+            .rev()
+            .filter(|day| self.matches_day(*day))
+            .find_map(|day| {
+                let until = if day == today {
+                    now % MINUTES_PER_DAY
+                } else {
+                    MINUTES_PER_DAY - 1
+                };
+                self.latest_time_of_day(until)
+                    .map(|minute| day * MINUTES_PER_DAY + minute)
+            })
+            .is_some_and(|start| now - start < span)
+    }
+
+    fn matches_day(&self, days: u64) -> bool {
+        let (month, day) = civil_from_days(days);
+        let weekday = (days + 4) % 7;
+
+        let day_of_month = bit(self.days_of_month, day);
+        let day_of_week = bit(self.days_of_week, weekday);
+        // like cron, when both day fields are restricted, matching either one is enough
+        let day_matches =
+            if self.days_of_month == ALL_DAYS_OF_MONTH || self.days_of_week == ALL_DAYS_OF_WEEK {
+                day_of_month && day_of_week
+            } else {
+                day_of_month || day_of_week
+            };
+
+        day_matches && bit(self.months, month)
+    }
+
+    /// The latest minute of the day matching the expression, no later than `until`
+    fn latest_time_of_day(&self, until: u64) -> Option<u64> {
+        let (hour, minute) = (until / 60, until % 60);
+
+        bit(self.hours, hour)
+            .then(|| highest(self.minutes, minute))
+            .flatten()
+            .map(|minute| hour * 60 + minute)
+            .or_else(|| {
+                let hour = highest(self.hours, hour.checked_sub(1)?)?;
+                Some(hour * 60 + highest(self.minutes, 59)?)
+            })
+    }
+}
+
+/// The cron expression of a [TimeWindow] is not valid
+#[derive(Debug)]
+pub struct InvalidCron(String);
+
+impl fmt::Display for InvalidCron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cron expression: {:?}", self.0)
+    }
+}
+
+impl Error for InvalidCron {}
+
+const ALL_DAYS_OF_MONTH: u64 = range(1, 31);
+const ALL_DAYS_OF_WEEK: u64 = range(0, 7);
+
+const fn range(min: u64, max: u64) -> u64 {
+    (u64::MAX >> (63 - max)) & (u64::MAX << min)
+}
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+fn bit(set: u64, value: u64) -> bool {
+    set & (1 << value) != 0
+}
+
+/// The highest value of the set no greater than `max`
+fn highest(set: u64, max: u64) -> Option<u64> {
+    let set = set & range(0, max);
+    (set != 0).then(|| 63 - u64::from(set.leading_zeros()))
+}
+
+fn parse_field(field: &str, min: u64, max: u64) -> Option<u64> {
+    let mut set = 0;
+
+    for part in field.split(',') {
+        let (values, step) = match part.split_once('/') {
+            Some((values, step)) => (values, step.parse().ok().filter(|step| *step > 0)?),
+            None => (part, 1),
+        };
+
+        let (start, end) = match values.split_once('-') {
+            _ if values == "*" => (min, max),
+            Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+            None => {
+                let value = values.parse().ok()?;
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return None;
+        }
+
+        set |= (start..=end)
+            .step_by(step)
+            .fold(0, |set, value| set | (1 << value));
+    }
+
+    Some(set)
+}
+
+// Converts days since the Unix epoch to a (month, day) pair
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64) {
+    let z = days + 719_468;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    (month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    // Saturday 2025-06-14 at the given time, UTC
+    fn saturday(hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_749_859_200 + hour * 3600 + minute * 60)
+    }
+
+    #[test]
+    fn it_is_active_during_the_window() {
+        let window = TimeWindow::new("0 2 * * 6", Duration::from_secs(2 * 3600)).unwrap();
+
+        check!(!window.contains(saturday(1, 59)));
+        check!(window.contains(saturday(2, 0)));
+        check!(window.contains(saturday(3, 59)));
+        check!(!window.contains(saturday(4, 0)));
+        check!(!window.contains(saturday(2, 0) + Duration::from_secs(24 * 3600)));
+    }
+
+    #[test]
+    fn it_matches_either_day_field_when_both_are_restricted() {
+        let window = TimeWindow::new("0 12 1 * 6", Duration::from_secs(60)).unwrap();
+
+        check!(window.contains(saturday(12, 0)));
+    }
+
+    #[test]
+    fn it_is_active_across_days() {
+        let window = TimeWindow::new("30 22 * * 5", Duration::from_secs(8 * 3600)).unwrap();
+
+        check!(window.contains(saturday(0, 0)));
+        check!(window.contains(saturday(6, 29)));
+        check!(!window.contains(saturday(6, 30)));
+    }
+
+    #[test]
+    fn it_is_active_for_the_whole_week_long_window() {
+        let window = TimeWindow::new("0 0 * * 1", Duration::from_secs(7 * 24 * 3600)).unwrap();
+
+        check!(window.contains(saturday(12, 0)));
+        check!(window.contains(saturday(23, 59) + Duration::from_secs(24 * 3600)));
+    }
+
+    #[test]
+    fn it_uses_the_latest_start_within_the_hour() {
+        let window = TimeWindow::new("*/15 9 * * *", Duration::from_secs(5 * 60)).unwrap();
+
+        check!(window.contains(saturday(9, 47)));
+        check!(!window.contains(saturday(9, 50)));
+        check!(!window.contains(saturday(10, 2)));
+    }
+
+    #[test]
+    fn it_rejects_invalid_expressions() {
+        check!(TimeWindow::new("* * *", Duration::ZERO).is_err());
+        check!(TimeWindow::new("60 * * * *", Duration::ZERO).is_err());
+        check!(TimeWindow::new("*/0 * * * *", Duration::ZERO).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{fmt, sync::Arc};
+
+use http::Method;
+
+use crate::{DenialReason, policy::path_matches};
+
+/// How serious a denial or an exemption hit is, so that alerting can treat them differently
+///
+/// When [severities](crate::SecFetchLayer::severities) are configured, the severity is inserted in the request
+/// extensions before calling the [SecFetchReporter](crate::SecFetchReporter), and logged at the matching
+/// [tracing] level when the `tracing` feature is enabled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warn,
+    Critical,
+}
+
+impl Severity {
+    /// Short machine-readable identifier of the severity
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Assigns a [Severity] to denied requests and exemption hits
+///
+/// Rules are evaluated in order, and the first one matching the denied request decides its severity.
+/// Denials matching no rule are [Warn](Severity::Warn), and exemption hits are [Info](Severity::Info) by default.
+///
+/// ```
+/// use http::Method;
+/// use tower_sec_fetch::{DenialReason, SecFetchLayer, Severities, Severity, SeverityRule};
+///
+/// let severities = Severities::default()
+///     .rule(
+///         SeverityRule::new(Severity::Info)
+///             .reason(DenialReason::MissingMetadata)
+///             .method(Method::GET),
+///     )
+///     .rule(
+///         SeverityRule::new(Severity::Critical)
+///             .method(Method::POST)
+///             .path("/transfer"),
+///     );
+///
+/// SecFetchLayer::default().severities(severities);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Severities {
+    rules: Vec<SeverityRule>,
+    denied: Severity,
+    exemptions: Severity,
+}
+
+impl Default for Severities {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            denied: Severity::Warn,
+            exemptions: Severity::Info,
+        }
+    }
+}
+
+impl Severities {
+    /// Adds a rule, evaluated after the previously added ones
+    pub fn rule(mut self, rule: SeverityRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The severity of denials matching no rule, defaults to [Severity::Warn]
+    pub fn denied(mut self, severity: Severity) -> Self {
+        self.denied = severity;
+        self
+    }
+
+    /// The severity of exemption hits, defaults to [Severity::Info]
+    pub fn exemptions(mut self, severity: Severity) -> Self {
+        self.exemptions = severity;
+        self
+    }
+
+    pub(crate) fn classify<B>(&self, request: &http::Request<B>, reason: DenialReason) -> Severity {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(request, reason))
+            .map_or(self.denied, |rule| rule.severity)
+    }
+
+    #[cfg(feature = "exemptions")]
+    pub(crate) fn exemption(&self) -> Severity {
+        self.exemptions
+    }
+}
+
+/// A rule of [Severities], matching denied requests when all of its conditions match
+#[derive(Clone, Debug)]
+pub struct SeverityRule {
+    severity: Severity,
+    reason: Option<DenialReason>,
+    method: Option<Method>,
+    path: Option<Arc<str>>,
+}
+
+impl SeverityRule {
+    /// A rule matching every denied request
+    pub fn new(severity: Severity) -> Self {
+        Self {
+            severity,
+            reason: None,
+            method: None,
+            path: None,
+        }
+    }
+
+    /// Only match requests denied for `reason`
+    pub fn reason(mut self, reason: DenialReason) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+
+    /// Only match requests using `method`
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Only match requests whose path matches `pattern`, where `*` matches a single path segment,
+    /// `{v}` matches an API version segment such as `v1`, and a trailing `**` matches any number of segments
+    pub fn path(mut self, pattern: impl Into<Arc<str>>) -> Self {
+        self.path = Some(pattern.into());
+        self
+    }
+
+    fn matches<B>(&self, request: &http::Request<B>, reason: DenialReason) -> bool {
+        self.reason.is_none_or(|expected| expected == reason)
+            && self
+                .method
+                .as_ref()
+                .is_none_or(|method| method == request.method())
+            && self
+                .path
+                .as_deref()
+                .is_none_or(|pattern| path_matches(pattern, request.uri().path()))
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    future::{self, Ready},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+use http::{StatusCode, header::CONTENT_TYPE};
+use serde::Serialize;
+use tower::Service;
+
+use crate::{SecFetchReporter, UserAgent, header, policy::path_matches};
+
+/// A [SecFetchReporter] that keeps count of denied requests, and of evaluated requests without Fetch Metadata headers
+///
+/// The fraction of requests missing Fetch Metadata headers, by path and [UserAgent], tells whether
+/// [reject_missing_metadata](crate::PolicyBuilder::reject_missing_metadata) can be enabled without breaking clients.
+///
+/// Paths and methods are chosen by clients, so their counters are bounded: requests are counted by the first
+/// matching [route](StatsCollector::routes) pattern when configured, and otherwise by their path or method until
+/// [max_keys](StatsCollector::max_keys) distinct values are tracked. Any other request is counted under [OTHER](StatsCollector::OTHER).
+///
+/// Cloning the collector shares the underlying counters, so the same instance can be
+/// passed to [with_reporter](crate::SecFetchLayer::with_reporter) and used to build a [StatsService].
+#[derive(Clone)]
+pub struct StatsCollector {
+    routes: Arc<[&'static str]>,
+    max_keys: usize,
+    counters: Arc<Counters>,
+}
+
+#[derive(Default)]
+struct Counters {
+    denied: AtomicU64,
+    evaluated: AtomicU64,
+    missing_metadata: AtomicU64,
+    breakdown: Mutex<Breakdown>,
+}
+
+#[derive(Clone, Default)]
+struct Breakdown {
+    by_method: BTreeMap<String, u64>,
+    by_path: BTreeMap<String, u64>,
+    by_severity: BTreeMap<String, u64>,
+    missing_metadata_by_path: BTreeMap<String, u64>,
+    missing_metadata_by_user_agent: BTreeMap<String, u64>,
+}
+
+/// Point-in-time copy of the counters of a [StatsCollector]
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct StatsSnapshot {
+    /// Total number of denied requests
+    pub denied: u64,
+    /// Number of denied requests by HTTP method
+    pub by_method: BTreeMap<String, u64>,
+    /// Number of denied requests by request path or route
+    pub by_path: BTreeMap<String, u64>,
+    /// Number of denied requests by [Severity](crate::Severity), if [severities](crate::SecFetchLayer::severities) are configured
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_severity: BTreeMap<String, u64>,
+    /// Total number of requests evaluated by the policy
+    pub evaluated: u64,
+    /// Number of evaluated requests without Fetch Metadata headers
+    pub missing_metadata: u64,
+    /// Number of evaluated requests without Fetch Metadata headers by request path or route
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub missing_metadata_by_path: BTreeMap<String, u64>,
+    /// Number of evaluated requests without Fetch Metadata headers by [UserAgent]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub missing_metadata_by_user_agent: BTreeMap<String, u64>,
+}
+
+impl StatsSnapshot {
+    /// Fraction of the evaluated requests without Fetch Metadata headers, between 0 and 1
+    pub fn missing_metadata_ratio(&self) -> f64 {
+        if self.evaluated == 0 {
+            return 0.0;
+        }
+
+        self.missing_metadata as f64 / self.evaluated as f64
+    }
+}
+
+impl Default for StatsCollector {
+    fn default() -> Self {
+        Self {
+            routes: Arc::new([]),
+            max_keys: 100,
+            counters: Arc::default(),
+        }
+    }
+}
+
+impl StatsCollector {
+    /// Key counting the requests that exceed [max_keys](StatsCollector::max_keys) or match no [route](StatsCollector::routes)
+    pub const OTHER: &str = "(other)";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count requests by the first matching route pattern instead of by path
+    ///
+    /// Patterns are matched like in [PathAuthorizer](crate::PathAuthorizer), where `*` matches a single path segment
+    /// and a trailing `**` matches any number of segments.
+    ///
+    /// Requests matching no pattern are counted under [OTHER](StatsCollector::OTHER).
+    pub fn routes(mut self, routes: impl Into<Arc<[&'static str]>>) -> Self {
+        self.routes = routes.into();
+        self
+    }
+
+    /// Maximum number of distinct paths and methods counted separately in each breakdown, defaults to 100
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = max_keys;
+        self
+    }
+
+    /// Returns a copy of the current counters
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let breakdown = self
+            .counters
+            .breakdown
+            .lock()
+            .expect("stats lock poisoned")
+            .clone();
+
+        StatsSnapshot {
+            denied: self.counters.denied.load(Ordering::Relaxed),
+            by_method: breakdown.by_method,
+            by_path: breakdown.by_path,
+            by_severity: breakdown.by_severity,
+            evaluated: self.counters.evaluated.load(Ordering::Relaxed),
+            missing_metadata: self.counters.missing_metadata.load(Ordering::Relaxed),
+            missing_metadata_by_path: breakdown.missing_metadata_by_path,
+            missing_metadata_by_user_agent: breakdown.missing_metadata_by_user_agent,
+        }
+    }
+
+    /// Returns a [Service] rendering the current counters as JSON
+    pub fn service(&self) -> StatsService {
+        StatsService(self.clone())
+    }
+
+    fn count_path(&self, counters: &mut BTreeMap<String, u64>, path: &str) {
+        if self.routes.is_empty() {
+            return self.count(counters, path);
+        }
+
+        let route = self
+            .routes
+            .iter()
+            .find(|route| path_matches(route, path))
+            .map_or(Self::OTHER, |route| route);
+        *counters.entry(route.to_string()).or_default() += 1;
+    }
+
+    fn count(&self, counters: &mut BTreeMap<String, u64>, key: &str) {
+        let tracked = counters.len() - usize::from(counters.contains_key(Self::OTHER));
+        let key = match counters.get_mut(key) {
+            Some(count) => {
+                *count += 1;
+                return;
+            }
+            None if tracked < self.max_keys => key,
+            None => Self::OTHER,
+        };
+
+        *counters.entry(key.to_string()).or_default() += 1;
+    }
+}
+
+impl SecFetchReporter for StatsCollector {
+    fn on_request_denied<B>(&self, request: &http::Request<B>) {
+        self.counters.denied.fetch_add(1, Ordering::Relaxed);
+
+        let mut breakdown = self.counters.breakdown.lock().expect("stats lock poisoned");
+        self.count(&mut breakdown.by_method, request.method().as_str());
+        self.count_path(&mut breakdown.by_path, request.uri().path());
+        if let Some(severity) = request.extensions().get::<crate::Severity>() {
+            *breakdown
+                .by_severity
+                .entry(severity.to_string())
+                .or_default() += 1;
+        }
+    }
+
+    fn on_request_evaluated<B>(&self, request: &http::Request<B>) {
+        self.counters.evaluated.fetch_add(1, Ordering::Relaxed);
+        if request.headers().contains_key(header::SEC_FETCH_SITE) {
+            return;
+        }
+
+        self.counters
+            .missing_metadata
+            .fetch_add(1, Ordering::Relaxed);
+
+        let user_agent = UserAgent::classify(request.headers().get(http::header::USER_AGENT));
+        let mut breakdown = self.counters.breakdown.lock().expect("stats lock poisoned");
+        self.count_path(
+            &mut breakdown.missing_metadata_by_path,
+            request.uri().path(),
+        );
+        *breakdown
+            .missing_metadata_by_user_agent
+            .entry(user_agent.as_str().to_string())
+            .or_default() += 1;
+    }
+}
+
+/// [Service] that renders a [StatsSnapshot] as JSON
+///
+/// It can be mounted on any path, e.g. with Axum:
+///
+/// ```
+/// # use tower_sec_fetch::{SecFetchLayer, StatsCollector};
+/// #
+/// let stats = StatsCollector::new();
+///
+/// let routes: axum::Router = axum::Router::new()
+///     .route_service("/_sec-fetch/stats", stats.service())
+///     .layer(SecFetchLayer::default().no_enforce().with_reporter(stats));
+/// ```
+#[derive(Clone)]
+pub struct StatsService(StatsCollector);
+
+impl<B> Service<http::Request<B>> for StatsService {
+    type Response = http::Response<String>;
+
+    type Error = Infallible;
+
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: http::Request<B>) -> Self::Future {
+        let body = serde_json::to_string(&self.0.snapshot()).expect("valid json");
+
+        future::ready(Ok(http::Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .expect("valid response")))
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use http::{HeaderValue, header::CONTENT_TYPE};
+
+use crate::{DenialContext, SecFetchResponder, header};
+
+/// A [SecFetchResponder] that renders an HTML page for denied navigation requests
+///
+/// The template can reference the following variables using the `{{ name }}` syntax:
+///
+/// - `reason`: a human-readable description of the [DenialReason](crate::DenialReason)
+/// - `reason_id`: the machine-readable identifier of the [DenialReason](crate::DenialReason)
+/// - `method`: the method of the denied request
+/// - `path`: the path of the denied request
+/// - `uri`: the full URI of the denied request
+///
+/// Values are HTML-escaped before being rendered. Unknown variables are rendered as empty strings.
+///
+/// Requests that are not navigations (`sec-fetch-mode: navigate`) receive an empty `403 Forbidden`,
+/// as there is no user to show the page to.
+///
+/// ```
+/// use tower_sec_fetch::{HtmlTemplateResponder, SecFetchLayer};
+///
+/// SecFetchLayer::default().on_denied(HtmlTemplateResponder::new(
+///     "<h1>Blocked</h1><p>{{ method }} {{ path }}: {{ reason }}</p>",
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct HtmlTemplateResponder(Arc<str>);
+
+impl HtmlTemplateResponder {
+    pub fn new(template: impl Into<Arc<str>>) -> Self {
+        Self(template.into())
+    }
+
+    fn render(&self, denial: &DenialContext<'_>) -> String {
+        let mut output = String::with_capacity(self.0.len());
+        let mut rest = &*self.0;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+
+            output.push_str(&rest[..start]);
+
+            let name = rest[start + 2..start + end].trim();
+            let value = match name {
+                "reason" => denial.reason().to_string(),
+                "reason_id" => denial.reason().as_str().to_string(),
+                "method" => denial.method().to_string(),
+                "path" => denial.uri().path().to_string(),
+                "uri" => denial.uri().to_string(),
+                _ => String::new(),
+            };
+            escape_into(&mut output, &value);
+
+            rest = &rest[start + end + 2..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+}
+
+impl<B> SecFetchResponder<B> for HtmlTemplateResponder
+where
+    B: From<String> + Default,
+{
+    fn respond(&self, denial: &DenialContext<'_>) -> http::Response<B> {
+        let response = http::Response::builder().status(denial.status());
+
+        if denial
+            .headers()
+            .get(header::SEC_FETCH_MODE)
+            .is_none_or(|mode| mode != header::NAVIGATE)
+        {
+            return response.body(B::default()).expect("valid response");
+        }
+
+        response
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("text/html; charset=utf-8"),
+            )
+            .body(B::from(self.render(denial)))
+            .expect("valid response")
+    }
+}
+
+fn escape_into(output: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#x27;"),
+            c => output.push(c),
+        }
+    }
+}
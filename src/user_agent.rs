@@ -0,0 +1,196 @@
+use http::HeaderValue;
+
+/// The kind of client sending a request, guessed from its `user-agent` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UserAgent {
+    /// A browser version sending Fetch Metadata headers, which omits them for non-secure origins
+    Browser,
+    /// A browser version released before Fetch Metadata headers were supported, or an unknown browser
+    LegacyBrowser,
+    /// A client that does not identify as a browser, such as a CLI, SDK, or server
+    NonBrowser,
+}
+
+impl UserAgent {
+    /// Short machine-readable identifier of the kind of client
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Browser => "browser",
+            Self::LegacyBrowser => "legacy-browser",
+            Self::NonBrowser => "non-browser",
+        }
+    }
+
+    /// Classifies the `user-agent` header of a request
+    ///
+    /// Browsers are recognized by the `Mozilla/` prefix, and considered legacy before Chrome 76,
+    /// Firefox 90, and Safari 16.4. Browsers of unknown engines are considered legacy.
+    pub fn classify(user_agent: Option<&HeaderValue>) -> Self {
+        let Some(user_agent) = user_agent.and_then(|value| value.to_str().ok()) else {
+            return Self::NonBrowser;
+        };
+
+        if !user_agent.starts_with("Mozilla/") {
+            return Self::NonBrowser;
+        }
+
+        let modern = if let Some(version) = product_version(user_agent, "Chrome/") {
+            version >= (76, 0)
+        } else if let Some(version) = product_version(user_agent, "Firefox/") {
+            version >= (90, 0)
+        } else if user_agent.contains("Safari/") {
+            product_version(user_agent, "Version/").is_some_and(|version| version >= (16, 4))
+        } else {
+            false
+        };
+
+        if modern {
+            Self::Browser
+        } else {
+            Self::LegacyBrowser
+        }
+    }
+}
+
+/// What to do with a request without Fetch Metadata headers
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MissingMetadata {
+    /// The request is allowed
+    Allow,
+    /// The request is denied with [DenialReason::MissingMetadata](crate::DenialReason::MissingMetadata)
+    Deny,
+    /// The request is allowed if it has no `origin` header, or if it matches the request host or the
+    /// [external_origins](crate::PolicyBuilder::external_origins), and denied with
+    /// [DenialReason::OriginMismatch](crate::DenialReason::OriginMismatch) otherwise
+    ///
+    /// A request to `https://host` with the `upgrade-insecure-requests` header from `http://host`,
+    /// both on their default port, is also allowed as an upgrade of a page served over HTTP.
+    OriginFallback,
+}
+
+/// Decides the outcome of requests without Fetch Metadata headers by their [UserAgent],
+/// see [missing_metadata_by_user_agent](crate::PolicyBuilder::missing_metadata_by_user_agent)
+///
+/// By default, browsers fall back to the `origin` header, and non-browsers are allowed.
+///
+/// ```
+/// use tower_sec_fetch::{MissingMetadata, SecFetchLayer, UserAgentRules};
+///
+/// SecFetchLayer::new(|policy| {
+///     policy.missing_metadata_by_user_agent(
+///         UserAgentRules::default()
+///             .browsers(MissingMetadata::Deny)
+///             .non_browsers(MissingMetadata::Allow),
+///     );
+/// });
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UserAgentRules {
+    browsers: MissingMetadata,
+    legacy_browsers: MissingMetadata,
+    non_browsers: MissingMetadata,
+}
+
+impl Default for UserAgentRules {
+    fn default() -> Self {
+        Self {
+            browsers: MissingMetadata::OriginFallback,
+            legacy_browsers: MissingMetadata::OriginFallback,
+            non_browsers: MissingMetadata::Allow,
+        }
+    }
+}
+
+impl UserAgentRules {
+    /// The outcome for [UserAgent::Browser], defaults to [MissingMetadata::OriginFallback]
+    pub fn browsers(mut self, outcome: MissingMetadata) -> Self {
+        self.browsers = outcome;
+        self
+    }
+
+    /// The outcome for [UserAgent::LegacyBrowser], defaults to [MissingMetadata::OriginFallback]
+    pub fn legacy_browsers(mut self, outcome: MissingMetadata) -> Self {
+        self.legacy_browsers = outcome;
+        self
+    }
+
+    /// The outcome for [UserAgent::NonBrowser], defaults to [MissingMetadata::Allow]
+    pub fn non_browsers(mut self, outcome: MissingMetadata) -> Self {
+        self.non_browsers = outcome;
+        self
+    }
+
+    pub(crate) fn outcome(&self, user_agent: UserAgent) -> MissingMetadata {
+        match user_agent {
+            UserAgent::Browser => self.browsers,
+            UserAgent::LegacyBrowser => self.legacy_browsers,
+            UserAgent::NonBrowser => self.non_browsers,
+        }
+    }
+}
+
+/// Parses the major and minor version following `product` in `user_agent`
+fn product_version(user_agent: &str, product: &str) -> Option<(u32, u32)> {
+    let (_, version) = user_agent.split_once(product)?;
+    let version = version.split(' ').next()?;
+    let mut numbers = version.split('.');
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers
+        .next()
+        .and_then(|minor| minor.parse().ok())
+        .unwrap_or(0);
+
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+
+    #[test]
+    fn it_classifies_user_agents() {
+        for (user_agent, expected) in [
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36",
+                UserAgent::Browser,
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/70.0.3538.102 Safari/537.36 Edge/18.19582",
+                UserAgent::LegacyBrowser,
+            ),
+            (
+                "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+                UserAgent::Browser,
+            ),
+            (
+                "Mozilla/5.0 (X11; Linux x86_64; rv:78.0) Gecko/20100101 Firefox/78.0",
+                UserAgent::LegacyBrowser,
+            ),
+            (
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15",
+                UserAgent::Browser,
+            ),
+            (
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 16_3 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.3 Mobile/15E148 Safari/604.1",
+                UserAgent::LegacyBrowser,
+            ),
+            (
+                "Mozilla/5.0 (compatible; MSIE 10.0; Windows NT 6.1; Trident/6.0)",
+                UserAgent::LegacyBrowser,
+            ),
+            ("curl/8.5.0", UserAgent::NonBrowser),
+            ("okhttp/4.12.0", UserAgent::NonBrowser),
+        ] {
+            let header = HeaderValue::from_static(user_agent);
+
+            check!(
+                UserAgent::classify(Some(&header)) == expected,
+                "{user_agent}"
+            );
+        }
+
+        check!(UserAgent::classify(None) == UserAgent::NonBrowser);
+    }
+}
@@ -0,0 +1,244 @@
+//! JavaScript bindings to the evaluation policy, for edge functions and test harnesses simulating requests
+//! with the same implementation as the middleware
+//!
+//! Build them with `wasm-pack build --target nodejs -- --features wasm`, and import them:
+//!
+//! ```js
+//! const { Policy } = require("tower-sec-fetch");
+//!
+//! const policy = new Policy(false, true, false, false);
+//! const verdict = policy.simulate(
+//!   "POST",
+//!   "/submit",
+//!   ["sec-fetch-site", "sec-fetch-mode", "sec-fetch-dest"],
+//!   ["cross-site", "cors", "empty"],
+//! );
+//! console.log(verdict.verdict, verdict.reason, verdict.explanation);
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{SafeMethodsOrder, SecFetchLayer, Verdict};
+
+/// An evaluation policy, configured with the same flags as the [ffi](crate::ffi) interface or with a preset
+#[wasm_bindgen(js_name = Policy)]
+pub struct WasmPolicy {
+    layer: SecFetchLayer,
+}
+
+#[wasm_bindgen(js_class = Policy)]
+impl WasmPolicy {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        reject_missing_metadata: bool,
+        allow_safe_methods: bool,
+        safe_methods_after_report: bool,
+        go_compat: bool,
+    ) -> Self {
+        let layer = SecFetchLayer::new(|policy| {
+            if reject_missing_metadata {
+                policy.reject_missing_metadata();
+            }
+            if allow_safe_methods {
+                policy.allow_safe_methods();
+            }
+            if safe_methods_after_report {
+                policy.safe_methods_order(SafeMethodsOrder::AfterReport);
+            }
+            if go_compat {
+                policy.go_compat();
+            }
+        });
+
+        Self { layer }
+    }
+
+    /// The [website](crate::Policy::website) preset
+    pub fn website() -> Self {
+        Self::preset(crate::Policy::website())
+    }
+
+    /// The [api](crate::Policy::api) preset
+    pub fn api() -> Self {
+        Self::preset(crate::Policy::api())
+    }
+
+    /// The [strict](crate::Policy::strict) preset
+    pub fn strict() -> Self {
+        Self::preset(crate::Policy::strict())
+    }
+
+    /// The [public](crate::Policy::public) preset
+    pub fn public() -> Self {
+        Self::preset(crate::Policy::public())
+    }
+
+    /// The [graphql](crate::Policy::graphql) preset
+    pub fn graphql() -> Self {
+        Self::preset(crate::Policy::graphql())
+    }
+
+    /// Evaluates a request described by its method, path, and headers, see [SecFetchLayer::simulate]
+    ///
+    /// The headers are given as two arrays of the same length, so that a header can be repeated.
+    pub fn simulate(
+        &self,
+        method: &str,
+        path: &str,
+        names: Vec<String>,
+        values: Vec<String>,
+    ) -> Result<WasmVerdict, JsError> {
+        self.verdict(method, path, &names, &values)
+            .map(WasmVerdict)
+            .map_err(|err| JsError::new(&err))
+    }
+
+    /// The verdicts for every combination of Fetch Metadata headers and method, one per line,
+    /// see [SecFetchLayer::decision_table]
+    #[wasm_bindgen(js_name = decisionTable)]
+    pub fn decision_table(&self) -> String {
+        self.layer.decision_table().to_string()
+    }
+
+    /// Describes the steps executed for every request, see [SecFetchLayer::pipeline]
+    pub fn pipeline(&self) -> String {
+        self.layer.pipeline().to_string()
+    }
+}
+
+impl WasmPolicy {
+    fn preset(policy: crate::Policy) -> Self {
+        Self {
+            layer: SecFetchLayer::default().with_policy(policy),
+        }
+    }
+
+    fn verdict(
+        &self,
+        method: &str,
+        path: &str,
+        names: &[String],
+        values: &[String],
+    ) -> Result<Verdict, String> {
+        if names.len() != values.len() {
+            return Err("header names and values must have the same length".into());
+        }
+
+        let headers: Vec<_> = names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+
+        self.layer
+            .simulate(method, path, &headers)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// The [Verdict] of a simulated request
+#[wasm_bindgen(js_name = Verdict)]
+pub struct WasmVerdict(Verdict);
+
+#[wasm_bindgen(js_class = Verdict)]
+impl WasmVerdict {
+    /// `allowed`, `reported`, or `denied`, see [Verdict::as_str]
+    #[wasm_bindgen(getter)]
+    pub fn verdict(&self) -> String {
+        self.0.as_str().to_string()
+    }
+
+    /// Whether the request reaches the inner service, see [Verdict::is_allowed]
+    #[wasm_bindgen(getter)]
+    pub fn allowed(&self) -> bool {
+        self.0.is_allowed()
+    }
+
+    /// The identifier of the denial reason, or `undefined` if the request is allowed
+    #[wasm_bindgen(getter)]
+    pub fn reason(&self) -> Option<String> {
+        self.0.reason().map(|reason| reason.as_str().to_string())
+    }
+
+    /// Why the request was denied, in plain English, or `undefined` if the request is allowed
+    #[wasm_bindgen(getter)]
+    pub fn explanation(&self) -> Option<String> {
+        self.0.reason().map(|reason| reason.to_string())
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert2::check;
+
+    use super::*;
+    use crate::DenialReason;
+
+    fn headers(headers: &[(&str, &str)]) -> (Vec<String>, Vec<String>) {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .unzip()
+    }
+
+    #[test]
+    fn it_simulates_requests() {
+        let (names, values) = headers(&[
+            ("sec-fetch-site", "cross-site"),
+            ("sec-fetch-mode", "cors"),
+            ("sec-fetch-dest", "empty"),
+        ]);
+
+        let denied = WasmPolicy::new(false, false, false, false)
+            .simulate("POST", "/submit", names.clone(), values.clone())
+            .unwrap();
+        check!(denied.verdict() == "denied");
+        check!(!denied.allowed());
+        check!(denied.reason().as_deref() == Some("cross-site"));
+        check!(denied.explanation() == Some(DenialReason::CrossSite.to_string()));
+
+        let reported = WasmPolicy::new(false, true, true, false)
+            .simulate("GET", "/", names, values)
+            .unwrap();
+        check!(reported.verdict() == "reported");
+        check!(reported.allowed());
+        check!(reported.to_js_string() == "reported (cross-site)");
+
+        let (names, values) = headers(&[("sec-fetch-site", "same-origin")]);
+        let allowed = WasmPolicy::website()
+            .simulate("POST", "/", names, values)
+            .unwrap();
+        check!(allowed.verdict() == "allowed");
+        check!(allowed.reason() == None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_requests() {
+        let policy = WasmPolicy::api();
+
+        check!(policy.verdict("NOT A METHOD", "/", &[], &[]).is_err());
+        check!(
+            policy
+                .verdict("POST", "/", &["sec-fetch-site".into()], &[])
+                .is_err()
+        );
+        check!(
+            policy.verdict("POST", "/", &[], &[])
+                == Ok(Verdict::Denied(DenialReason::MissingMetadata))
+        );
+    }
+
+    #[test]
+    fn it_describes_the_policy() {
+        let layer = SecFetchLayer::default().with_policy(crate::Policy::strict());
+        let policy = WasmPolicy::strict();
+
+        check!(policy.pipeline() == layer.pipeline().to_string());
+        check!(policy.decision_table() == layer.decision_table().to_string());
+    }
+}